@@ -0,0 +1,42 @@
+// Panic button for runaway agents
+// Stops OpenCode and Pi together; one side failing never blocks the other
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::opencode::client::SessionAbortResult;
+use crate::opencode::OpenCodeClient;
+use crate::pi::PiManager;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmergencyStopReport {
+    pub opencode_sessions: Vec<SessionAbortResult>,
+    /// Set when OpenCode sessions couldn't be enumerated at all.
+    pub opencode_error: Option<String>,
+    pub pi_sessions: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn emergency_stop(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    manager: State<'_, PiManager>,
+) -> Result<EmergencyStopReport, String> {
+    let (opencode, pi_sessions) = tokio::join!(client.abort_all(), manager.shutdown());
+    let (opencode_sessions, opencode_error) = match opencode {
+        Ok(results) => (results, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let report = EmergencyStopReport {
+        opencode_sessions,
+        opencode_error,
+        pi_sessions,
+    };
+    tracing::warn!(
+        opencode = report.opencode_sessions.len(),
+        pi = report.pi_sessions.len(),
+        "emergency stop"
+    );
+    let _ = app.emit("system://emergency-stop", &report);
+    Ok(report)
+}
@@ -6,6 +6,7 @@ mod backend;
 mod codex;
 mod files;
 mod dictation;
+mod emergency;
 mod event_sink;
 mod git;
 mod git_utils;
@@ -56,10 +57,17 @@ pub fn run() {
             
             // Initialize OpenCode client
             let opencode_client = opencode::OpenCodeClient::new("http://localhost:4096");
+            if let Ok(data_dir) = app.path().app_data_dir() {
+                opencode_client.load_tags(data_dir.join("opencode-tags.json"));
+                opencode_client.load_pins(data_dir.join("opencode-pins.json"));
+            }
             app.manage(opencode_client);
             
             // Initialize Pi manager
             let pi_manager = pi::PiManager::new();
+            pi::commands::forward_lines(app.handle().clone(), pi_manager.subscribe_lines());
+            pi::commands::forward_states(app.handle().clone(), pi_manager.subscribe_states());
+            pi::commands::forward_approvals(app.handle().clone(), pi_manager.subscribe_approvals());
             app.manage(pi_manager);
             
             #[cfg(desktop)]
@@ -152,10 +160,17 @@ pub fn run() {
             prompts::prompts_list,
             prompts::prompts_create,
             prompts::prompts_update,
+            prompts::prompts_get_raw,
+            prompts::prompts_update_raw,
+            prompts::prompts_duplicate,
             prompts::prompts_delete,
             prompts::prompts_move,
+            prompts::prompts_validate,
+            prompts::prompts_export_bundle,
+            prompts::prompts_import_bundle,
             prompts::prompts_workspace_dir,
             prompts::prompts_global_dir,
+            prompts::prompts_storage_info,
             terminal::terminal_open,
             terminal::terminal_write,
             terminal::terminal_resize,
@@ -169,26 +184,116 @@ pub fn run() {
             dictation::dictation_stop,
             dictation::dictation_cancel,
             local_usage::local_usage_snapshot,
+            emergency::emergency_stop,
             // OpenCode commands
             opencode::commands::opencode_health,
+            opencode::commands::opencode_deep_health,
+            opencode::commands::opencode_wait_healthy,
+            opencode::commands::opencode_server_features,
             opencode::commands::opencode_list_sessions,
+            opencode::commands::opencode_list_sessions_sorted,
+            opencode::commands::opencode_search_sessions,
+            opencode::commands::opencode_pin_session,
+            opencode::commands::opencode_unpin_session,
+            opencode::commands::opencode_list_pinned_sessions,
+            opencode::commands::opencode_list_sessions_by_tag,
+            opencode::commands::opencode_sessions_in_range,
+            opencode::commands::opencode_add_tag,
+            opencode::commands::opencode_remove_tag,
+            opencode::commands::opencode_session_summary,
+            opencode::commands::opencode_get_client_config,
+            opencode::commands::opencode_reconfigure,
+            opencode::commands::opencode_set_log_bodies,
+            opencode::commands::opencode_list_models,
+            opencode::commands::opencode_server_config,
             opencode::commands::opencode_create_session,
+            opencode::commands::opencode_set_title_template,
+            opencode::commands::opencode_refresh_session,
+            opencode::commands::opencode_fork_session,
             opencode::commands::opencode_send_message,
+            opencode::commands::opencode_send_message_blocking,
             opencode::commands::opencode_get_messages,
+            opencode::commands::opencode_active_model,
+            opencode::commands::opencode_message_count,
+            opencode::commands::opencode_get_message_parts,
+            opencode::commands::opencode_total_usage,
+            opencode::commands::opencode_prefetch_previews,
+            opencode::commands::opencode_session_preview,
+            opencode::commands::opencode_active_generation,
             opencode::commands::opencode_get_diffs,
+            opencode::commands::opencode_get_file_diff,
+            opencode::commands::opencode_changed_files,
+            opencode::commands::opencode_per_file_stats,
+            opencode::commands::opencode_preview_message,
             opencode::commands::opencode_abort_session,
             opencode::commands::opencode_delete_session,
+            opencode::commands::opencode_delete_sessions,
             opencode::commands::opencode_search_files,
+            opencode::commands::opencode_search,
             opencode::commands::opencode_read_file,
+            opencode::commands::opencode_preview_edit,
+            opencode::commands::opencode_stream_file,
+            opencode::commands::opencode_clear_file_cache,
+            opencode::commands::opencode_set_file_cache_limit,
+            opencode::commands::opencode_snapshot_file,
+            opencode::commands::opencode_diff_snapshots,
             opencode::commands::opencode_list_files,
+            opencode::commands::opencode_get_agent,
+            opencode::commands::opencode_set_default_agent,
+            opencode::commands::opencode_set_workspace_root,
+            opencode::commands::opencode_is_within_workspace,
+            opencode::commands::opencode_workspace_status,
+            opencode::commands::opencode_subscribe_events,
+            opencode::commands::opencode_unsubscribe_events,
+            opencode::commands::opencode_recent_events,
+            opencode::commands::opencode_set_event_log_capacity,
+            opencode::commands::opencode_watch_diffs,
+            opencode::commands::opencode_poll_diffs,
+            opencode::commands::opencode_unwatch_diffs,
+            opencode::commands::opencode_replay_session,
+            opencode::commands::opencode_stop_replay,
+            opencode::commands::opencode_set_auto_abort,
+            opencode::commands::opencode_set_auto_abort_threshold,
+            opencode::commands::opencode_set_health_monitor,
+            opencode::commands::opencode_pause_health_monitor,
+            opencode::commands::opencode_resume_health_monitor,
+            opencode::commands::opencode_set_log_path,
+            opencode::commands::opencode_server_logs,
             // Pi commands
             pi::commands::pi_list_models,
+            pi::commands::pi_list_models_detailed,
             pi::commands::pi_get_config,
+            pi::commands::pi_config_diff,
+            pi::commands::pi_reset_config_field,
             pi::commands::pi_update_config,
+            pi::commands::pi_set_model_alias,
+            pi::commands::pi_set_completion_marker,
+            pi::commands::pi_set_launcher,
+            pi::commands::pi_set_no_output_timeout,
+            pi::commands::pi_load_system_prompt,
             pi::commands::pi_run_session,
+            pi::commands::pi_run_prompt,
+            pi::commands::pi_get_prompt_name,
+            pi::commands::pi_awaiting_input,
             pi::commands::pi_wait_session,
             pi::commands::pi_kill_session,
-            pi::commands::pi_get_output
+            pi::commands::pi_kill_by_workdir,
+            pi::commands::pi_restart_session,
+            pi::commands::pi_kill_pid,
+            pi::commands::pi_get_output,
+            pi::commands::pi_get_combined_output,
+            pi::commands::pi_get_structured_events,
+            pi::commands::pi_tail_all,
+            pi::commands::pi_get_summary,
+            pi::commands::pi_last_error,
+            pi::commands::pi_session_status,
+            pi::commands::pi_effective_env,
+            pi::commands::pi_set_log_dir,
+            pi::commands::pi_log_path,
+            pi::commands::pi_set_approval,
+            pi::commands::pi_respond_approval,
+            pi::commands::pi_queue_status,
+            pi::commands::pi_queue_reorder
         ])
         .build(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,82 @@
+// Active/idle classification of sessions for the dashboard summary
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::opencode::client::Session;
+
+pub const DEFAULT_ACTIVE_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionActivity {
+    /// Updated within the window, or generating right now.
+    pub active: usize,
+    pub idle: usize,
+    /// Subset of `active` with a reply in progress.
+    pub generating: usize,
+}
+
+/// Sessions without a parseable `updated_at` count as idle unless they're
+/// generating.
+pub fn summarize_activity(
+    sessions: &[Session],
+    generating: &HashSet<String>,
+    window: Duration,
+    now: DateTime<Utc>,
+) -> SessionActivity {
+    let cutoff = chrono::Duration::from_std(window)
+        .ok()
+        .and_then(|window| now.checked_sub_signed(window));
+    let mut summary = SessionActivity::default();
+    for session in sessions {
+        let busy = generating.contains(&session.id);
+        let recent = match (session.updated_at.as_datetime(), cutoff) {
+            (Some(updated), Some(cutoff)) => updated >= cutoff,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if busy {
+            summary.generating += 1;
+        }
+        if busy || recent {
+            summary.active += 1;
+        } else {
+            summary.idle += 1;
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opencode::timestamp::Timestamp;
+
+    fn session(id: &str, updated_at: &str) -> Session {
+        Session {
+            id: id.to_string(),
+            updated_at: Timestamp::parse(updated_at),
+            ..Session::default()
+        }
+    }
+
+    #[test]
+    fn classifies_by_recency_and_generation() {
+        let now: DateTime<Utc> = "2024-06-01T12:00:00Z".parse().unwrap();
+        let sessions = vec![
+            session("recent", "2024-06-01T11:55:00Z"),
+            session("old", "2024-06-01T09:00:00Z"),
+            session("old-busy", "2024-06-01T09:00:00Z"),
+            session("unknown", "not a date"),
+        ];
+        let generating: HashSet<String> = ["old-busy".to_string()].into();
+
+        let summary = summarize_activity(&sessions, &generating, DEFAULT_ACTIVE_WINDOW, now);
+        assert_eq!(summary, SessionActivity { active: 2, idle: 2, generating: 1 });
+
+        let summary = summarize_activity(&sessions, &generating, Duration::from_secs(4 * 3600), now);
+        assert_eq!(summary.active, 3);
+    }
+}
@@ -0,0 +1,124 @@
+// Agent details from `/agent`
+// Servers differ in shape: tools come as a list of names or a map of name
+// to enabled, and the model as a string or `{ providerID, modelID }`
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentDetail {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub model: Option<String>,
+    /// `primary`, `subagent`, ... when the server says.
+    pub mode: Option<String>,
+    /// Enabled tools, sorted.
+    pub tools: Vec<String>,
+    /// What the agent may do beyond its tools, from its permissions
+    /// (`edit`, `bash`, ...); denied ones are left out.
+    pub capabilities: Vec<String>,
+}
+
+impl AgentDetail {
+    /// `None` for entries with neither an id nor a name.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let id = text("id").or_else(|| text("name"))?;
+        let name = text("name").unwrap_or_else(|| id.clone());
+        Some(Self {
+            id,
+            name,
+            description: text("description"),
+            model: value.get("model").and_then(model_name),
+            mode: text("mode"),
+            tools: value.get("tools").map(enabled_names).unwrap_or_default(),
+            capabilities: capabilities(value),
+        })
+    }
+
+    pub fn matches(&self, agent: &str) -> bool {
+        self.id == agent || self.name == agent
+    }
+}
+
+/// `provider/model` from `{ providerID, modelID }`, or a plain model string.
+pub fn model_name(model: &Value) -> Option<String> {
+    if let Some(model) = model.as_str() {
+        return Some(model.to_string());
+    }
+    let model_id = model.get("modelID").and_then(Value::as_str)?;
+    match model.get("providerID").and_then(Value::as_str) {
+        Some(provider) => Some(format!("{}/{}", provider, model_id)),
+        None => Some(model_id.to_string()),
+    }
+}
+
+// `["read", "bash"]`, `[{ "name": "read" }]` or `{ "read": true, "bash": false }`
+fn enabled_names(tools: &Value) -> Vec<String> {
+    let mut names: Vec<String> = match tools {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.as_str().or_else(|| item.get("name").and_then(Value::as_str)))
+            .map(str::to_string)
+            .collect(),
+        Value::Object(map) => map
+            .iter()
+            .filter(|(_, enabled)| enabled.as_bool().unwrap_or(true))
+            .map(|(name, _)| name.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn capabilities(value: &Value) -> Vec<String> {
+    let mut capabilities = value.get("capabilities").map(enabled_names).unwrap_or_default();
+    if let Some(Value::Object(permissions)) = value.get("permission").or_else(|| value.get("permissions")) {
+        for (name, level) in permissions {
+            if level.as_str() != Some("deny") && !capabilities.contains(name) {
+                capabilities.push(name.clone());
+            }
+        }
+    }
+    capabilities.sort();
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_tool_maps_and_permissions() {
+        let agent = AgentDetail::from_value(&json!({
+            "name": "plan",
+            "mode": "primary",
+            "model": { "providerID": "anthropic", "modelID": "claude-sonnet-4" },
+            "tools": { "write": false, "read": true, "grep": true },
+            "permission": { "edit": "deny", "bash": "ask", "webfetch": "allow" }
+        }))
+        .unwrap();
+        assert_eq!(agent.id, "plan");
+        assert_eq!(agent.model.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(agent.tools, vec!["grep", "read"]);
+        assert_eq!(agent.capabilities, vec!["bash", "webfetch"]);
+    }
+
+    #[test]
+    fn parses_tool_lists() {
+        let agent = AgentDetail::from_value(&json!({
+            "id": "build",
+            "name": "Build",
+            "model": "gpt-5",
+            "tools": ["bash", { "name": "edit" }, 3]
+        }))
+        .unwrap();
+        assert_eq!(agent.tools, vec!["bash", "edit"]);
+        assert!(agent.capabilities.is_empty());
+        assert!(AgentDetail::from_value(&json!({ "tools": [] })).is_none());
+    }
+}
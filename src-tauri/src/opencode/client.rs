@@ -3,27 +3,388 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use reqwest::{Client, Error};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use reqwest::header::{HeaderValue, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::opencode::activity::{summarize_activity, SessionActivity};
+use crate::opencode::agents::{model_name, AgentDetail};
+use crate::opencode::config::ClientConfig;
+use crate::opencode::diff::{language_for_path, unified_diff, FileStats};
+use crate::opencode::error::{envelope_message, OpenCodeError};
+use crate::opencode::events::{EventLog, ServerEvent, SseState};
+use crate::opencode::files::{FileCache, FileRange, FileStream};
+use crate::opencode::health::{probe, DeepHealth, HealthMonitor, DEEP_HEALTH_TIMEOUT};
+use crate::opencode::idle::AutoAbort;
+use crate::opencode::logs::{default_log_dir, latest_log_file, tail_lines, LogLine};
+use crate::opencode::paths;
+use crate::opencode::pins::PinStore;
+use crate::opencode::request::{Http, Request};
+use crate::opencode::search::{parse_matches, SearchMatch};
+use crate::opencode::snapshots::SnapshotStore;
+use crate::opencode::tags::TagStore;
+use crate::opencode::timestamp::Timestamp;
+use crate::opencode::titles::{TitleTemplate, DEFAULT_TITLE_TEMPLATE};
+use crate::opencode::transport::Transport;
+use crate::opencode::usage::{TotalUsage, Usage};
+use crate::opencode::version::{parse_version, supports, ServerFeature};
+use crate::opencode::workspace::{shell_output, WorkspaceStatus, GIT_STATUS_COMMAND};
+
+const REPLY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DELETE_CONCURRENCY: usize = 4;
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+// Until the server sends its own `retry:`
+const DEFAULT_EVENT_RETRY: Duration = Duration::from_secs(3);
+// Backoff between health checks while waiting for the server to come up
+const HEALTH_WAIT_INITIAL: Duration = Duration::from_millis(250);
+const HEALTH_WAIT_MAX: Duration = Duration::from_secs(2);
+// OpenCode's own default agent
+const DEFAULT_SHELL_AGENT: &str = "build";
+// Long enough to cover one render of the session list
+const MESSAGE_COUNT_TTL: Duration = Duration::from_secs(5);
+const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+// Summing usage reads every session's messages, so it's cached and bounded
+const TOTAL_USAGE_TTL: Duration = Duration::from_secs(30);
+const USAGE_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
+    #[serde(default)]
     pub title: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    #[serde(default)]
+    pub created_at: Timestamp,
+    #[serde(default)]
+    pub updated_at: Timestamp,
+    #[serde(default)]
     pub model: Option<String>,
+    #[serde(default)]
     pub agent: Option<String>,
+    /// Tags from the server, if it has any, merged with the monitor's own.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Pinned in this monitor; the server never sends it.
+    #[serde(default)]
+    pub is_pinned: bool,
+    /// Fields this client doesn't know about yet, kept for forward compat.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionSort {
+    #[default]
+    Updated,
+    Created,
+    Title,
+}
+
+impl SessionSort {
+    /// Times sort newest first and titles A-Z unless `descending` says
+    /// otherwise. Titles compare case-insensitively; ties fall back to id so
+    /// the order is stable across fetches.
+    pub fn sort(self, sessions: &mut [Session], descending: Option<bool>) {
+        let descending = descending.unwrap_or(self != SessionSort::Title);
+        sessions.sort_by(|a, b| {
+            let ordering = match self {
+                SessionSort::Updated => a.updated_at.cmp(&b.updated_at),
+                SessionSort::Created => a.created_at.cmp(&b.created_at),
+                SessionSort::Title => {
+                    let title = |session: &Session| session.title.as_deref().unwrap_or_default().to_lowercase();
+                    title(a).cmp(&title(b))
+                }
+            }
+            .then_with(|| a.id.cmp(&b.id));
+            if descending { ordering.reverse() } else { ordering }
+        });
+    }
+}
+
+/// `/session` is a bare array on most servers, but some wrap it.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SessionList {
+    Bare(Vec<Session>),
+    Wrapped { sessions: Vec<Session> },
+}
+
+impl From<SessionList> for Vec<Session> {
+    fn from(list: SessionList) -> Self {
+        match list {
+            SessionList::Bare(sessions) | SessionList::Wrapped { sessions } => sessions,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "MessageWire")]
 pub struct Message {
     pub id: String,
     pub role: String,
     pub content: String,
     pub parts: Vec<MessagePart>,
-    pub created_at: String,
+    pub created_at: Timestamp,
+    /// "completed", "aborted", "error", ... as reported by the server,
+    /// lowercased. `None` while generating or when the server doesn't say.
+    pub status: Option<String>,
+}
+
+// `Message` as servers send it, where `content` may be a list of blocks
+#[derive(Deserialize)]
+struct MessageWire {
+    id: String,
+    role: String,
+    content: MessageContent,
+    #[serde(default)]
+    parts: Vec<MessagePart>,
+    #[serde(default)]
+    created_at: Timestamp,
+    #[serde(default, deserialize_with = "lenient_status")]
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MessageContent {
+    Text(String),
+    Blocks(Vec<serde_json::Value>),
+}
+
+impl From<MessageWire> for Message {
+    fn from(wire: MessageWire) -> Self {
+        let MessageWire { id, role, content, mut parts, created_at, status } = wire;
+        let content = match content {
+            MessageContent::Text(text) => text,
+            MessageContent::Blocks(blocks) => {
+                let mut text = String::new();
+                for block in blocks {
+                    match block_text(&block) {
+                        Some(block_text) => text.push_str(block_text),
+                        None => parts.push(MessagePart::from_block(block)),
+                    }
+                }
+                text
+            }
+        };
+        Self { id, role, content, parts, created_at, status }
+    }
+}
+
+// The text of a `{ "type": "text", "text": ... }` block (or a bare string)
+fn block_text(block: &serde_json::Value) -> Option<&str> {
+    if let Some(text) = block.as_str() {
+        return Some(text);
+    }
+    let kind = block.get("type").and_then(serde_json::Value::as_str).unwrap_or("text");
+    match kind {
+        "text" => block.get("text").and_then(serde_json::Value::as_str),
+        _ => None,
+    }
+}
+
+impl Message {
+    pub fn is_aborted(&self) -> bool {
+        matches!(self.status.as_deref(), Some("aborted" | "cancelled" | "canceled"))
+    }
+
+    /// Whether the server reports the message as still being written.
+    pub fn is_in_progress(&self) -> bool {
+        matches!(
+            self.status.as_deref(),
+            Some("pending" | "running" | "streaming" | "generating" | "in_progress")
+        )
+    }
+}
+
+// Accepts a bare string or an object like `{ "type": "aborted" }`; anything
+// else reads as no status rather than failing the whole message
+fn lenient_status<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let status = match &value {
+        serde_json::Value::Object(fields) => fields.get("type").or_else(|| fields.get("status")),
+        _ => Some(&value),
+    };
+    Ok(status
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|status| !status.is_empty())
+        .map(str::to_ascii_lowercase))
+}
+
+/// A page of messages plus the cursor to pass as `after` on the next poll.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    pub latest_id: Option<String>,
+    /// Set when the requested limit was over the configured maximum.
+    #[serde(default)]
+    pub clamped: bool,
+}
+
+impl MessagePage {
+    pub fn new(messages: Vec<Message>, after: Option<&str>) -> Self {
+        let latest_id = messages
+            .last()
+            .map(|message| message.id.clone())
+            .or_else(|| after.map(str::to_string));
+        Self { messages, latest_id, clamped: false }
+    }
+}
+
+/// Answer to `is_within_workspace`. `within` is `None` when the workspace
+/// root isn't known, so there's nothing to compare against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceCheck {
+    pub root: Option<String>,
+    pub path: String,
+    pub within: Option<bool>,
+}
+
+fn parse_range_bound(raw: &str) -> Result<chrono::DateTime<chrono::Utc>, OpenCodeError> {
+    chrono::DateTime::parse_from_rfc3339(raw.trim())
+        .map(|parsed| parsed.with_timezone(&chrono::Utc))
+        .map_err(|_| OpenCodeError::InvalidConfig(format!("invalid RFC3339 time {:?}", raw)))
+}
+
+// Sessions whose creation time couldn't be parsed are left out
+fn created_between(
+    mut sessions: Vec<Session>,
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+) -> Vec<Session> {
+    sessions.retain(|session| match session.created_at.as_datetime() {
+        Some(created) => from.is_none_or(|from| created >= from) && to.is_none_or(|to| created <= to),
+        None => false,
+    });
+    SessionSort::Created.sort(&mut sessions, Some(false));
+    sessions
+}
+
+// Assistant messages carry flat `providerID`/`modelID`; user messages a
+// `model` object or string. Either may sit under `info`
+fn message_model(message: &serde_json::Value) -> Option<String> {
+    let info = message.get("info").unwrap_or(message);
+    info.get("model").and_then(model_name).or_else(|| model_name(info))
+}
+
+// `/path` on newer servers; older ones put the directory in `/config`
+const WORKSPACE_ROOT_FIELDS: [&str; 3] = ["worktree", "directory", "root"];
+
+fn workspace_root_field(value: &serde_json::Value) -> Option<String> {
+    WORKSPACE_ROOT_FIELDS
+        .iter()
+        .find_map(|field| value.get(*field).and_then(|v| v.as_str()))
+        .filter(|root| !root.is_empty())
+        .map(str::to_string)
+}
+
+/// One page of a message's parts. `total` counts every part of the
+/// message; `next_offset` is `None` on the last page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagePartsPage {
+    pub parts: Vec<MessagePart>,
+    pub total: usize,
+    pub next_offset: Option<usize>,
+}
+
+impl MessagePartsPage {
+    fn new(parts: Vec<MessagePart>, offset: usize, total: usize) -> Self {
+        let end = offset + parts.len();
+        let next_offset = (end < total).then_some(end);
+        Self { parts, total, next_offset }
+    }
+
+    // For servers that send every part at once
+    fn slice(parts: Vec<MessagePart>, offset: usize, limit: usize) -> Self {
+        let total = parts.len();
+        let parts = parts.into_iter().skip(offset).take(limit).collect();
+        Self::new(parts, offset, total)
+    }
+}
+
+/// One page of `search_sessions`. `next_offset` is `None` on the last page;
+/// `server_side` is false when the server had no search endpoint and the
+/// session list was filtered here instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSearchPage {
+    pub sessions: Vec<Session>,
+    pub next_offset: Option<usize>,
+    pub server_side: bool,
+}
+
+impl SessionSearchPage {
+    fn new(sessions: Vec<Session>, offset: usize, limit: usize, server_side: bool) -> Self {
+        let next_offset = (sessions.len() >= limit).then_some(offset + sessions.len());
+        Self { sessions, next_offset, server_side }
+    }
+}
+
+/// Case-insensitive match on the title or id, for servers without search.
+fn session_matches(session: &Session, query: &str) -> bool {
+    let query = query.to_lowercase();
+    let title = session.title.as_deref().unwrap_or_default().to_lowercase();
+    title.contains(&query) || session.id.to_lowercase().contains(&query)
+}
+
+/// Reads the answer to an abort or delete: a bare bool, an object like
+/// `{ "success": true }` or `{ "ok": true }`, or an empty body (as with
+/// `204 No Content`), which counts as success.
+fn parse_acknowledgement(body: &str) -> Result<bool, OpenCodeError> {
+    if body.trim().is_empty() {
+        return Ok(true);
+    }
+    let value: serde_json::Value = serde_json::from_str(body)
+        .map_err(|_| OpenCodeError::Server(format!("unexpected response: {}", body.trim())))?;
+    if let Some(error) = value.get("error") {
+        return Err(OpenCodeError::Server(envelope_message(error)));
+    }
+    let acknowledged = value
+        .as_bool()
+        .or_else(|| ["success", "ok"].iter().find_map(|key| value.get(*key)?.as_bool()));
+    acknowledged.ok_or_else(|| OpenCodeError::Server(format!("unexpected response: {}", value)))
+}
+
+/// Reads a `/message` response, which may be an array, a single message or
+/// an `{ "error": ... }` envelope.
+fn parse_message_response(status: StatusCode, body: &str) -> Result<Vec<Message>, OpenCodeError> {
+    let value: serde_json::Value = serde_json::from_str(body).map_err(|_| {
+        let snippet: String = body.chars().take(200).collect();
+        OpenCodeError::Server(format!("{} with a non-JSON body: {}", status, snippet.trim()))
+    })?;
+    if let Some(error) = value.get("error") {
+        return Err(OpenCodeError::Server(envelope_message(error)));
+    }
+    if !status.is_success() {
+        return Err(OpenCodeError::Server(format!("{}: {}", status, value)));
+    }
+    let messages = if value.is_array() {
+        serde_json::from_value::<Vec<Message>>(value)
+    } else {
+        serde_json::from_value::<Message>(value).map(|message| vec![message])
+    };
+    messages.map_err(|e| OpenCodeError::Server(format!("unexpected message shape: {}", e)))
+}
+
+/// Drops everything up to and including `after`. When `after` isn't in the
+/// list, the server already filtered (or the cursor is older than the page),
+/// so every message is new.
+fn messages_after(messages: Vec<Message>, after: Option<&str>) -> Vec<Message> {
+    let Some(after) = after else {
+        return messages;
+    };
+    match messages.iter().position(|message| message.id == after) {
+        Some(index) => messages.into_iter().skip(index + 1).collect(),
+        None => messages,
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,12 +394,57 @@ pub struct MessagePart {
     pub source: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl MessagePart {
+    /// A non-text content block, kept whole as JSON when it has no `text`
+    /// or `content` of its own.
+    fn from_block(block: serde_json::Value) -> Self {
+        let field = |key: &str| block.get(key).and_then(serde_json::Value::as_str).map(str::to_string);
+        let kind = field("type").unwrap_or_else(|| "unknown".to_string());
+        let source = field("source").or_else(|| field("name"));
+        let content = field("text").or_else(|| field("content")).or_else(|| Some(block.to_string()));
+        Self { kind, content, source }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileDiff {
     pub path: String,
     pub old_content: Option<String>,
     pub new_content: Option<String>,
     pub diff: Option<String>,
+    pub language: Option<String>,
+}
+
+impl FileDiff {
+    /// Syntax-highlighting language derived from the file extension.
+    pub fn language(&self) -> Option<&'static str> {
+        language_for_path(&self.path)
+    }
+}
+
+fn with_languages(mut diffs: Vec<FileDiff>) -> Vec<FileDiff> {
+    for diff in diffs.iter_mut() {
+        if diff.language.is_none() {
+            diff.language = diff.language().map(str::to_string);
+        }
+    }
+    diffs
+}
+
+// How servers answer for an endpoint they don't have
+fn is_missing_endpoint(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+    )
+}
+
+async fn diffs_from_response(response: reqwest::Response) -> Result<Vec<FileDiff>, OpenCodeError> {
+    if is_missing_endpoint(response.status()) {
+        return Err(OpenCodeError::Unsupported("session diffs"));
+    }
+    let diffs = response.error_for_status()?.json::<Vec<FileDiff>>().await?;
+    Ok(with_languages(diffs))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,55 +455,398 @@ pub struct Agent {
     pub model: Option<String>,
 }
 
+/// Outcome of one id in a batch delete.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDeleteResult {
+    pub id: String,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+/// Outcome of one session in `abort_all`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionAbortResult {
+    pub id: String,
+    pub aborted: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub provider: String,
+    pub context_window: Option<u64>,
+}
+
+/// The server's `/config`. Fields the monitor doesn't model yet are kept in
+/// `extra` rather than dropped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Tool name to whether it's enabled.
+    #[serde(default)]
+    pub tools: HashMap<String, bool>,
+    #[serde(default, alias = "experimental")]
+    pub features: HashMap<String, serde_json::Value>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// `/config/providers` nests models under their provider, keyed by id.
+#[derive(Deserialize)]
+struct ProviderList {
+    #[serde(default)]
+    providers: Vec<ProviderEntry>,
+}
+
+#[derive(Deserialize)]
+struct ProviderEntry {
+    id: String,
+    #[serde(default)]
+    models: HashMap<String, ProviderModel>,
+}
+
+#[derive(Deserialize)]
+struct ProviderModel {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    limit: Option<ModelLimit>,
+}
+
+#[derive(Deserialize)]
+struct ModelLimit {
+    #[serde(default)]
+    context: Option<u64>,
+}
+
+impl From<ProviderList> for Vec<ModelInfo> {
+    fn from(list: ProviderList) -> Self {
+        let mut models: Vec<ModelInfo> = list
+            .providers
+            .into_iter()
+            .flat_map(|provider| {
+                let provider_id = provider.id;
+                provider.models.into_iter().map(move |(key, model)| ModelInfo {
+                    id: model.id.unwrap_or(key),
+                    provider: provider_id.clone(),
+                    context_window: model.limit.and_then(|limit| limit.context),
+                })
+            })
+            .collect();
+        models.sort_by(|a, b| (&a.provider, &a.id).cmp(&(&b.provider, &b.id)));
+        models
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub healthy: bool,
     pub version: String,
 }
 
+impl HealthResponse {
+    pub fn parsed_version(&self) -> Option<semver::Version> {
+        parse_version(&self.version)
+    }
+
+    /// Unparseable versions are treated as supporting nothing.
+    pub fn supports(&self, feature: ServerFeature) -> bool {
+        self.parsed_version()
+            .map(|version| supports(&version, feature))
+            .unwrap_or(false)
+    }
+
+    pub fn features(&self) -> Vec<ServerFeature> {
+        ServerFeature::ALL
+            .into_iter()
+            .filter(|feature| self.supports(*feature))
+            .collect()
+    }
+}
+
+/// Cloning is cheap: clones share the HTTP connection pool and the session cache.
+#[derive(Clone)]
 pub struct OpenCodeClient {
     base_url: String,
-    http: Client,
+    // Rebuilt on reconfigure; every clone sees the new client
+    http: Arc<RwLock<Client>>,
+    // Sends the requests `http` builds, when an embedder supplied one
+    transport: Option<Arc<dyn Transport>>,
+    config: Arc<RwLock<ClientConfig>>,
     sessions: Arc<Mutex<Vec<Session>>>,
+    diff_watchers: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+    replays: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+    event_subscription: Arc<Mutex<Option<JoinHandle<()>>>>,
+    // Stream position and server-requested backoff, kept for reconnects
+    last_event_id: Arc<StdMutex<Option<String>>>,
+    event_retry: Arc<StdMutex<Duration>>,
+    event_log: Arc<StdMutex<EventLog>>,
+    pinned: Arc<StdMutex<PinStore>>,
+    auto_abort: Arc<AutoAbort>,
+    health_monitor: Arc<HealthMonitor>,
+    file_cache: Arc<Mutex<FileCache>>,
+    snapshots: Arc<StdMutex<SnapshotStore>>,
+    tags: Arc<StdMutex<TagStore>>,
+    log_path: Arc<Mutex<Option<PathBuf>>>,
+    default_agent: Arc<Mutex<String>>,
+    title_template: Arc<StdMutex<TitleTemplate>>,
+    // Sessions with a reply in progress, as seen on the event stream
+    generating: Arc<StdMutex<HashSet<String>>>,
+    // Response paths under this directory are shown relative to it
+    workspace_root: Arc<StdMutex<Option<String>>>,
+    message_counts: Arc<StdMutex<HashMap<String, (usize, Instant)>>>,
+    total_usage: Arc<StdMutex<Option<(TotalUsage, Instant)>>>,
+    // Latest message per session for the list view; `None` for empty sessions
+    previews: Arc<StdMutex<HashMap<String, Option<Message>>>>,
 }
 
 impl OpenCodeClient {
     pub fn new(base_url: &str) -> Self {
+        Self::with_config(base_url, ClientConfig::default())
+    }
+
+    /// Falls back to a default `reqwest` client if `config` can't be built.
+    pub fn with_config(base_url: &str, config: ClientConfig) -> Self {
+        let http = config.build_http().unwrap_or_default();
         Self {
             base_url: base_url.to_string(),
-            http: Client::new(),
+            http: Arc::new(RwLock::new(http)),
+            transport: None,
+            config: Arc::new(RwLock::new(config)),
             sessions: Arc::new(Mutex::new(Vec::new())),
+            diff_watchers: Arc::new(Mutex::new(HashMap::new())),
+            replays: Arc::new(Mutex::new(HashMap::new())),
+            event_subscription: Arc::new(Mutex::new(None)),
+            last_event_id: Arc::new(StdMutex::new(None)),
+            event_retry: Arc::new(StdMutex::new(DEFAULT_EVENT_RETRY)),
+            event_log: Arc::new(StdMutex::new(EventLog::default())),
+            pinned: Arc::new(StdMutex::new(PinStore::default())),
+            auto_abort: Arc::new(AutoAbort::default()),
+            health_monitor: Arc::new(HealthMonitor::default()),
+            file_cache: Arc::new(Mutex::new(FileCache::default())),
+            snapshots: Arc::new(StdMutex::new(SnapshotStore::default())),
+            tags: Arc::new(StdMutex::new(TagStore::default())),
+            log_path: Arc::new(Mutex::new(None)),
+            default_agent: Arc::new(Mutex::new(DEFAULT_SHELL_AGENT.to_string())),
+            title_template: Arc::new(StdMutex::new(TitleTemplate::default())),
+            generating: Arc::new(StdMutex::new(HashSet::new())),
+            workspace_root: Arc::new(StdMutex::new(None)),
+            message_counts: Arc::new(StdMutex::new(HashMap::new())),
+            total_usage: Arc::new(StdMutex::new(None)),
+            previews: Arc::new(StdMutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sends every request through `transport` instead of `reqwest`, e.g.
+    /// a test double or an instrumented client. Requests are still built,
+    /// retried and logged by this client.
+    pub fn with_transport(base_url: &str, transport: impl Transport + 'static) -> Self {
+        Self {
+            transport: Some(Arc::new(transport)),
+            ..Self::new(base_url)
+        }
+    }
+
+    fn http(&self) -> Http {
+        Http::new(self.http.read().unwrap().clone(), self.transport.clone(), self.config.clone())
+    }
+
+    pub fn config(&self) -> ClientConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Sets the directory response paths are made relative to; `None` leaves
+    /// them as the server sent them, apart from separators.
+    pub fn set_workspace_root(&self, root: Option<&str>) {
+        *self.workspace_root.lock().unwrap() = root
+            .filter(|root| !root.is_empty())
+            .map(paths::normalize_separators);
+    }
+
+    pub fn workspace_root(&self) -> Option<String> {
+        self.workspace_root.lock().unwrap().clone()
+    }
+
+    /// The workspace root, asking the server for the project directory when
+    /// none has been set. A root found this way is kept.
+    pub async fn discover_workspace_root(&self) -> Result<Option<String>, OpenCodeError> {
+        if let Some(root) = self.workspace_root() {
+            return Ok(Some(root));
+        }
+        let response = self.http()
+            .get(&format!("{}/path", self.base_url))
+            .send()
+            .await?;
+        let root = if is_missing_endpoint(response.status()) {
+            match self.server_config().await {
+                Ok(config) => workspace_root_field(&json!(config.extra)),
+                Err(OpenCodeError::Unsupported(_)) => None,
+                Err(e) => return Err(e),
+            }
+        } else {
+            workspace_root_field(&response.error_for_status()?.json::<serde_json::Value>().await?)
+        };
+        if let Some(root) = &root {
+            self.set_workspace_root(Some(root));
+        }
+        Ok(self.workspace_root())
+    }
+
+    /// Whether `path` is inside the project the server works on. Both sides
+    /// are canonicalized first, so symlinks and `..` don't fool the check.
+    pub async fn is_within_workspace(&self, path: &str) -> Result<WorkspaceCheck, OpenCodeError> {
+        let root = self.discover_workspace_root().await?;
+        let Some(root) = root else {
+            return Ok(WorkspaceCheck { root: None, path: paths::canonicalize(path), within: None });
+        };
+        let canonical_root = paths::canonicalize(&root);
+        let path = paths::canonicalize(&paths::to_absolute(&root, path));
+        let within = paths::is_within(&canonical_root, &path);
+        Ok(WorkspaceCheck { root: Some(root), path, within: Some(within) })
+    }
+
+    pub fn to_relative(&self, path: &str) -> String {
+        match self.workspace_root() {
+            Some(root) => paths::to_relative(&root, path),
+            None => paths::normalize_separators(path),
+        }
+    }
+
+    pub fn to_absolute(&self, path: &str) -> String {
+        match self.workspace_root() {
+            Some(root) => paths::to_absolute(&root, path),
+            None => paths::normalize_separators(path),
+        }
+    }
+
+    fn relative_diffs(&self, mut diffs: Vec<FileDiff>) -> Vec<FileDiff> {
+        for diff in &mut diffs {
+            diff.path = self.to_relative(&diff.path);
         }
+        diffs
     }
 
-    pub async fn health(&self) -> Result<HealthResponse, Error> {
-        self.http
+    /// Turns request and response body logging on or off. Takes effect on
+    /// the next request.
+    pub fn set_log_bodies(&self, enabled: bool) {
+        self.config.write().unwrap().log_bodies = enabled;
+    }
+
+    /// Swaps in a client built from `config`. Requests already in flight
+    /// finish on the old one. A config without a token refresh hook, as
+    /// any config from the UI is, keeps the current hook. Likewise a config
+    /// without a token keeps the current token, since the UI never gets it
+    /// back to resend; an empty token clears it.
+    pub fn reconfigure(&self, mut config: ClientConfig) -> Result<(), OpenCodeError> {
+        let http = config.build_http()?;
+        {
+            let current = self.config.read().unwrap();
+            if config.token_refresh.is_none() {
+                config.token_refresh = current.token_refresh.clone();
+            }
+            if config.auth_token.is_none() {
+                config.auth_token = current.auth_token.clone();
+            }
+        }
+        config.auth_token = config.auth_token.filter(|token| !token.is_empty());
+        *self.http.write().unwrap() = http;
+        *self.config.write().unwrap() = config;
+        Ok(())
+    }
+
+    pub async fn health(&self) -> Result<HealthResponse, OpenCodeError> {
+        Ok(self.http()
             .get(&format!("{}/global/health", self.base_url))
             .send()
             .await?
             .json()
+            .await?)
+    }
+
+    /// Probes `/global/health`, `/session` and `/agent` at once, since the
+    /// health endpoint can be up while the ones the monitor needs are not.
+    pub async fn deep_health(&self) -> DeepHealth {
+        let (health, sessions, agents) = tokio::join!(
+            probe("/global/health", DEEP_HEALTH_TIMEOUT, async {
+                match self.health().await? {
+                    health if health.healthy => Ok(()),
+                    _ => Err(OpenCodeError::Server("reported unhealthy".to_string())),
+                }
+            }),
+            probe("/session", DEEP_HEALTH_TIMEOUT, async { self.list_sessions().await.map(|_| ()) }),
+            probe("/agent", DEEP_HEALTH_TIMEOUT, async { self.list_agents().await.map(|_| ()) }),
+        );
+        DeepHealth::new(vec![health, sessions, agents])
+    }
+
+    /// Polls `health` until the server reports healthy, backing off between
+    /// attempts. Connection errors count as "not up yet" rather than failing.
+    pub async fn wait_until_healthy(&self, timeout: Duration) -> Result<HealthResponse, OpenCodeError> {
+        let wait = async {
+            let mut delay = HEALTH_WAIT_INITIAL;
+            loop {
+                if let Ok(health) = self.health().await {
+                    if health.healthy {
+                        return health;
+                    }
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(HEALTH_WAIT_MAX);
+            }
+        };
+        tokio::time::timeout(timeout, wait)
             .await
+            .map_err(|_| OpenCodeError::Timeout(timeout))
     }
 
-    pub async fn list_sessions(&self) -> Result<Vec<Session>, Error> {
-        let response = self.http
+    pub async fn list_sessions(&self) -> Result<Vec<Session>, OpenCodeError> {
+        let response = self.http()
             .get(&format!("{}/session", self.base_url))
             .send()
             .await?
-            .json::<Vec<Session>>()
+            .json::<SessionList>()
             .await?;
-        
+        let mut response: Vec<Session> = response.into();
+        self.apply_local_state(&mut response);
+
         let mut sessions = self.sessions.lock().await;
         *sessions = response.clone();
         Ok(response)
     }
 
-    pub async fn create_session(&self, title: Option<&str>) -> Result<Session, Error> {
+    pub async fn cached_sessions(&self) -> Vec<Session> {
+        self.sessions.lock().await.clone()
+    }
+
+    /// Counts sessions updated within `window` (or generating) as active and
+    /// the rest as idle. Generation state is only known while an event stream
+    /// is open.
+    pub async fn session_activity_summary(&self, window: Duration) -> Result<SessionActivity, OpenCodeError> {
+        let sessions = self.list_sessions().await?;
+        let generating = self.generating.lock().unwrap().clone();
+        Ok(summarize_activity(&sessions, &generating, window, chrono::Utc::now()))
+    }
+
+    /// Sets the title used for sessions created without one; `None` restores
+    /// the default. Supports `{date}` and `{n}` placeholders.
+    pub fn set_title_template(&self, template: Option<&str>) -> String {
+        let template = template.filter(|t| !t.trim().is_empty()).unwrap_or(DEFAULT_TITLE_TEMPLATE);
+        *self.title_template.lock().unwrap() = TitleTemplate::new(template);
+        template.to_string()
+    }
+
+    pub async fn create_session(&self, title: Option<&str>) -> Result<Session, OpenCodeError> {
+        let title = match title {
+            Some(title) => title.to_string(),
+            None => self.title_template.lock().unwrap().next_title(chrono::Local::now().date_naive()),
+        };
         let body = json!({
-            "title": title.unwrap_or("New Session")
+            "title": title
         });
         
-        let response = self.http
+        let response = self.http()
             .post(&format!("{}/session", self.base_url))
             .json(&body)
             .send()
@@ -110,136 +859,1192 @@ impl OpenCodeClient {
         Ok(response)
     }
 
-    pub async fn send_message(&self, session_id: &str, message: &str, model: Option<&str>) -> Result<Vec<Message>, Error> {
-        let body = json!({
-            "message": message,
-            "model": model
-        });
-        
-        self.http
-            .post(&format!("{}/session/{}/message", self.base_url, session_id))
-            .json(&body)
+    pub async fn get_session(&self, session_id: &str) -> Result<Session, OpenCodeError> {
+        let response = self.http()
+            .get(&format!("{}/session/{}", self.base_url, session_id))
             .send()
-            .await?
-            .json::<Vec<Message>>()
-            .await
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(OpenCodeError::NotFound(session_id.to_string()));
+        }
+        let mut session = response.error_for_status()?.json::<Session>().await?;
+        self.apply_local_state(std::slice::from_mut(&mut session));
+        Ok(session)
     }
 
-    pub async fn get_messages(&self, session_id: &str, limit: Option<i32>) -> Result<Vec<Message>, Error> {
-        let mut url = format!("{}/session/{}/message", self.base_url, session_id);
-        if let Some(l) = limit {
-            url.push_str(&format!("?limit={}", l));
+    /// Re-fetches one session and updates just its cache entry. A session the
+    /// server no longer has is dropped from the cache.
+    pub async fn refresh_session(&self, session_id: &str) -> Result<Session, OpenCodeError> {
+        let result = self.get_session(session_id).await;
+        let mut sessions = self.sessions.lock().await;
+        match &result {
+            Ok(session) => match sessions.iter_mut().find(|s| s.id == session.id) {
+                Some(cached) => *cached = session.clone(),
+                None => sessions.push(session.clone()),
+            },
+            Err(OpenCodeError::NotFound(_)) => sessions.retain(|s| s.id != session_id),
+            Err(_) => {}
         }
-        
-        self.http
-            .get(&url)
-            .send()
-            .await?
-            .json()
-            .await
+        result
     }
 
-    pub async fn get_diffs(&self, session_id: &str) -> Result<Vec<FileDiff>, Error> {
-        self.http
-            .get(&format!("{}/session/{}/diff", self.base_url, session_id))
+    /// The model the session is on now, which may differ from the one it was
+    /// created with. Read from the newest message that names one, falling
+    /// back to the session itself; the cached session is updated to match.
+    pub async fn active_model(&self, session_id: &str) -> Result<Option<String>, OpenCodeError> {
+        let response = self.http()
+            .get(&format!("{}/session/{}/message", self.base_url, session_id))
             .send()
-            .await?
-            .json()
-            .await
-    }
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(OpenCodeError::NotFound(session_id.to_string()));
+        }
+        let messages = response.error_for_status()?.json::<Vec<serde_json::Value>>().await?;
+        let model = match messages.iter().rev().find_map(message_model) {
+            Some(model) => Some(model),
+            None => self.get_session(session_id).await?.model,
+        };
 
-    pub async fn abort_session(&self, session_id: &str) -> Result<bool, Error> {
-        self.http
-            .post(&format!("{}/session/{}/abort", self.base_url, session_id))
-            .send()
-            .await?
-            .json()
-            .await
+        if let Some(cached) = self.sessions.lock().await.iter_mut().find(|s| s.id == session_id) {
+            cached.model = model.clone();
+        }
+        Ok(model)
     }
 
-    pub async fn delete_session(&self, session_id: &str) -> Result<bool, Error> {
-        self.http
-            .delete(&format!("{}/session/{}", self.base_url, session_id))
-            .send()
-            .await?
-            .json()
-            .await
-    }
+    /// Branches a new session off `session_id`, using the server's fork endpoint
+    /// when it has one.
+    pub async fn fork_session(&self, session_id: &str, title: Option<&str>) -> Result<Session, OpenCodeError> {
+        let body = json!({
+            "title": title
+        });
 
-    pub async fn search_files(&self, pattern: &str) -> Result<Vec<String>, Error> {
-        self.http
-            .get(&format!("{}/find?pattern={}", self.base_url, pattern))
+        let response = self.http()
+            .post(&format!("{}/session/{}/fork", self.base_url, session_id))
+            .json(&body)
             .send()
-            .await?
-            .json()
-            .await
-    }
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return self.fork_by_replay(session_id, title).await;
+        }
 
-    pub async fn read_file(&self, path: &str) -> Result<String, Error> {
-        self.http
-            .get(&format!("{}/file/content?path={}", self.base_url, path))
-            .send()
-            .await?
-            .text()
-            .await
+        let forked = response
+            .error_for_status()?
+            .json::<Session>()
+            .await?;
+        let mut sessions = self.sessions.lock().await;
+        sessions.push(forked.clone());
+        Ok(forked)
     }
 
-    pub async fn list_files(&self, path: &str) -> Result<serde_json::Value, Error> {
-        self.http
-            .get(&format!("{}/file?path={}", self.base_url, path))
-            .send()
-            .await?
-            .json()
-            .await
+    /// Fork fallback for servers without `/fork`: replays the source session's
+    /// user messages into a fresh session. Assistant replies are regenerated,
+    /// not copied, so they can differ from the original and replaying costs a
+    /// full model turn per message.
+    async fn fork_by_replay(&self, session_id: &str, title: Option<&str>) -> Result<Session, OpenCodeError> {
+        let history = self.fetch_messages(session_id, None, None).await?;
+        let title = title
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Fork of {}", session_id));
+        let forked = self.create_session(Some(&title)).await?;
+        for message in history.iter().filter(|m| m.role == "user") {
+            self.send_message(&forked.id, &message.content, None, None).await?;
+        }
+        Ok(forked)
     }
 
-    pub async fn run_shell(&self, session_id: &str, command: &str, agent: &str) -> Result<Vec<Message>, Error> {
+    fn send_message_request(
+        &self,
+        session_id: &str,
+        message: &str,
+        model: Option<&str>,
+        idempotency_key: Option<&str>,
+    ) -> Request {
+        self.message_counts.lock().unwrap().remove(session_id);
+        self.previews.lock().unwrap().remove(session_id);
         let body = json!({
-            "command": command,
-            "agent": agent
+            "message": message,
+            "model": model,
+            "idempotencyKey": idempotency_key
         });
-        
-        self.http
-            .post(&format!("{}/session/{}/shell", self.base_url, session_id))
-            .json(&body)
-            .send()
-            .await?
-            .json()
-            .await
+
+        let request = self.http()
+            .post(&format!("{}/session/{}/message", self.base_url, session_id))
+            .json(&body);
+        match idempotency_key {
+            Some(key) => request.header(IDEMPOTENCY_KEY_HEADER, key),
+            None => request,
+        }
     }
 
-    pub async fn list_agents(&self) -> Result<Vec<Agent>, Error> {
-        self.http
-            .get(&format!("{}/agent", self.base_url))
+    /// Sends a user message. With an `idempotency_key`, retrying after a
+    /// timeout is safe: the server drops repeats of a key it has already
+    /// seen. Servers that don't know the key just ignore it.
+    pub async fn send_message(
+        &self,
+        session_id: &str,
+        message: &str,
+        model: Option<&str>,
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<Message>, OpenCodeError> {
+        let response = self.send_message_request(session_id, message, model, idempotency_key)
             .send()
-            .await?
-            .json()
-            .await
+            .await?;
+        let status = response.status();
+        parse_message_response(status, &response.text().await?)
     }
 
-    // events function requires tokio-stream - temporarily disabled
-    // pub async fn events(&self) -> impl tokio_stream::Stream<Item = Result<serde_json::Value, reqwest::Error>> {
-    //     let response = self
-    //         .http
-    //         .get(&format!("{}/event", self.base_url))
-    //         .send()
-    //         .await
-    //         .unwrap();
-    //     
-    //     stream::iter(
-    //         response.bytes_stream()
-    //             .map(|chunk| {
-    //                 let chunk = chunk.unwrap();
-    //                 let line = String::from_utf8_lossy(&chunk);
-    //                 Ok(serde_json::from_str::<serde_json::Value>(&line).unwrap_or_else(|_| json!({ "raw": line.trim().to_string() })))
-    //             })
-    //     )
-    // }
+    /// Sends a message and waits until the assistant's turn completes, returning
+    /// the final assistant message. Completion is taken from the `session.idle`
+    /// event, falling back to polling when the event stream is unavailable.
+    pub async fn send_message_blocking(
+        &self,
+        session_id: &str,
+        message: &str,
+        model: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Message, OpenCodeError> {
+        // Subscribe before sending so the idle event can't slip past us;
+        // servers without streaming go straight to polling
+        let streaming = self.health().await
+            .map(|health| health.supports(ServerFeature::Streaming))
+            .unwrap_or(false);
+        let events = if streaming { self.events(None).await.ok() } else { None };
+
+        let wait = async {
+            // The reply already there before sending, which mustn't be taken
+            // for the answer to this message
+            let baseline = match self.latest_assistant_message(session_id, None).await {
+                Ok(previous) => Some(previous.id),
+                Err(OpenCodeError::NoReply) => None,
+                Err(err) => return Err(err),
+            };
+            self.send_message(session_id, message, model, None).await?;
+            if let Some(mut events) = events {
+                while let Some(event) = events.recv().await {
+                    if matches!(&event, ServerEvent::SessionIdle { session_id: id } if id == session_id) {
+                        return self.latest_assistant_message(session_id, baseline.as_deref()).await;
+                    }
+                }
+            }
+            self.wait_for_settled_reply(session_id, baseline.as_deref()).await
+        };
+
+        let mut reply = tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| OpenCodeError::Timeout(timeout))??;
+        // The turn is over, so a reply the server left unmarked is complete
+        reply.status.get_or_insert_with(|| "completed".to_string());
+        Ok(reply)
+    }
+
+    /// The newest assistant message, only counting ones after message
+    /// `after` when given.
+    async fn latest_assistant_message(&self, session_id: &str, after: Option<&str>) -> Result<Message, OpenCodeError> {
+        let messages = self.fetch_messages(session_id, None, None).await?;
+        messages_after(messages, after)
+            .into_iter()
+            .rev()
+            .find(|m| m.role == "assistant" && Some(m.id.as_str()) != after)
+            .ok_or(OpenCodeError::NoReply)
+    }
+
+    /// Fetches the latest message of each session, at most `concurrency` at
+    /// a time, into the cache `session_preview` reads. Returns how many were
+    /// fetched; sessions that fail keep whatever was cached before.
+    pub async fn prefetch_previews(&self, session_ids: Vec<String>, concurrency: usize) -> usize {
+        let slots = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        for id in session_ids {
+            let client = self.clone();
+            let slots = slots.clone();
+            tasks.spawn(async move {
+                let _permit = slots.acquire_owned().await;
+                let latest = client.fetch_messages(&id, Some(1), None).await.map(|mut messages| messages.pop());
+                (id, latest)
+            });
+        }
+
+        let mut fetched = 0;
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((id, latest)) = joined else { continue };
+            match latest {
+                Ok(latest) => {
+                    self.previews.lock().unwrap().insert(id, latest);
+                    fetched += 1;
+                }
+                Err(e) => tracing::debug!(session_id = %id, error = %e, "preview prefetch failed"),
+            }
+        }
+        fetched
+    }
+
+    /// The cached latest message, if `prefetch_previews` has fetched one.
+    pub fn session_preview(&self, session_id: &str) -> Option<Message> {
+        self.previews.lock().unwrap().get(session_id).cloned().flatten()
+    }
+
+    /// The assistant message still being generated, or `None` when the
+    /// session is idle. A message counts as in flight if its status says so,
+    /// or if it has no status and the event stream last saw the session busy.
+    pub async fn active_generation(&self, session_id: &str) -> Result<Option<Message>, OpenCodeError> {
+        let latest = match self.latest_assistant_message(session_id, None).await {
+            Ok(latest) => latest,
+            Err(OpenCodeError::NoReply) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let busy = self.generating.lock().unwrap().contains(session_id);
+        let in_flight = latest.is_in_progress() || (latest.status.is_none() && busy);
+        Ok(in_flight.then_some(latest))
+    }
+
+    /// Polls for an assistant message newer than `baseline` until the server
+    /// marks it finished or, without a status, it stops changing between
+    /// polls.
+    async fn wait_for_settled_reply(&self, session_id: &str, baseline: Option<&str>) -> Result<Message, OpenCodeError> {
+        let mut previous: Option<Message> = None;
+        loop {
+            tokio::time::sleep(REPLY_POLL_INTERVAL).await;
+            let latest = match self.latest_assistant_message(session_id, baseline).await {
+                Ok(latest) => latest,
+                Err(OpenCodeError::NoReply) => continue,
+                Err(err) => return Err(err),
+            };
+            if latest.is_in_progress() {
+                previous = Some(latest);
+                continue;
+            }
+            if latest.status.is_some() {
+                return Ok(latest);
+            }
+            if let Some(prev) = previous.as_ref() {
+                if prev.id == latest.id && prev.content == latest.content && prev.parts.len() == latest.parts.len() {
+                    return Ok(latest);
+                }
+            }
+            previous = Some(latest);
+        }
+    }
+
+    /// Messages of a session, at most `limit` of them. Without a limit the
+    /// configured default applies, and any limit is capped at the configured
+    /// maximum. With `after`, only messages newer than that message id are
+    /// returned; the id is also sent to the server, and if the server ignores
+    /// it the page is filtered client-side.
+    pub async fn get_messages(&self, session_id: &str, limit: Option<i32>, after: Option<&str>) -> Result<Vec<Message>, OpenCodeError> {
+        let (limit, clamped) = self.config().message_limit(limit);
+        if clamped {
+            tracing::warn!(session_id, limit, "message limit clamped to the configured maximum");
+        }
+        self.fetch_messages(session_id, Some(limit), after).await
+    }
+
+    /// Every message of a session, without the configured limits.
+    pub async fn all_messages(&self, session_id: &str) -> Result<Vec<Message>, OpenCodeError> {
+        self.fetch_messages(session_id, None, None).await
+    }
+
+    // Unclamped, for callers that need the whole history
+    async fn fetch_messages(&self, session_id: &str, limit: Option<i32>, after: Option<&str>) -> Result<Vec<Message>, OpenCodeError> {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(l) = limit {
+            query.push(("limit", l.to_string()));
+        }
+        if let Some(after) = after {
+            query.push(("after", after.to_string()));
+        }
+
+        let messages = self.http()
+            .get(&format!("{}/session/{}/message", self.base_url, session_id))
+            .query(&query)
+            .send()
+            .await?
+            .json::<Vec<Message>>()
+            .await?;
+        Ok(messages_after(messages, after))
+    }
+
+    /// Number of messages in the session, cached for a few seconds. Asks
+    /// for `limit=0` and reads the total from `X-Total-Count`; servers that
+    /// don't send it get the full list counted instead, without parsing the
+    /// message bodies.
+    pub async fn message_count(&self, session_id: &str) -> Result<usize, OpenCodeError> {
+        if let Some((count, at)) = self.message_counts.lock().unwrap().get(session_id) {
+            if at.elapsed() < MESSAGE_COUNT_TTL {
+                return Ok(*count);
+            }
+        }
+
+        let url = format!("{}/session/{}/message", self.base_url, session_id);
+        let response = self.http()
+            .get(&url)
+            .query(&[("limit", "0")])
+            .send()
+            .await?
+            .error_for_status()?;
+        let total = response
+            .headers()
+            .get(TOTAL_COUNT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<usize>().ok());
+        let count = match total {
+            Some(total) => total,
+            None => {
+                let listed = response.json::<Vec<serde::de::IgnoredAny>>().await?.len();
+                // An empty page may just mean the limit was honoured
+                if listed > 0 {
+                    listed
+                } else {
+                    self.http()
+                        .get(&url)
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .json::<Vec<serde::de::IgnoredAny>>()
+                        .await?
+                        .len()
+                }
+            }
+        };
+
+        self.message_counts
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), (count, Instant::now()));
+        Ok(count)
+    }
+
+    /// Tokens and cost over the session's messages; `None` when none of
+    /// them report usage.
+    pub async fn session_usage(&self, session_id: &str) -> Result<Option<Usage>, OpenCodeError> {
+        let messages = self.http()
+            .get(&format!("{}/session/{}/message", self.base_url, session_id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<serde_json::Value>>()
+            .await?;
+        Ok(Usage::from_messages(&messages))
+    }
+
+    /// Usage summed across every session, a few at a time. The result is
+    /// cached briefly since it reads every message on the server.
+    pub async fn total_usage(&self) -> Result<TotalUsage, OpenCodeError> {
+        if let Some((total, at)) = self.total_usage.lock().unwrap().as_ref() {
+            if at.elapsed() < TOTAL_USAGE_TTL {
+                return Ok(total.clone());
+            }
+        }
+
+        let sessions = self.list_sessions().await?;
+        let slots = Arc::new(Semaphore::new(USAGE_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for session in &sessions {
+            let client = self.clone();
+            let slots = slots.clone();
+            let id = session.id.clone();
+            tasks.spawn(async move {
+                let _permit = slots.acquire_owned().await;
+                let usage = client.session_usage(&id).await;
+                (id, usage)
+            });
+        }
+
+        let mut total = TotalUsage { sessions: sessions.len(), ..TotalUsage::default() };
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((id, usage)) = joined else { continue };
+            match usage {
+                Ok(Some(usage)) => total.usage += usage,
+                Ok(None) => total.sessions_without_usage += 1,
+                Err(e) => {
+                    tracing::debug!(session_id = %id, error = %e, "skipping session usage");
+                    total.failed.push(id);
+                }
+            }
+        }
+        total.failed.sort();
+        total.total_tokens = total.usage.total_tokens();
+
+        *self.total_usage.lock().unwrap() = Some((total.clone(), Instant::now()));
+        Ok(total)
+    }
+
+    /// A page of one message's parts, so long tool output can be loaded on
+    /// demand. Uses the server's part endpoint with the total from
+    /// `X-Total-Count`; without either, the whole message is fetched and
+    /// sliced here.
+    pub async fn get_message_parts(
+        &self,
+        session_id: &str,
+        message_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<MessagePartsPage, OpenCodeError> {
+        let limit = limit.max(1);
+        let message_url = format!("{}/session/{}/message/{}", self.base_url, session_id, message_id);
+        let response = self.http()
+            .get(&format!("{}/part", message_url))
+            .query(&[("offset", offset.to_string()), ("limit", limit.to_string())])
+            .send()
+            .await?;
+        if !is_missing_endpoint(response.status()) {
+            let response = response.error_for_status()?;
+            let total = response
+                .headers()
+                .get(TOTAL_COUNT_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<usize>().ok());
+            let parts = response.json::<Vec<MessagePart>>().await?;
+            return Ok(match total {
+                Some(total) if parts.len() <= limit => MessagePartsPage::new(parts, offset, total),
+                _ => MessagePartsPage::slice(parts, offset, limit),
+            });
+        }
+
+        let message = self.http()
+            .get(&message_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Message>()
+            .await?;
+        Ok(MessagePartsPage::slice(message.parts, offset, limit))
+    }
+
+    /// Older servers lack the diff endpoint and answer 404; that comes back
+    /// as `Unsupported` so callers can show an empty list instead of failing.
+    pub async fn get_diffs(&self, session_id: &str) -> Result<Vec<FileDiff>, OpenCodeError> {
+        let response = self.http()
+            .get(&format!("{}/session/{}/diff", self.base_url, session_id))
+            .send()
+            .await?;
+        Ok(self.relative_diffs(diffs_from_response(response).await?))
+    }
+
+    /// Added and removed line counts for each file the session changed.
+    pub async fn per_file_stats(&self, session_id: &str) -> Result<Vec<FileStats>, OpenCodeError> {
+        let diffs = self.get_diffs(session_id).await?;
+        Ok(diffs
+            .into_iter()
+            .map(|diff| {
+                FileStats::new(
+                    &diff.path,
+                    diff.diff.as_deref(),
+                    diff.old_content.as_deref(),
+                    diff.new_content.as_deref(),
+                    diff.language,
+                )
+            })
+            .collect())
+    }
+
+    /// Paths the session has changed, without the diff bodies. The server
+    /// has no lighter endpoint for this, so it's derived from the diffs.
+    pub async fn changed_files(&self, session_id: &str) -> Result<Vec<String>, OpenCodeError> {
+        let diffs = self.get_diffs(session_id).await?;
+        Ok(diffs.into_iter().map(|diff| diff.path).collect())
+    }
+
+    /// The diff of one file, or `None` if it has no changes. Servers that
+    /// ignore the `path` filter send every file, so the result is filtered
+    /// here as well.
+    pub async fn get_file_diff(&self, session_id: &str, path: &str) -> Result<Option<FileDiff>, OpenCodeError> {
+        let response = self.http()
+            .get(&format!("{}/session/{}/diff", self.base_url, session_id))
+            .query(&[("path", path)])
+            .send()
+            .await?;
+        let diffs = self.relative_diffs(diffs_from_response(response).await?);
+        let path = self.to_relative(path);
+        Ok(diffs.into_iter().find(|diff| diff.path == path))
+    }
+
+    /// The edits `message` would make, computed by the server's preview mode.
+    /// Nothing is applied and no assistant turn is recorded.
+    pub async fn preview_message(
+        &self,
+        session_id: &str,
+        message: &str,
+        model: Option<&str>,
+    ) -> Result<Vec<FileDiff>, OpenCodeError> {
+        // Check the version first so an older server never sees the request
+        if !self.health().await?.supports(ServerFeature::MessagePreview) {
+            return Err(OpenCodeError::Unsupported("message previews"));
+        }
+
+        let body = json!({
+            "message": message,
+            "model": model
+        });
+        let response = self.http()
+            .post(&format!("{}/session/{}/message/preview", self.base_url, session_id))
+            .json(&body)
+            .send()
+            .await?;
+        if is_missing_endpoint(response.status()) {
+            return Err(OpenCodeError::Unsupported("message previews"));
+        }
+        let diffs = response.error_for_status()?.json::<Vec<FileDiff>>().await?;
+        Ok(self.relative_diffs(with_languages(diffs)))
+    }
+
+    pub async fn abort_session(&self, session_id: &str) -> Result<bool, OpenCodeError> {
+        let body = self.http()
+            .post(&format!("{}/session/{}/abort", self.base_url, session_id))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        parse_acknowledgement(&body)
+    }
+
+    /// Aborts every known session, concurrently and past failures. Sessions
+    /// come from the server, or from the cache if it can't list them, plus
+    /// any seen generating on the event stream. Results are sorted by id.
+    pub async fn abort_all(&self) -> Result<Vec<SessionAbortResult>, OpenCodeError> {
+        let mut ids: HashSet<String> = self.generating.lock().unwrap().clone();
+        match self.list_sessions().await {
+            Ok(sessions) => ids.extend(sessions.into_iter().map(|s| s.id)),
+            Err(e) => {
+                ids.extend(self.cached_sessions().await.into_iter().map(|s| s.id));
+                if ids.is_empty() {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut tasks = JoinSet::new();
+        for id in ids {
+            let client = self.clone();
+            tasks.spawn(async move {
+                let result = client.abort_session(&id).await;
+                (id, result)
+            });
+        }
+        let mut results = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((id, result)) = joined {
+                results.push(match result {
+                    Ok(aborted) => SessionAbortResult { id, aborted, error: None },
+                    Err(e) => SessionAbortResult { id, aborted: false, error: Some(e.to_string()) },
+                });
+            }
+        }
+        results.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(results)
+    }
+
+    pub async fn delete_session(&self, session_id: &str) -> Result<bool, OpenCodeError> {
+        let body = self.http()
+            .delete(&format!("{}/session/{}", self.base_url, session_id))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        parse_acknowledgement(&body)
+    }
+
+    /// Deletes `ids` a few at a time, carrying on past failures. Results come
+    /// back in the order of `ids`.
+    pub async fn delete_sessions(&self, ids: Vec<String>) -> Vec<SessionDeleteResult> {
+        let slots = Arc::new(Semaphore::new(DELETE_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (index, id) in ids.into_iter().enumerate() {
+            let client = self.clone();
+            let slots = slots.clone();
+            tasks.spawn(async move {
+                let _permit = slots.acquire_owned().await;
+                let result = client.delete_session(&id).await;
+                (index, id, result)
+            });
+        }
+
+        let mut results = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(result) = joined {
+                results.push(result);
+            }
+        }
+        results.sort_by_key(|(index, _, _)| *index);
+
+        let results: Vec<SessionDeleteResult> = results
+            .into_iter()
+            .map(|(_, id, result)| match result {
+                Ok(deleted) => SessionDeleteResult { id, deleted, error: None },
+                Err(e) => SessionDeleteResult { id, deleted: false, error: Some(e.to_string()) },
+            })
+            .collect();
+
+        let deleted: HashSet<&str> = results
+            .iter()
+            .filter(|result| result.deleted)
+            .map(|result| result.id.as_str())
+            .collect();
+        self.sessions
+            .lock()
+            .await
+            .retain(|session| !deleted.contains(session.id.as_str()));
+        results
+    }
+
+    /// Matching paths, each listed once.
+    pub async fn search_files(&self, pattern: &str) -> Result<Vec<String>, OpenCodeError> {
+        let mut paths: Vec<String> = Vec::new();
+        for found in self.search(pattern).await? {
+            if !paths.contains(&found.path) {
+                paths.push(found.path);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Matches with their line, column and snippet where the server gives them.
+    pub async fn search(&self, pattern: &str) -> Result<Vec<SearchMatch>, OpenCodeError> {
+        let values = self.http()
+            .get(&format!("{}/find", self.base_url))
+            .query(&[("pattern", pattern)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<serde_json::Value>>()
+            .await?;
+        let mut matches = parse_matches(&values);
+        for found in &mut matches {
+            found.path = self.to_relative(&found.path);
+        }
+        Ok(matches)
+    }
+
+    /// Reads a file, revalidating any cached copy with `If-None-Match` /
+    /// `If-Modified-Since` so an unchanged file comes back as a cheap `304`.
+    pub async fn read_file(&self, path: &str) -> Result<String, OpenCodeError> {
+        let mut request = self.http()
+            .get(&format!("{}/file/content?path={}", self.base_url, path));
+        let cached = self.file_cache.lock().await.get(path).cloned();
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                return Ok(cached.content);
+            }
+        }
+        let response = response.error_for_status()?;
+        let header = |name| {
+            response.headers().get(name).and_then(|v: &HeaderValue| v.to_str().ok()).map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let content = response.text().await?;
+        self.file_cache.lock().await.insert(path, content.clone(), etag, last_modified);
+        Ok(content)
+    }
+
+    /// What writing `new_content` to `path` would change, as a unified diff
+    /// against the file's current content. A file the server doesn't have
+    /// yet diffs as newly added, with no `old_content`.
+    pub async fn preview_edit(&self, path: &str, new_content: &str) -> Result<FileDiff, OpenCodeError> {
+        let old_content = match self.read_file(path).await {
+            Ok(content) => Some(content),
+            Err(OpenCodeError::Http(e)) if e.status() == Some(StatusCode::NOT_FOUND) => None,
+            Err(e) => return Err(e),
+        };
+        let display_path = self.to_relative(path);
+        let old_path = old_content.as_ref().map(|_| display_path.as_str());
+        let diff = unified_diff(old_path, old_content.as_deref().unwrap_or_default(), &display_path, new_content)
+            .map_err(|err| OpenCodeError::Server(err.message().to_string()))?;
+        Ok(FileDiff {
+            language: language_for_path(&display_path).map(str::to_string),
+            path: display_path,
+            old_content,
+            new_content: Some(new_content.to_string()),
+            diff: Some(diff),
+        })
+    }
+
+    pub async fn clear_file_cache(&self) {
+        self.file_cache.lock().await.clear();
+    }
+
+    /// Returns the bytes still cached after evicting down to the new limit.
+    pub async fn set_file_cache_limit(&self, max_bytes: usize) -> usize {
+        let mut cache = self.file_cache.lock().await;
+        cache.set_max_bytes(max_bytes);
+        cache.used_bytes()
+    }
+
+    /// Captures the current content of `path` for a later `diff_snapshots`.
+    pub async fn snapshot_file(&self, path: &str) -> Result<String, OpenCodeError> {
+        let content = self.read_file(path).await?;
+        Ok(self.snapshots.lock().unwrap().insert(path, content))
+    }
+
+    pub fn diff_snapshots(&self, id_a: &str, id_b: &str) -> Result<String, OpenCodeError> {
+        self.snapshots.lock().unwrap().diff(id_a, id_b)
+    }
+
+    /// Streams a file's bytes rather than buffering them, optionally just
+    /// `range`. Use `read_file` for small files.
+    pub async fn read_file_stream(&self, path: &str, range: Option<FileRange>) -> Result<FileStream, OpenCodeError> {
+        let range_header = range.map(|range| range.header_value()).transpose()?;
+        let mut request = self.http()
+            .get(&format!("{}/file/content", self.base_url))
+            .query(&[("path", path)])
+            .streaming();
+        if let Some(value) = range_header {
+            request = request.header(reqwest::header::RANGE, value);
+        }
+        let response = request.send().await?.error_for_status()?;
+        Ok(FileStream::new(response, range))
+    }
+
+    pub async fn list_files(&self, path: &str) -> Result<serde_json::Value, OpenCodeError> {
+        Ok(self.http()
+            .get(&format!("{}/file?path={}", self.base_url, path))
+            .send()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Runs `command` in the session's shell as `agent`, or as the default
+    /// agent when none is given.
+    pub async fn run_shell(&self, session_id: &str, command: &str, agent: Option<&str>) -> Result<Vec<Message>, OpenCodeError> {
+        let agent = match agent {
+            Some(agent) => agent.to_string(),
+            None => self.default_agent().await,
+        };
+        let body = json!({
+            "command": command,
+            "agent": agent
+        });
+        
+        Ok(self.http()
+            .post(&format!("{}/session/{}/shell", self.base_url, session_id))
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Reports the branch and cleanliness of the workspace by running
+    /// `git status` through the session's shell.
+    pub async fn workspace_status(&self, session_id: &str, agent: Option<&str>) -> Result<WorkspaceStatus, OpenCodeError> {
+        let messages = self.run_shell(session_id, GIT_STATUS_COMMAND, agent).await?;
+        Ok(WorkspaceStatus::parse_porcelain_v2(&shell_output(&messages)))
+    }
+
+    pub async fn list_agents(&self) -> Result<Vec<Agent>, OpenCodeError> {
+        Ok(self.http()
+            .get(&format!("{}/agent", self.base_url))
+            .send()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// One agent with its tools and capabilities, by id or name.
+    pub async fn get_agent(&self, agent: &str) -> Result<AgentDetail, OpenCodeError> {
+        let agents = self.http()
+            .get(&format!("{}/agent", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<serde_json::Value>>()
+            .await?;
+        agents
+            .iter()
+            .filter_map(AgentDetail::from_value)
+            .find(|detail| detail.matches(agent))
+            .ok_or_else(|| OpenCodeError::InvalidConfig(format!("unknown agent {:?}", agent)))
+    }
+
+    pub async fn default_agent(&self) -> String {
+        self.default_agent.lock().await.clone()
+    }
+
+    /// Sets the agent `run_shell` falls back to. It must be one the server
+    /// lists, matched by id or name.
+    pub async fn set_default_agent(&self, agent: &str) -> Result<(), OpenCodeError> {
+        let agents = self.list_agents().await?;
+        if !agents.iter().any(|a| a.id == agent || a.name == agent) {
+            return Err(OpenCodeError::InvalidConfig(format!("unknown agent {:?}", agent)));
+        }
+        *self.default_agent.lock().await = agent.to_string();
+        Ok(())
+    }
+
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>, OpenCodeError> {
+        let response = self.http()
+            .get(&format!("{}/config/providers", self.base_url))
+            .send()
+            .await?
+            .json::<ProviderList>()
+            .await?;
+        Ok(response.into())
+    }
+
+    pub async fn server_config(&self) -> Result<ServerConfig, OpenCodeError> {
+        let response = self.http()
+            .get(&format!("{}/config", self.base_url))
+            .send()
+            .await?;
+        if is_missing_endpoint(response.status()) {
+            return Err(OpenCodeError::Unsupported("server config"));
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Loads saved session pins from `path` and saves future changes there.
+    pub fn load_pins(&self, path: PathBuf) {
+        *self.pinned.lock().unwrap() = PinStore::load(path);
+    }
+
+    /// Returns whether anything changed.
+    pub async fn set_pinned(&self, session_id: &str, pinned: bool) -> Result<bool, std::io::Error> {
+        let changed = self.pinned.lock().unwrap().set(session_id, pinned)?;
+        if let Some(session) = self.sessions.lock().await.iter_mut().find(|s| s.id == session_id) {
+            session.is_pinned = pinned;
+        }
+        Ok(changed)
+    }
+
+    pub async fn pinned_sessions(&self) -> HashSet<String> {
+        self.pinned.lock().unwrap().ids()
+    }
+
+    /// Pinned sessions that still exist on the server; pins for sessions
+    /// that are gone are dropped.
+    pub async fn list_pinned_sessions(&self) -> Result<Vec<Session>, OpenCodeError> {
+        let sessions = self.list_sessions().await?;
+        let existing: HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        if let Err(e) = self.pinned.lock().unwrap().retain_existing(&existing) {
+            tracing::warn!(error = %e, "failed to save pruned session pins");
+        }
+        Ok(sessions.into_iter().filter(|s| s.is_pinned).collect())
+    }
+
+    pub fn auto_abort(&self) -> &AutoAbort {
+        &self.auto_abort
+    }
+
+    pub fn health_monitor(&self) -> &HealthMonitor {
+        &self.health_monitor
+    }
+
+    /// Loads saved session tags from `path` and saves future changes there.
+    pub fn load_tags(&self, path: PathBuf) {
+        *self.tags.lock().unwrap() = TagStore::load(path);
+    }
+
+    // Merges the monitor's own tags and pins into sessions from the server
+    fn apply_local_state(&self, sessions: &mut [Session]) {
+        let store = self.tags.lock().unwrap();
+        let pins = self.pinned.lock().unwrap();
+        for session in sessions {
+            for tag in store.tags(&session.id) {
+                if !session.tags.contains(&tag) {
+                    session.tags.push(tag);
+                }
+            }
+            session.is_pinned = pins.contains(&session.id);
+        }
+    }
+
+    /// Returns the session's monitor-side tags after the change.
+    pub fn add_tag(&self, session_id: &str, tag: &str) -> Result<Vec<String>, std::io::Error> {
+        self.tags.lock().unwrap().add(session_id, tag)
+    }
+
+    pub fn remove_tag(&self, session_id: &str, tag: &str) -> Result<Vec<String>, std::io::Error> {
+        self.tags.lock().unwrap().remove(session_id, tag)
+    }
+
+    /// Searches sessions on the server, `limit` at a time from `offset`.
+    /// Servers without `/session/search` get the full list filtered by
+    /// title and id here.
+    pub async fn search_sessions(
+        &self,
+        query: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<SessionSearchPage, OpenCodeError> {
+        let limit = limit.max(1);
+        let response = self.http()
+            .get(&format!("{}/session/search", self.base_url))
+            .query(&[("q", query.to_string()), ("limit", limit.to_string()), ("offset", offset.to_string())])
+            .send()
+            .await?;
+        if !is_missing_endpoint(response.status()) {
+            let mut sessions: Vec<Session> = response.error_for_status()?.json::<SessionList>().await?.into();
+            // Servers that ignore the paging params send every match
+            if sessions.len() > limit {
+                sessions = sessions.into_iter().skip(offset).take(limit).collect();
+            }
+            self.apply_local_state(&mut sessions);
+            return Ok(SessionSearchPage::new(sessions, offset, limit, true));
+        }
+
+        let matched: Vec<Session> = self.list_sessions()
+            .await?
+            .into_iter()
+            .filter(|session| session_matches(session, query))
+            .skip(offset)
+            .take(limit)
+            .collect();
+        Ok(SessionSearchPage::new(matched, offset, limit, false))
+    }
+
+    pub async fn list_sessions_by_tag(&self, tag: &str) -> Result<Vec<Session>, OpenCodeError> {
+        let mut sessions = self.list_sessions().await?;
+        sessions.retain(|session| session.tags.iter().any(|t| t == tag));
+        Ok(sessions)
+    }
+
+    /// Sessions created between `from` and `to`, both RFC3339 and inclusive,
+    /// oldest first. Either bound may be left open. The server has no range
+    /// filter, so this narrows the full list.
+    pub async fn sessions_in_range(&self, from: Option<&str>, to: Option<&str>) -> Result<Vec<Session>, OpenCodeError> {
+        let from = from.map(parse_range_bound).transpose()?;
+        let to = to.map(parse_range_bound).transpose()?;
+        let sessions = self.list_sessions().await?;
+        Ok(created_between(sessions, from, to))
+    }
+
+    /// Overrides the log file `server_logs` reads; `None` goes back to the
+    /// newest file in OpenCode's default log directory.
+    pub async fn set_log_path(&self, path: Option<PathBuf>) {
+        *self.log_path.lock().await = path;
+    }
+
+    /// Returns the last `lines` lines of the server log.
+    pub async fn server_logs(&self, lines: usize) -> Result<Vec<LogLine>, std::io::Error> {
+        let configured = self.log_path.lock().await.clone();
+        let path = configured
+            .or_else(|| default_log_dir().and_then(|dir| latest_log_file(&dir)))
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No OpenCode log file found"))?;
+
+        let raw = tokio::task::spawn_blocking(move || tail_lines(&path, lines))
+            .await
+            .map_err(std::io::Error::other)??;
+        Ok(raw.iter().map(|line| LogLine::parse(line)).collect())
+    }
+
+    fn events_request(&self, since: Option<&str>) -> Request {
+        let request = self.http().get(&format!("{}/event", self.base_url)).streaming();
+        match since {
+            Some(since) => request.query(&[("since", since)]),
+            None => request,
+        }
+    }
+
+    /// Subscribes to the server's `/event` stream. The returned receiver closes
+    /// when the connection drops.
+    ///
+    /// `since` (an event id or timestamp) asks the server to replay events after
+    /// that point, to fill the gap left by a reconnect. Servers without replay
+    /// ignore it and stream live events only.
+    ///
+    /// The stream is read as SSE or NDJSON according to its `Content-Type`,
+    /// unless the config's `event_format` forces one.
+    pub async fn events(&self, since: Option<&str>) -> Result<mpsc::Receiver<ServerEvent>, OpenCodeError> {
+        let mut response = self.events_request(since)
+            .send()
+            .await?
+            .error_for_status()?;
+        let content_type = response.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok());
+        let format = self.config().event_format.resolve(content_type);
+
+        let (tx, rx) = mpsc::channel(64);
+        let last_event_id = self.last_event_id.clone();
+        let event_retry = self.event_retry.clone();
+        let generating = self.generating.clone();
+        tokio::spawn(async move {
+            let mut state = SseState::default();
+            while let Ok(Some(chunk)) = response.chunk().await {
+                let frames = format.parse_chunk(&mut state, &chunk);
+                if let Some(retry) = state.retry {
+                    *event_retry.lock().unwrap() = retry;
+                }
+                for frame in frames {
+                    if frame.id.is_some() {
+                        *last_event_id.lock().unwrap() = frame.id.clone();
+                    }
+                    let Some(event) = frame.to_server_event() else {
+                        continue;
+                    };
+                    match &event {
+                        ServerEvent::MessageUpdated { session_id, .. } => {
+                            generating.lock().unwrap().insert(session_id.clone());
+                        }
+                        ServerEvent::SessionIdle { session_id } => {
+                            generating.lock().unwrap().remove(session_id);
+                        }
+                        _ => {}
+                    }
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    /// Id of the last event seen on any stream, to resume from on reconnect.
+    pub fn last_event_id(&self) -> Option<String> {
+        self.last_event_id.lock().unwrap().clone()
+    }
+
+    /// How long to wait before reconnecting, as last requested by the server.
+    pub fn event_retry(&self) -> Duration {
+        *self.event_retry.lock().unwrap()
+    }
+
+    /// Remembers an event forwarded by the subscription, for `recent_events`.
+    pub fn record_event(&self, event: &ServerEvent) {
+        self.event_log.lock().unwrap().push(event.clone());
+    }
+
+    /// Up to `n` of the latest subscription events, oldest first.
+    pub fn recent_events(&self, n: usize) -> Vec<ServerEvent> {
+        self.event_log.lock().unwrap().recent(n)
+    }
+
+    pub fn set_event_log_capacity(&self, capacity: usize) {
+        self.event_log.lock().unwrap().set_capacity(capacity);
+    }
+
+    /// Tracks the task forwarding events to the frontend, replacing any
+    /// previous subscription.
+    pub async fn set_event_subscription(&self, task: JoinHandle<()>) {
+        if let Some(previous) = self.event_subscription.lock().await.replace(task) {
+            previous.abort();
+        }
+    }
+
+    pub async fn stop_event_subscription(&self) -> bool {
+        match self.event_subscription.lock().await.take() {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tracks the background task watching diffs for a session, replacing
+    /// (and aborting) any previous watcher for the same session.
+    pub async fn register_diff_watcher(&self, session_id: &str, task: JoinHandle<()>) {
+        let mut watchers = self.diff_watchers.lock().await;
+        if let Some(previous) = watchers.insert(session_id.to_string(), task) {
+            previous.abort();
+        }
+    }
+
+    pub async fn stop_diff_watcher(&self, session_id: &str) -> bool {
+        let mut watchers = self.diff_watchers.lock().await;
+        match watchers.remove(session_id) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tracks a session's replay task, aborting any replay already running
+    /// for it.
+    pub async fn register_replay(&self, session_id: &str, task: JoinHandle<()>) {
+        let mut replays = self.replays.lock().await;
+        if let Some(previous) = replays.insert(session_id.to_string(), task) {
+            previous.abort();
+        }
+    }
+
+    /// Stops the replay if it's still running. Returns false if there was
+    /// nothing left to stop.
+    pub async fn stop_replay(&self, session_id: &str) -> bool {
+        let mut replays = self.replays.lock().await;
+        match replays.remove(session_id) {
+            Some(task) if !task.is_finished() => {
+                task.abort();
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::opencode::config::TokenRefresh;
+    use crate::opencode::events::EventFormat;
+    use crate::opencode::trace::REQUEST_ID_HEADER;
+    use crate::opencode::transport::TransportFuture;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves `respond(request head) -> raw HTTP response` on a local port and
+    /// returns its base URL. One request per connection.
+    async fn mock_http<F>(respond: F) -> String
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        let respond = Arc::new(respond);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let respond = respond.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let response = respond(&String::from_utf8_lossy(&request));
+                    socket.write_all(response.as_bytes()).await.ok();
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn http_response(status: u16, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {} X\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    /// Serves `respond(method, path) -> (status, body)`.
+    async fn mock_server(respond: fn(&str, &str) -> (u16, String)) -> String {
+        mock_http(move |request| {
+            let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+            let method = request_line.next().unwrap_or_default();
+            let path = request_line.next().unwrap_or_default();
+            let (status, body) = respond(method, path);
+            http_response(status, "", &body)
+        })
+        .await
+    }
 
     #[tokio::test]
     async fn test_health() {
@@ -251,6 +2056,973 @@ mod tests {
         println!("OpenCode version: {}", h.version);
     }
 
+    #[tokio::test]
+    async fn test_active_model_follows_latest_message() {
+        let base_url = mock_server(|_, path| match path {
+            "/session/switched/message" => (200, r#"[
+                {"info":{"role":"user","model":{"providerID":"openai","modelID":"gpt-5"}}},
+                {"info":{"role":"assistant","providerID":"anthropic","modelID":"claude-sonnet-4"}},
+                {"info":{"role":"user"}}
+            ]"#.to_string()),
+            "/session/fresh/message" => (200, "[]".to_string()),
+            "/session/fresh" => (200, r#"{"id":"fresh","model":"gpt-5-mini"}"#.to_string()),
+            _ => (404, String::new()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        client.sessions.lock().await.push(Session {
+            id: "switched".to_string(),
+            model: Some("openai/gpt-5".to_string()),
+            ..Session::default()
+        });
+
+        let model = client.active_model("switched").await.unwrap();
+        assert_eq!(model.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(client.cached_sessions().await[0].model, model);
+        assert_eq!(client.active_model("fresh").await.unwrap().as_deref(), Some("gpt-5-mini"));
+        assert!(matches!(client.active_model("gone").await, Err(OpenCodeError::NotFound(_))));
+    }
+
+    // Answers from memory and records what it was asked
+    #[derive(Default)]
+    struct RecordingTransport {
+        seen: Arc<StdMutex<Vec<String>>>,
+    }
+
+    impl RecordingTransport {
+        fn answer(&self, verb: &str, request: reqwest::Request) -> TransportFuture {
+            let agent = request.headers().get(reqwest::header::USER_AGENT).is_some();
+            self.seen.lock().unwrap().push(format!("{} {} {}", verb, request.url().path(), agent));
+            let body = match request.method().as_str() {
+                "GET" => r#"[{"id":"ses_1","title":"Via transport"}]"#,
+                _ => "true",
+            };
+            Box::pin(async move { Ok(reqwest::Response::from(http::Response::new(body))) })
+        }
+    }
+
+    impl Transport for RecordingTransport {
+        fn get(&self, request: reqwest::Request) -> TransportFuture {
+            self.answer("get", request)
+        }
+
+        fn post(&self, request: reqwest::Request) -> TransportFuture {
+            self.answer("post", request)
+        }
+
+        fn delete(&self, request: reqwest::Request) -> TransportFuture {
+            self.answer("delete", request)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport_carries_requests() {
+        let transport = RecordingTransport::default();
+        let seen = transport.seen.clone();
+        let client = OpenCodeClient::with_transport("http://opencode.invalid", transport);
+
+        let sessions = client.list_sessions().await.unwrap();
+        assert_eq!(sessions[0].title.as_deref(), Some("Via transport"));
+        assert!(client.delete_session("ses_1").await.unwrap());
+        assert_eq!(*seen.lock().unwrap(), vec!["get /session true", "delete /session/ses_1 true"]);
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_reports_each_endpoint() {
+        let base_url = mock_server(|_, path| match path {
+            "/global/health" => (200, r#"{"healthy":true,"version":"1.0.0"}"#.to_string()),
+            "/session" => (500, "boom".to_string()),
+            "/agent" => (200, "[]".to_string()),
+            _ => (404, String::new()),
+        })
+        .await;
+        let report = OpenCodeClient::new(&base_url).deep_health().await;
+        assert!(!report.healthy);
+        let ok: Vec<(&str, bool)> = report.endpoints.iter().map(|e| (e.endpoint.as_str(), e.ok)).collect();
+        assert_eq!(ok, vec![("/global/health", true), ("/session", false), ("/agent", true)]);
+        assert!(report.endpoints[1].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_session_cache() {
+        let client = OpenCodeClient::new("http://localhost:4096");
+        let clone = client.clone();
+        client.sessions.lock().await.push(Session {
+            id: "ses_1".to_string(),
+            ..Session::default()
+        });
+        let cached = clone.cached_sessions().await;
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].id, "ses_1");
+    }
+
+    #[test]
+    fn test_session_list_accepts_bare_and_wrapped_shapes() {
+        let bare: SessionList = serde_json::from_value(serde_json::json!([
+            { "id": "ses_1", "title": "One", "created_at": "2024-01-01T00:00:00Z", "updated_at": 1704067200000i64 }
+        ]))
+        .unwrap();
+        let bare: Vec<Session> = bare.into();
+        assert_eq!(bare.len(), 1);
+        assert_eq!(bare[0].title.as_deref(), Some("One"));
+        assert_eq!(bare[0].created_at, bare[0].updated_at);
+
+        let wrapped: SessionList = serde_json::from_value(serde_json::json!({
+            "sessions": [{ "id": "ses_2", "version": "0.5.1", "time": { "created": 1 } }]
+        }))
+        .unwrap();
+        let wrapped: Vec<Session> = wrapped.into();
+        assert_eq!(wrapped[0].id, "ses_2");
+        assert_eq!(wrapped[0].title, None);
+        assert_eq!(wrapped[0].extra["version"], "0.5.1");
+        assert_eq!(wrapped[0].extra["time"]["created"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_file_revalidates_with_etag() {
+        let hits = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = hits.clone();
+        let base_url = mock_http(move |request| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if request.to_ascii_lowercase().contains("if-none-match: \"v1\"") {
+                http_response(304, "ETag: \"v1\"\r\n", "")
+            } else {
+                http_response(200, "ETag: \"v1\"\r\n", "hello")
+            }
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        assert_eq!(client.read_file("a.txt").await.unwrap(), "hello");
+        assert_eq!(client.read_file("a.txt").await.unwrap(), "hello");
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        client.clear_file_cache().await;
+        assert_eq!(client.read_file("a.txt").await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_preview_edit_diffs_against_current_content() {
+        let base_url = mock_server(|_, path| match path {
+            "/file/content?path=src/lib.rs" => (200, "fn a() {}\n".to_string()),
+            _ => (404, "{}".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let edit = client.preview_edit("src/lib.rs", "fn a() {}\nfn b() {}\n").await.unwrap();
+        assert_eq!(edit.old_content.as_deref(), Some("fn a() {}\n"));
+        assert_eq!(edit.language.as_deref(), Some("rust"));
+        assert!(edit.diff.unwrap().contains("+fn b() {}"));
+
+        let created = client.preview_edit("src/new.rs", "fn c() {}\n").await.unwrap();
+        assert_eq!(created.old_content, None);
+        assert!(created.diff.unwrap().contains("--- /dev/null"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_stream_applies_ignored_range() {
+        let base_url = mock_server(|_, _| (200, "0123456789".to_string())).await;
+        let client = OpenCodeClient::new(&base_url);
+        let range = FileRange { start: 3, length: Some(4) };
+        let mut stream = client.read_file_stream("big.log", Some(range)).await.unwrap();
+
+        let mut body = Vec::new();
+        while let Some(chunk) = stream.next_chunk().await.unwrap() {
+            body.extend(chunk);
+        }
+        assert_eq!(body, b"3456");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_session_updates_one_cache_entry() {
+        let base_url = mock_server(|_, path| match path {
+            "/session/ses_1" => (200, r#"{"id":"ses_1","title":"Renamed"}"#.to_string()),
+            _ => (404, r#"{"name":"NotFoundError"}"#.to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        *client.sessions.lock().await = ["ses_1", "ses_2"]
+            .iter()
+            .map(|id| Session { id: id.to_string(), ..Session::default() })
+            .collect();
+
+        let session = client.refresh_session("ses_1").await.unwrap();
+        assert_eq!(session.title.as_deref(), Some("Renamed"));
+        assert!(matches!(client.refresh_session("ses_2").await, Err(OpenCodeError::NotFound(_))));
+
+        let cached = client.cached_sessions().await;
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].title.as_deref(), Some("Renamed"));
+    }
+
+    #[tokio::test]
+    async fn test_default_agent_must_exist() {
+        let base_url = mock_server(|_, _| {
+            (200, r#"[{"id":"build","name":"build"},{"id":"plan","name":"Plan"}]"#.to_string())
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        assert_eq!(client.default_agent().await, "build");
+
+        client.set_default_agent("Plan").await.unwrap();
+        assert_eq!(client.default_agent().await, "Plan");
+        assert!(matches!(
+            client.set_default_agent("nope").await,
+            Err(OpenCodeError::InvalidConfig(_))
+        ));
+        assert_eq!(client.default_agent().await, "Plan");
+    }
+
+    #[tokio::test]
+    async fn test_get_file_diff_filters_unfiltered_responses() {
+        let base_url = mock_server(|_, _| {
+            (200, r#"[{"path":"src/a.rs","diff":"@@ a"},{"path":"src/b.rs","diff":"@@ b"}]"#.to_string())
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let diff = client.get_file_diff("ses_1", "src/b.rs").await.unwrap().unwrap();
+        assert_eq!(diff.diff.as_deref(), Some("@@ b"));
+        assert_eq!(diff.language.as_deref(), Some("rust"));
+        assert!(client.get_file_diff("ses_1", "README.md").await.unwrap().is_none());
+    }
+
+    fn event_stream_response(content_type: &'static str, body: &'static str) -> impl Fn(&str) -> String {
+        move |_| {
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                content_type,
+                body.len(),
+                body
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_detect_sse_and_ndjson() {
+        let sse = "id: 1\ndata: {\"type\":\"session.idle\",\"properties\":{\"sessionID\":\"ses_1\"}}\n\n";
+        let ndjson = "{\"type\":\"session.idle\",\"properties\":{\"sessionID\":\"ses_1\"}}\n";
+
+        for (content_type, body) in [("text/event-stream", sse), ("application/x-ndjson", ndjson)] {
+            let base_url = mock_http(event_stream_response(content_type, body)).await;
+            let client = OpenCodeClient::new(&base_url);
+            let mut events = client.events(None).await.unwrap();
+            let event = events.recv().await.unwrap();
+            assert!(matches!(event, ServerEvent::SessionIdle { session_id } if session_id == "ses_1"), "{}", content_type);
+        }
+
+        // A server that labels NDJSON as SSE needs the override
+        let base_url = mock_http(event_stream_response("text/event-stream", ndjson)).await;
+        let client = OpenCodeClient::new(&base_url);
+        client
+            .reconfigure(ClientConfig { event_format: EventFormat::Ndjson, ..ClientConfig::default() })
+            .unwrap();
+        let mut events = client.events(None).await.unwrap();
+        assert!(matches!(events.recv().await, Some(ServerEvent::SessionIdle { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_get_diffs_reports_missing_endpoint_as_unsupported() {
+        let base_url = mock_server(|_, _| (404, "Not Found".to_string())).await;
+        let client = OpenCodeClient::new(&base_url);
+
+        assert!(matches!(client.get_diffs("ses_1").await, Err(OpenCodeError::Unsupported(_))));
+        assert!(matches!(client.get_file_diff("ses_1", "a.rs").await, Err(OpenCodeError::Unsupported(_))));
+    }
+
+    #[tokio::test]
+    async fn test_server_config_keeps_unknown_fields() {
+        let base_url = mock_server(|_, path| match path {
+            "/config" => (
+                200,
+                r#"{"model":"anthropic/claude-sonnet-4","tools":{"bash":false},"experimental":{"hooks":true},"theme":"dark"}"#.to_string(),
+            ),
+            _ => (404, "Not Found".to_string()),
+        })
+        .await;
+        let config = OpenCodeClient::new(&base_url).server_config().await.unwrap();
+        assert_eq!(config.model.as_deref(), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(config.tools.get("bash"), Some(&false));
+        assert_eq!(config.features.get("hooks"), Some(&json!(true)));
+        assert_eq!(config.extra.get("theme"), Some(&json!("dark")));
+
+        let base_url = mock_server(|_, _| (404, "Not Found".to_string())).await;
+        let result = OpenCodeClient::new(&base_url).server_config().await;
+        assert!(matches!(result, Err(OpenCodeError::Unsupported(_))));
+    }
+
+    #[tokio::test]
+    async fn test_logged_responses_still_parse() {
+        let base_url = mock_server(|_, _| (200, r#"[{"path":"src/a.rs","diff":"@@ a"}]"#.to_string())).await;
+        let client = OpenCodeClient::new(&base_url);
+        client.set_log_bodies(true);
+
+        let diffs = client.get_diffs("ses_1").await.unwrap();
+        assert_eq!(diffs[0].path, "src/a.rs");
+    }
+
+    #[test]
+    fn test_sessions_created_between() {
+        let session = |id: &str, created: &str| Session {
+            id: id.to_string(),
+            created_at: Timestamp::parse(created),
+            ..Session::default()
+        };
+        let sessions = vec![
+            session("ses_c", "2024-03-01T00:00:00Z"),
+            session("ses_a", "2024-01-01T00:00:00Z"),
+            session("ses_x", "not a date"),
+            session("ses_b", "2024-02-01T00:00:00Z"),
+        ];
+        let ids = |sessions: Vec<Session>| sessions.into_iter().map(|s| s.id).collect::<Vec<_>>();
+        let bound = |raw: &str| Some(parse_range_bound(raw).unwrap());
+
+        let within = created_between(sessions.clone(), bound("2024-01-01T00:00:00Z"), bound("2024-02-01T00:00:00+00:00"));
+        assert_eq!(ids(within), vec!["ses_a", "ses_b"]);
+        assert_eq!(ids(created_between(sessions.clone(), bound("2024-01-15T00:00:00Z"), None)), vec!["ses_b", "ses_c"]);
+        assert_eq!(ids(created_between(sessions.clone(), None, bound("2024-01-15T00:00:00Z"))), vec!["ses_a"]);
+        assert_eq!(created_between(sessions, None, None).len(), 3);
+        assert!(matches!(parse_range_bound("last week"), Err(OpenCodeError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_session_sort_orders() {
+        let session = |id: &str, title: &str, created: &str, updated: &str| Session {
+            id: id.to_string(),
+            title: Some(title.to_string()),
+            created_at: Timestamp::parse(created),
+            updated_at: Timestamp::parse(updated),
+            ..Session::default()
+        };
+        let mut sessions = vec![
+            session("ses_a", "beta", "2024-01-01T00:00:00Z", "2024-03-01T00:00:00Z"),
+            session("ses_b", "Alpha", "2024-02-01T00:00:00Z", "2024-02-01T00:00:00Z"),
+            session("ses_c", "gamma", "2024-03-01T00:00:00Z", "not a date"),
+        ];
+        let ids = |sessions: &[Session]| sessions.iter().map(|s| s.id.clone()).collect::<Vec<_>>();
+
+        SessionSort::default().sort(&mut sessions, None);
+        assert_eq!(ids(&sessions), vec!["ses_a", "ses_b", "ses_c"]);
+        SessionSort::Created.sort(&mut sessions, Some(false));
+        assert_eq!(ids(&sessions), vec!["ses_a", "ses_b", "ses_c"]);
+        SessionSort::Title.sort(&mut sessions, None);
+        assert_eq!(ids(&sessions), vec!["ses_b", "ses_a", "ses_c"]);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_healthy_retries_until_up() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let seen = attempts.clone();
+        let base_url = mock_http(move |_| {
+            match seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => http_response(503, "", "starting"),
+                1 => http_response(200, "", r#"{"healthy":false,"version":"0.6.0"}"#),
+                _ => http_response(200, "", r#"{"healthy":true,"version":"0.6.0"}"#),
+            }
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let health = client.wait_until_healthy(Duration::from_secs(5)).await.unwrap();
+        assert!(health.healthy);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let result = OpenCodeClient::new(&closed).wait_until_healthy(Duration::from_millis(300)).await;
+        assert!(matches!(result, Err(OpenCodeError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_sessions_reports_each_id() {
+        let base_url = mock_server(|method, path| match (method, path) {
+            ("DELETE", "/session/ses_1" | "/session/ses_3") => (200, "true".to_string()),
+            _ => (404, r#"{"name":"NotFoundError"}"#.to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        *client.sessions.lock().await = ["ses_1", "ses_2", "ses_3"]
+            .iter()
+            .map(|id| Session { id: id.to_string(), ..Session::default() })
+            .collect();
+
+        let results = client
+            .delete_sessions(vec!["ses_1".into(), "missing".into(), "ses_3".into()])
+            .await;
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["ses_1", "missing", "ses_3"]);
+        assert!(results[0].deleted && results[2].deleted);
+        assert!(!results[1].deleted);
+        assert!(results[1].error.is_some());
+
+        let cached: Vec<String> = client.cached_sessions().await.into_iter().map(|s| s.id).collect();
+        assert_eq!(cached, vec!["ses_2"]);
+    }
+
+    #[tokio::test]
+    async fn test_message_count_uses_header_then_list() {
+        let hits = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let seen = hits.clone();
+        let base_url = mock_http(move |request| {
+            seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let path = request.split(' ').nth(1).unwrap_or_default();
+            match path {
+                "/session/counted/message?limit=0" => http_response(200, "X-Total-Count: 42\r\n", "[]"),
+                "/session/listed/message?limit=0" => http_response(200, "", "[]"),
+                "/session/listed/message" => http_response(200, "", r#"[{"id":"a"},{"id":"b"}]"#),
+                _ => http_response(404, "", "{}"),
+            }
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        assert_eq!(client.message_count("counted").await.unwrap(), 42);
+        assert_eq!(client.message_count("listed").await.unwrap(), 2);
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        assert_eq!(client.message_count("counted").await.unwrap(), 42);
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert!(client.message_count("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_active_generation_follows_status_and_events() {
+        let base_url = mock_server(|_, path| match path {
+            "/session/busy/message" => (200, r#"[
+                {"id":"m1","role":"user","content":"hi","parts":[]},
+                {"id":"m2","role":"assistant","content":"Wor","parts":[],"status":{"type":"pending"}}
+            ]"#.to_string()),
+            "/session/quiet/message" => (200, r#"[
+                {"id":"m3","role":"assistant","content":"Done","parts":[]}
+            ]"#.to_string()),
+            _ => (200, "[]".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let active = client.active_generation("busy").await.unwrap().unwrap();
+        assert_eq!(active.id, "m2");
+        assert!(client.active_generation("quiet").await.unwrap().is_none());
+        assert!(client.active_generation("empty").await.unwrap().is_none());
+
+        client.generating.lock().unwrap().insert("quiet".to_string());
+        assert_eq!(client.active_generation("quiet").await.unwrap().unwrap().id, "m3");
+    }
+
+    #[tokio::test]
+    async fn test_diff_paths_are_workspace_relative() {
+        let base_url = mock_server(|_, _| {
+            (200, r#"[
+                {"path":"/work/app/src/main.rs","old_content":null,"new_content":null,"diff":null,"language":null},
+                {"path":"src\\lib.rs","old_content":null,"new_content":null,"diff":null,"language":null}
+            ]"#.to_string())
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        client.set_workspace_root(Some("/work/app/"));
+
+        let paths: Vec<String> = client.get_diffs("ses").await.unwrap().into_iter().map(|d| d.path).collect();
+        assert_eq!(paths, vec!["src/main.rs", "src/lib.rs"]);
+        let found = client.get_file_diff("ses", "/work/app/src/lib.rs").await.unwrap();
+        assert_eq!(found.unwrap().path, "src/lib.rs");
+        assert_eq!(client.to_absolute("src/lib.rs"), "/work/app/src/lib.rs");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_surfaces_and_retries_gets() {
+        let hits = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let seen = hits.clone();
+        let base_url = mock_http(move |request| {
+            let hit = seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if request.starts_with("GET /session ") && hit == 1 {
+                http_response(200, "", "[]")
+            } else {
+                http_response(429, "Retry-After: 0\r\n", "{}")
+            }
+        })
+        .await;
+
+        let client = OpenCodeClient::new(&base_url);
+        match client.list_sessions().await {
+            Err(OpenCodeError::RateLimited { retry_after }) => assert_eq!(retry_after, Some(Duration::ZERO)),
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+
+        client.reconfigure(ClientConfig { rate_limit_retries: 2, ..ClientConfig::default() }).unwrap();
+        assert!(client.list_sessions().await.unwrap().is_empty());
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 2);
+        // POSTs are never repeated
+        assert!(matches!(client.abort_session("ses").await, Err(OpenCodeError::RateLimited { .. })));
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_post_is_not_repeated_with_refresh_hook() {
+        let hits = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let seen = hits.clone();
+        let base_url = mock_http(move |_| {
+            seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            http_response(429, "Retry-After: 0\r\n", "{}")
+        })
+        .await;
+
+        let client = OpenCodeClient::with_config(
+            &base_url,
+            ClientConfig {
+                token_refresh: Some(TokenRefresh::new(|| async { Ok("fresh".to_string()) })),
+                ..ClientConfig::default()
+            },
+        );
+        assert!(matches!(client.abort_session("ses").await, Err(OpenCodeError::RateLimited { .. })));
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_send_skips_the_previous_reply() {
+        let polls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let sent = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (polled, posted) = (polls.clone(), sent.clone());
+        let base_url = mock_http(move |request| {
+            let request_line = request.lines().next().unwrap_or_default();
+            if request_line.starts_with("POST /session/ses/message") {
+                posted.store(true, std::sync::atomic::Ordering::SeqCst);
+                return http_response(200, "", r#"{"id":"u2","role":"user","content":"again","parts":[]}"#);
+            }
+            if !request_line.starts_with("GET /session/ses/message") {
+                return http_response(404, "", "{}");
+            }
+            let previous = r#"{"id":"u1","role":"user","content":"hi","parts":[]},
+                {"id":"a1","role":"assistant","content":"hello","parts":[]}"#;
+            // The new turn only shows up on the third poll after sending
+            let body = if posted.load(std::sync::atomic::Ordering::SeqCst)
+                && polled.fetch_add(1, std::sync::atomic::Ordering::SeqCst) >= 2
+            {
+                format!(
+                    r#"[{},{{"id":"u2","role":"user","content":"again","parts":[]}},
+                    {{"id":"a2","role":"assistant","content":"hello again","parts":[],"status":"completed"}}]"#,
+                    previous
+                )
+            } else {
+                format!("[{}]", previous)
+            };
+            http_response(200, "", &body)
+        })
+        .await;
+
+        let client = OpenCodeClient::new(&base_url);
+        let reply = client
+            .send_message_blocking("ses", "again", None, Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(reply.id, "a2");
+        assert!(sent.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_refreshes_token_once() {
+        let base_url = mock_http(|request| {
+            if request.to_lowercase().contains("authorization: bearer fresh") {
+                http_response(200, "", "[]")
+            } else {
+                http_response(401, "", "{}")
+            }
+        })
+        .await;
+
+        let client = OpenCodeClient::with_config(
+            &base_url,
+            ClientConfig { auth_token: Some("stale".to_string()), ..ClientConfig::default() },
+        );
+        assert!(matches!(client.list_sessions().await, Err(OpenCodeError::Unauthorized)));
+
+        let refreshes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = refreshes.clone();
+        let refresh = TokenRefresh::new(move || {
+            counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Ok("fresh".to_string()) }
+        });
+        client
+            .reconfigure(ClientConfig {
+                auth_token: Some("stale".to_string()),
+                token_refresh: Some(refresh),
+                ..ClientConfig::default()
+            })
+            .unwrap();
+        assert!(client.list_sessions().await.unwrap().is_empty());
+        assert_eq!(client.config().auth_token.as_deref(), Some("fresh"));
+        // The new token sticks, so later requests don't refresh again
+        assert!(client.list_sessions().await.unwrap().is_empty());
+        assert_eq!(refreshes.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        client
+            .reconfigure(ClientConfig {
+                auth_token: Some("stale".to_string()),
+                token_refresh: Some(TokenRefresh::new(|| async { Err("expired".to_string()) })),
+                ..ClientConfig::default()
+            })
+            .unwrap();
+        assert!(matches!(client.list_sessions().await, Err(OpenCodeError::Unauthorized)));
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_the_token_unless_cleared() {
+        let client = OpenCodeClient::with_config(
+            "http://127.0.0.1:1",
+            ClientConfig { auth_token: Some("secret".to_string()), ..ClientConfig::default() },
+        );
+        // What the UI sends back after reading the config
+        let from_ui: ClientConfig = serde_json::from_value(serde_json::to_value(client.config()).unwrap()).unwrap();
+        client.reconfigure(ClientConfig { log_bodies: true, ..from_ui }).unwrap();
+        assert_eq!(client.config().auth_token.as_deref(), Some("secret"));
+        assert!(client.config().log_bodies);
+
+        client
+            .reconfigure(ClientConfig { auth_token: Some(String::new()), ..ClientConfig::default() })
+            .unwrap();
+        assert_eq!(client.config().auth_token, None);
+    }
+
+    #[tokio::test]
+    async fn test_all_messages_skips_the_message_limit() {
+        let base_url = mock_server(|_, path| match path {
+            "/session/ses/message?limit=5" => (200, r#"[{"id":"m5","role":"user","content":"","parts":[]}]"#.to_string()),
+            "/session/ses/message" => (200, r#"[{"id":"m1","role":"user","content":"","parts":[]},
+                {"id":"m5","role":"user","content":"","parts":[]}]"#.to_string()),
+            _ => (500, "{}".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::with_config(
+            &base_url,
+            ClientConfig { max_message_limit: 5, ..ClientConfig::default() },
+        );
+
+        assert_eq!(client.get_messages("ses", Some(100), None).await.unwrap().len(), 1);
+        assert_eq!(client.all_messages("ses").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_previews_caches_latest_message() {
+        let base_url = mock_server(|_, path| match path {
+            // Only the last message is asked for
+            "/session/ses_1/message?limit=1" => (200, r#"[
+                {"id":"msg_2","role":"assistant","content":"hello","parts":[]}
+            ]"#.to_string()),
+            "/session/ses_2/message?limit=1" => (200, "[]".to_string()),
+            _ => (500, "{}".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let ids = ["ses_1", "ses_2", "ses_3"].map(str::to_string).to_vec();
+        assert_eq!(client.prefetch_previews(ids, 2).await, 2);
+        assert_eq!(client.session_preview("ses_1").unwrap().id, "msg_2");
+        assert!(client.session_preview("ses_2").is_none());
+        assert!(client.session_preview("ses_3").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_total_usage_sums_sessions() {
+        let base_url = mock_server(|_, path| match path {
+            "/session" => (200, r#"[{"id":"ses_1"},{"id":"ses_2"},{"id":"ses_3"},{"id":"ses_4"}]"#.to_string()),
+            "/session/ses_1/message" => (200, r#"[{"tokens":{"input":10,"output":5},"cost":0.5}]"#.to_string()),
+            "/session/ses_2/message" => (200, r#"[{"info":{"tokens":{"input":1,"output":1},"cost":0.25}}]"#.to_string()),
+            "/session/ses_3/message" => (200, r#"[{"role":"user"}]"#.to_string()),
+            _ => (500, "{}".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let total = client.total_usage().await.unwrap();
+        assert_eq!(total.sessions, 4);
+        assert_eq!(total.total_tokens, 17);
+        assert_eq!(total.usage.cost, 0.75);
+        assert_eq!(total.sessions_without_usage, 1);
+        assert_eq!(total.failed, vec!["ses_4"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_message_parts_pages() {
+        let base_url = mock_http(|request| {
+            let path = request.lines().next().unwrap_or_default().split(' ').nth(1).unwrap_or_default();
+            let part = |n: usize| format!(r#"{{"kind":"text","content":"part {}","source":null}}"#, n);
+            match path {
+                "/session/paged/message/msg_1/part?offset=2&limit=2" => {
+                    http_response(200, "X-Total-Count: 5\r\n", &format!("[{},{}]", part(2), part(3)))
+                }
+                p if p.starts_with("/session/old/message/msg_1/part") => http_response(404, "", "{}"),
+                _ => {
+                    let parts: Vec<String> = (0..5).map(part).collect();
+                    http_response(
+                        200,
+                        "",
+                        &format!(r#"{{"id":"msg_1","role":"assistant","content":"","parts":[{}]}}"#, parts.join(",")),
+                    )
+                }
+            }
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let page = client.get_message_parts("paged", "msg_1", 2, 2).await.unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.next_offset, Some(4));
+        assert_eq!(page.parts[0].content.as_deref(), Some("part 2"));
+
+        let page = client.get_message_parts("old", "msg_1", 3, 10).await.unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.next_offset, None);
+        assert_eq!(page.parts.len(), 2);
+        assert_eq!(page.parts[0].content.as_deref(), Some("part 3"));
+    }
+
+    #[tokio::test]
+    async fn test_is_within_workspace_uses_server_path() {
+        let base_url = mock_server(|_, path| match path {
+            "/path" => (404, "{}".to_string()),
+            "/config" => (200, r#"{"directory":"/nonexistent/app"}"#.to_string()),
+            _ => (500, "{}".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let check = client.is_within_workspace("src/../tests").await.unwrap();
+        assert_eq!(check.root.as_deref(), Some("/nonexistent/app"));
+        assert_eq!(check.path, "/nonexistent/app/tests");
+        assert_eq!(check.within, Some(true));
+        let check = client.is_within_workspace("/nonexistent/other").await.unwrap();
+        assert_eq!(check.within, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_search_sessions_falls_back_to_filtering() {
+        let base_url = mock_server(|_, path| match path {
+            p if p.starts_with("/session/search?q=deploy&limit=1&offset=0") => {
+                (200, r#"{"sessions":[{"id":"ses_9","title":"Deploy"}]}"#.to_string())
+            }
+            p if p.starts_with("/session/search") => (404, "{}".to_string()),
+            _ => (200, r#"[
+                {"id":"ses_1","title":"Fix login"},
+                {"id":"ses_2","title":"Refactor"},
+                {"id":"ses_3","title":"Login tests"}
+            ]"#.to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+
+        let page = client.search_sessions("deploy", 1, 0).await.unwrap();
+        assert!(page.server_side);
+        assert_eq!((page.sessions[0].id.as_str(), page.next_offset), ("ses_9", Some(1)));
+
+        let page = client.search_sessions("LOGIN", 1, 0).await.unwrap();
+        assert!(!page.server_side);
+        assert_eq!((page.sessions[0].id.as_str(), page.next_offset), ("ses_1", Some(1)));
+        let page = client.search_sessions("login", 5, 1).await.unwrap();
+        assert_eq!((page.sessions[0].id.as_str(), page.next_offset), ("ses_3", None));
+    }
+
+    #[test]
+    fn test_acknowledgement_shapes() {
+        assert!(parse_acknowledgement("true").unwrap());
+        assert!(!parse_acknowledgement("false").unwrap());
+        assert!(parse_acknowledgement(r#"{"success":true}"#).unwrap());
+        assert!(!parse_acknowledgement(r#"{"ok":false}"#).unwrap());
+        assert!(parse_acknowledgement("").unwrap());
+        assert!(parse_acknowledgement("  \n").unwrap());
+        assert!(matches!(parse_acknowledgement(r#"{"error":"busy"}"#), Err(OpenCodeError::Server(m)) if m == "busy"));
+        assert!(parse_acknowledgement(r#"{"id":"ses"}"#).is_err());
+        assert!(parse_acknowledgement("<html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delete_accepts_no_content() {
+        let base_url = mock_http(|request| {
+            if request.starts_with("DELETE") {
+                "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n".to_string()
+            } else {
+                http_response(200, "", r#"{"success":true}"#)
+            }
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        assert!(client.delete_session("ses").await.unwrap());
+        assert!(client.abort_session("ses").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_abort_all_falls_back_to_cached_sessions() {
+        let base_url = mock_server(|method, path| match (method, path) {
+            ("POST", "/session/ses_1/abort") => (200, "true".to_string()),
+            _ => (500, "{}".to_string()),
+        })
+        .await;
+        let client = OpenCodeClient::new(&base_url);
+        assert!(client.abort_all().await.is_err());
+
+        *client.sessions.lock().await = ["ses_2", "ses_1"]
+            .iter()
+            .map(|id| Session { id: id.to_string(), ..Session::default() })
+            .collect();
+        let results = client.abort_all().await.unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["ses_1", "ses_2"]);
+        assert!(results[0].aborted);
+        assert!(!results[1].aborted && results[1].error.is_some());
+    }
+
+    #[test]
+    fn test_provider_list_flattens_into_models() {
+        let list: ProviderList = serde_json::from_value(serde_json::json!({
+            "providers": [
+                { "id": "openai", "models": {
+                    "gpt-5": { "id": "gpt-5", "limit": { "context": 400000, "output": 128000 } },
+                    "o3": { "name": "o3" }
+                } },
+                { "id": "anthropic", "name": "Anthropic" }
+            ],
+            "default": { "openai": "gpt-5" }
+        }))
+        .unwrap();
+        let models: Vec<ModelInfo> = list.into();
+        assert_eq!(models.len(), 2);
+        assert_eq!((models[0].provider.as_str(), models[0].id.as_str()), ("openai", "gpt-5"));
+        assert_eq!(models[0].context_window, Some(400000));
+        assert_eq!(models[1].id, "o3");
+        assert_eq!(models[1].context_window, None);
+    }
+
+    #[test]
+    fn test_send_message_request_carries_idempotency_key() {
+        let client = OpenCodeClient::new("http://localhost:4096");
+        let request = client
+            .send_message_request("ses_1", "hi", None, Some("key-1"))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[IDEMPOTENCY_KEY_HEADER], "key-1");
+        let body: serde_json::Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["idempotencyKey"], "key-1");
+
+        let request = client.send_message_request("ses_1", "hi", None, None).build().unwrap();
+        assert!(request.headers().get(IDEMPOTENCY_KEY_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_requests_carry_the_command_request_id() {
+        let client = OpenCodeClient::new("http://localhost:4096");
+        let untagged = client.events_request(None).build().unwrap();
+        assert!(untagged.headers().get(REQUEST_ID_HEADER).is_none());
+
+        let tagged = crate::opencode::trace::traced("test", async {
+            Ok::<_, String>(client.events_request(None).build().unwrap())
+        })
+        .await
+        .unwrap();
+        assert_eq!(tagged.value.headers()[REQUEST_ID_HEADER], tagged.request_id.as_str());
+    }
+
+    #[test]
+    fn test_events_request_passes_since_through() {
+        let client = OpenCodeClient::new("http://localhost:4096");
+        let request = client.events_request(Some("evt_42")).build().unwrap();
+        assert_eq!(request.url().as_str(), "http://localhost:4096/event?since=evt_42");
+
+        let request = client.events_request(None).build().unwrap();
+        assert_eq!(request.url().query(), None);
+    }
+
+    fn message(id: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            role: "assistant".to_string(),
+            content: String::new(),
+            parts: Vec::new(),
+            created_at: Timestamp::parse("2024-01-01T00:00:00Z"),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn test_message_status_is_lenient() {
+        let parse = |status: &str| {
+            let body = format!(r#"{{"id":"msg_1","role":"assistant","content":"","parts":[],"status":{}}}"#, status);
+            serde_json::from_str::<Message>(&body).unwrap()
+        };
+        assert!(parse(r#""Aborted""#).is_aborted());
+        assert!(parse(r#"{"type":"aborted"}"#).is_aborted());
+        assert_eq!(parse(r#""completed""#).status.as_deref(), Some("completed"));
+        assert_eq!(parse("42").status, None);
+        assert_eq!(parse("null").status, None);
+
+        let missing: Message = serde_json::from_str(r#"{"id":"msg_1","role":"assistant","content":"","parts":[]}"#).unwrap();
+        assert!(!missing.is_aborted());
+    }
+
+    #[test]
+    fn test_message_content_string_or_blocks() {
+        let text: Message = serde_json::from_str(
+            r#"{"id":"msg_1","role":"assistant","content":"plain","parts":[{"kind":"text","content":"plain","source":null}]}"#,
+        )
+        .unwrap();
+        assert_eq!(text.content, "plain");
+        assert_eq!(text.parts.len(), 1);
+
+        let blocks: Message = serde_json::from_str(
+            r#"{"id":"msg_2","role":"assistant","content":[
+                {"type":"text","text":"Reading "},
+                {"type":"tool_use","name":"read","input":{"path":"a.rs"}},
+                {"type":"text","text":"the file"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(blocks.content, "Reading the file");
+        assert_eq!(blocks.parts.len(), 1);
+        assert_eq!(blocks.parts[0].kind, "tool_use");
+        assert_eq!(blocks.parts[0].source.as_deref(), Some("read"));
+        assert!(blocks.parts[0].content.as_deref().unwrap().contains(r#""path":"a.rs""#));
+    }
+
+    #[test]
+    fn test_message_response_shapes() {
+        let one = r#"{"id":"msg_1","role":"assistant","content":"hi","parts":[]}"#;
+        let parsed = parse_message_response(StatusCode::OK, one).unwrap();
+        assert_eq!(parsed.len(), 1);
+        let parsed = parse_message_response(StatusCode::OK, &format!("[{}, {}]", one, one)).unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let err = parse_message_response(StatusCode::OK, r#"{"error":"model not found"}"#).unwrap_err();
+        assert!(matches!(err, OpenCodeError::Server(ref m) if m == "model not found"));
+        let err = parse_message_response(
+            StatusCode::BAD_REQUEST,
+            r#"{"error":{"name":"ProviderAuthError","data":{"message":"bad key"}}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, OpenCodeError::Server(ref m) if m == "bad key"));
+
+        let err = parse_message_response(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>").unwrap_err();
+        assert!(matches!(err, OpenCodeError::Server(ref m) if m.contains("502") && m.contains("Bad Gateway")));
+        let err = parse_message_response(StatusCode::INTERNAL_SERVER_ERROR, r#"{"oops":true}"#).unwrap_err();
+        assert!(matches!(err, OpenCodeError::Server(ref m) if m.starts_with("500")));
+        let err = parse_message_response(StatusCode::OK, r#"{"unexpected":1}"#).unwrap_err();
+        assert!(matches!(err, OpenCodeError::Server(ref m) if m.starts_with("unexpected message shape")));
+    }
+
+    #[test]
+    fn test_messages_after_cursor() {
+        let page = vec![message("m1"), message("m2"), message("m3")];
+        let ids = |messages: Vec<Message>| messages.into_iter().map(|m| m.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(messages_after(page.clone(), Some("m1"))), vec!["m2", "m3"]);
+        assert_eq!(ids(messages_after(page.clone(), Some("m3"))), Vec::<String>::new());
+        assert_eq!(ids(messages_after(page.clone(), Some("m0"))), vec!["m1", "m2", "m3"]);
+
+        let empty = MessagePage::new(Vec::new(), Some("m3"));
+        assert_eq!(empty.latest_id.as_deref(), Some("m3"));
+        let next = MessagePage::new(messages_after(page, Some("m1")), Some("m1"));
+        assert_eq!(next.latest_id.as_deref(), Some("m3"));
+    }
+
     #[tokio::test]
     async fn test_list_sessions() {
         let client = OpenCodeClient::new("http://localhost:4096");
@@ -1,81 +1,860 @@
 // OpenCode Tauri Commands
 
-use crate::opencode::{OpenCodeClient, Session, Message, FileDiff};
-use tauri::State;
+use crate::opencode::{OpenCodeClient, Session, Message, FileDiff, HealthResponse};
+use crate::opencode::activity::{SessionActivity, DEFAULT_ACTIVE_WINDOW};
+use crate::opencode::agents::AgentDetail;
+use crate::opencode::client::{MessagePage, MessagePartsPage, ModelInfo, ServerConfig, SessionDeleteResult, SessionSearchPage, SessionSort, WorkspaceCheck};
+use crate::opencode::config::ClientConfig;
+use crate::opencode::diff::{FileStats, PollBackoff, DEFAULT_DIFF_POLL_INTERVAL};
+use crate::opencode::error::OpenCodeError;
+use crate::opencode::events::{EventFilter, ServerEvent, DEFAULT_EVENT_LOG_CAPACITY};
+use crate::opencode::files::{take_utf8, FileRange};
+use crate::opencode::health::{DeepHealth, HealthStatus};
+use crate::opencode::idle::{IdleTracker, IDLE_POLL_INTERVAL};
+use crate::opencode::logs::LogLine;
+use crate::opencode::replay::{replay_delays, ReplayComplete, ReplayMessage, DEFAULT_REPLAY_SPEED};
+use crate::opencode::search::SearchMatch;
+use crate::opencode::trace::{traced, Traced};
+use crate::opencode::usage::TotalUsage;
+use crate::opencode::version::ServerFeature;
+use crate::opencode::workspace::WorkspaceStatus;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const DEFAULT_REPLY_TIMEOUT_SECS: u64 = 300;
+const DEFAULT_LOG_LINES: usize = 200;
+const DEFAULT_HEALTH_WAIT_SECS: u64 = 30;
+const DEFAULT_SESSION_SEARCH_LIMIT: usize = 50;
+const DEFAULT_MESSAGE_PARTS_LIMIT: usize = 20;
+const DEFAULT_PREVIEW_CONCURRENCY: usize = 6;
+
+// Quiet period after the last diff-related event before re-fetching
+const DIFF_REFRESH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Serialize, Clone)]
+pub struct DiffsUpdated {
+    pub session_id: String,
+    pub diffs: Vec<FileDiff>,
+}
+
+/// `supported` is false when the server has no diff endpoint; `diffs` is
+/// then empty rather than the command failing.
+#[derive(Serialize, Clone)]
+pub struct SessionDiffs {
+    pub diffs: Vec<FileDiff>,
+    pub supported: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileChunk {
+    pub path: String,
+    /// Byte offset of `data` within the file.
+    pub offset: u64,
+    pub data: String,
+    pub done: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ServerFeatures {
+    pub version: String,
+    pub features: Vec<ServerFeature>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AutoAborted {
+    pub session_id: String,
+    pub idle_secs: u64,
+}
 
 // Global OpenCode client instance
 #[tauri::command]
-pub async fn opencode_health(client: State<'_, OpenCodeClient>) -> Result<serde_json::Value, String> {
-    let health = client.health().await
-        .map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(health).unwrap())
+pub async fn opencode_health(client: State<'_, OpenCodeClient>) -> Result<Traced<serde_json::Value>, String> {
+    let health = traced("opencode_health", client.health()).await?;
+    Ok(health.map(|health| serde_json::to_value(health).unwrap()))
+}
+
+/// Health of each endpoint the monitor relies on, not just `/global/health`.
+#[tauri::command]
+pub async fn opencode_deep_health(client: State<'_, OpenCodeClient>) -> Result<Traced<DeepHealth>, String> {
+    // Failures are in the report; traced only for the request ids
+    traced("opencode_deep_health", async { Ok::<_, OpenCodeError>(client.deep_health().await) }).await
+}
+
+/// Resolves once the server reports healthy, for a "connecting..." screen at
+/// startup.
+#[tauri::command]
+pub async fn opencode_wait_healthy(
+    client: State<'_, OpenCodeClient>,
+    timeout_secs: Option<u64>
+) -> Result<Traced<HealthResponse>, String> {
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_HEALTH_WAIT_SECS));
+    traced("opencode_wait_healthy", client.wait_until_healthy(timeout)).await
+}
+
+#[tauri::command]
+pub async fn opencode_server_features(client: State<'_, OpenCodeClient>) -> Result<Traced<ServerFeatures>, String> {
+    let health = traced("opencode_server_features", client.health()).await?;
+    Ok(health.map(|health| ServerFeatures {
+        features: health.features(),
+        version: health.version,
+    }))
+}
+
+#[tauri::command]
+pub async fn opencode_list_sessions(client: State<'_, OpenCodeClient>) -> Result<Traced<Vec<Session>>, String> {
+    traced("opencode_list_sessions", client.list_sessions()).await
+}
+
+/// Sessions sorted here rather than in each view. Defaults to the most
+/// recently updated first.
+#[tauri::command]
+pub async fn opencode_list_sessions_sorted(
+    client: State<'_, OpenCodeClient>,
+    sort_by: Option<SessionSort>,
+    descending: Option<bool>
+) -> Result<Traced<Vec<Session>>, String> {
+    let sessions = traced("opencode_list_sessions_sorted", client.list_sessions()).await?;
+    Ok(sessions.map(|mut sessions| {
+        sort_by.unwrap_or_default().sort(&mut sessions, descending);
+        sessions
+    }))
+}
+
+#[tauri::command]
+pub async fn opencode_search_sessions(
+    client: State<'_, OpenCodeClient>,
+    query: &str,
+    limit: Option<usize>,
+    offset: Option<usize>
+) -> Result<Traced<SessionSearchPage>, String> {
+    let limit = limit.unwrap_or(DEFAULT_SESSION_SEARCH_LIMIT);
+    traced("opencode_search_sessions", client.search_sessions(query, limit, offset.unwrap_or(0))).await
+}
+
+#[tauri::command]
+pub async fn opencode_pin_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<bool, String> {
+    client.set_pinned(session_id, true).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn opencode_unpin_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<bool, String> {
+    client.set_pinned(session_id, false).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn opencode_list_pinned_sessions(client: State<'_, OpenCodeClient>) -> Result<Traced<Vec<Session>>, String> {
+    traced("opencode_list_pinned_sessions", client.list_pinned_sessions()).await
+}
+
+#[tauri::command]
+pub async fn opencode_list_sessions_by_tag(client: State<'_, OpenCodeClient>, tag: &str) -> Result<Traced<Vec<Session>>, String> {
+    traced("opencode_list_sessions_by_tag", client.list_sessions_by_tag(tag)).await
+}
+
+/// Sessions created between two RFC3339 times, oldest first; either bound
+/// may be omitted.
+#[tauri::command]
+pub async fn opencode_sessions_in_range(
+    client: State<'_, OpenCodeClient>,
+    from: Option<&str>,
+    to: Option<&str>
+) -> Result<Traced<Vec<Session>>, String> {
+    traced("opencode_sessions_in_range", client.sessions_in_range(from, to)).await
 }
 
 #[tauri::command]
-pub async fn opencode_list_sessions(client: State<'_, OpenCodeClient>) -> Result<Vec<Session>, String> {
-    client.list_sessions().await
+pub async fn opencode_add_tag(client: State<'_, OpenCodeClient>, session_id: &str, tag: &str) -> Result<Vec<String>, String> {
+    client.add_tag(session_id, tag)
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn opencode_create_session(client: State<'_, OpenCodeClient>, title: Option<&str>) -> Result<Session, String> {
-    client.create_session(title).await
+pub async fn opencode_remove_tag(client: State<'_, OpenCodeClient>, session_id: &str, tag: &str) -> Result<Vec<String>, String> {
+    client.remove_tag(session_id, tag)
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn opencode_get_client_config(client: State<'_, OpenCodeClient>) -> Result<ClientConfig, String> {
+    Ok(client.config())
+}
+
+#[tauri::command]
+pub async fn opencode_reconfigure(client: State<'_, OpenCodeClient>, config: ClientConfig) -> Result<ClientConfig, String> {
+    client.reconfigure(config)
+        .map_err(|e| e.to_string())?;
+    Ok(client.config())
+}
+
+#[tauri::command]
+pub async fn opencode_session_summary(
+    client: State<'_, OpenCodeClient>,
+    active_window_secs: Option<u64>
+) -> Result<Traced<SessionActivity>, String> {
+    let window = active_window_secs.map(Duration::from_secs).unwrap_or(DEFAULT_ACTIVE_WINDOW);
+    traced("opencode_session_summary", client.session_activity_summary(window)).await
+}
+
+/// Toggles debug logging of request and response bodies.
+#[tauri::command]
+pub async fn opencode_set_log_bodies(client: State<'_, OpenCodeClient>, enabled: bool) -> Result<bool, String> {
+    client.set_log_bodies(enabled);
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn opencode_list_models(client: State<'_, OpenCodeClient>) -> Result<Traced<Vec<ModelInfo>>, String> {
+    traced("opencode_list_models", client.list_models()).await
+}
+
+#[tauri::command]
+pub async fn opencode_server_config(client: State<'_, OpenCodeClient>) -> Result<Traced<ServerConfig>, String> {
+    traced("opencode_server_config", client.server_config()).await
+}
+
+#[tauri::command]
+pub async fn opencode_create_session(client: State<'_, OpenCodeClient>, title: Option<&str>) -> Result<Traced<Session>, String> {
+    traced("opencode_create_session", client.create_session(title)).await
+}
+
+/// Sets the title template for sessions created without a title; omitting
+/// `template` restores "New Session". Returns the template now in use.
+#[tauri::command]
+pub async fn opencode_set_title_template(client: State<'_, OpenCodeClient>, template: Option<&str>) -> Result<String, String> {
+    Ok(client.set_title_template(template))
+}
+
+#[tauri::command]
+pub async fn opencode_refresh_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<Session>, String> {
+    traced("opencode_refresh_session", client.refresh_session(session_id)).await
+}
+
+#[tauri::command]
+pub async fn opencode_fork_session(
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    title: Option<&str>
+) -> Result<Traced<Session>, String> {
+    traced("opencode_fork_session", client.fork_session(session_id, title)).await
+}
+
 #[tauri::command]
 pub async fn opencode_send_message(
     client: State<'_, OpenCodeClient>,
     session_id: &str,
     message: &str,
-    model: Option<&str>
-) -> Result<Vec<Message>, String> {
-    client.send_message(session_id, message, model).await
-        .map_err(|e| e.to_string())
+    model: Option<&str>,
+    idempotency_key: Option<&str>
+) -> Result<Traced<Vec<Message>>, String> {
+    traced("opencode_send_message", client.send_message(session_id, message, model, idempotency_key)).await
+}
+
+#[tauri::command]
+pub async fn opencode_send_message_blocking(
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    message: &str,
+    model: Option<&str>,
+    timeout_secs: Option<u64>
+) -> Result<Traced<Message>, String> {
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_REPLY_TIMEOUT_SECS));
+    traced("opencode_send_message_blocking", client.send_message_blocking(session_id, message, model, timeout)).await
 }
 
 #[tauri::command]
 pub async fn opencode_get_messages(
     client: State<'_, OpenCodeClient>,
     session_id: &str,
-    limit: Option<i32>
-) -> Result<Vec<Message>, String> {
-    client.get_messages(session_id, limit).await
-        .map_err(|e| e.to_string())
+    limit: Option<i32>,
+    after: Option<&str>
+) -> Result<Traced<MessagePage>, String> {
+    let (_, clamped) = client.config().message_limit(limit);
+    let messages = traced("opencode_get_messages", client.get_messages(session_id, limit, after)).await?;
+    Ok(messages.map(|messages| MessagePage { clamped, ..MessagePage::new(messages, after) }))
 }
 
+/// The session's current model, after any mid-session switch.
 #[tauri::command]
-pub async fn opencode_get_diffs(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Vec<FileDiff>, String> {
-    client.get_diffs(session_id).await
-        .map_err(|e| e.to_string())
+pub async fn opencode_active_model(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<Option<String>>, String> {
+    traced("opencode_active_model", client.active_model(session_id)).await
 }
 
+/// The in-flight assistant message, or `None` when the session is idle.
 #[tauri::command]
-pub async fn opencode_abort_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<bool, String> {
-    client.abort_session(session_id).await
-        .map_err(|e| e.to_string())
+pub async fn opencode_active_generation(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<Option<Message>>, String> {
+    traced("opencode_active_generation", client.active_generation(session_id)).await
 }
 
 #[tauri::command]
-pub async fn opencode_delete_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<bool, String> {
-    client.delete_session(session_id).await
-        .map_err(|e| e.to_string())
+pub async fn opencode_message_count(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<usize>, String> {
+    traced("opencode_message_count", client.message_count(session_id)).await
 }
 
+/// Warms the preview cache for the session list; returns how many sessions
+/// were fetched.
 #[tauri::command]
-pub async fn opencode_search_files(client: State<'_, OpenCodeClient>, pattern: &str) -> Result<Vec<String>, String> {
-    client.search_files(pattern).await
-        .map_err(|e| e.to_string())
+pub async fn opencode_prefetch_previews(
+    client: State<'_, OpenCodeClient>,
+    session_ids: Vec<String>,
+    concurrency: Option<usize>
+) -> Result<usize, String> {
+    let concurrency = concurrency.unwrap_or(DEFAULT_PREVIEW_CONCURRENCY);
+    Ok(client.prefetch_previews(session_ids, concurrency).await)
 }
 
 #[tauri::command]
-pub async fn opencode_read_file(client: State<'_, OpenCodeClient>, path: &str) -> Result<String, String> {
-    client.read_file(path).await
-        .map_err(|e| e.to_string())
+pub async fn opencode_session_preview(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Option<Message>, String> {
+    Ok(client.session_preview(session_id))
 }
 
 #[tauri::command]
-pub async fn opencode_list_files(client: State<'_, OpenCodeClient>, path: &str) -> Result<serde_json::Value, String> {
-    client.list_files(path).await
-        .map_err(|e| e.to_string())
+pub async fn opencode_total_usage(client: State<'_, OpenCodeClient>) -> Result<Traced<TotalUsage>, String> {
+    traced("opencode_total_usage", client.total_usage()).await
+}
+
+#[tauri::command]
+pub async fn opencode_get_message_parts(
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    message_id: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Traced<MessagePartsPage>, String> {
+    let limit = limit.unwrap_or(DEFAULT_MESSAGE_PARTS_LIMIT);
+    traced(
+        "opencode_get_message_parts",
+        client.get_message_parts(session_id, message_id, offset.unwrap_or(0), limit),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn opencode_get_diffs(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<SessionDiffs>, String> {
+    traced("opencode_get_diffs", async {
+        match client.get_diffs(session_id).await {
+            Ok(diffs) => Ok(SessionDiffs { diffs, supported: true }),
+            Err(OpenCodeError::Unsupported(_)) => Ok(SessionDiffs { diffs: Vec::new(), supported: false }),
+            Err(err) => Err(err),
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn opencode_changed_files(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<Vec<String>>, String> {
+    traced("opencode_changed_files", async {
+        match client.changed_files(session_id).await {
+            Err(OpenCodeError::Unsupported(_)) => Ok(Vec::new()),
+            result => result,
+        }
+    })
+    .await
+}
+
+/// `+N/-M` per changed file for the diff view's file tree.
+#[tauri::command]
+pub async fn opencode_per_file_stats(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<Vec<FileStats>>, String> {
+    traced("opencode_per_file_stats", async {
+        match client.per_file_stats(session_id).await {
+            Err(OpenCodeError::Unsupported(_)) => Ok(Vec::new()),
+            result => result,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn opencode_get_file_diff(
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    path: &str
+) -> Result<Traced<Option<FileDiff>>, String> {
+    traced("opencode_get_file_diff", async {
+        match client.get_file_diff(session_id, path).await {
+            Err(OpenCodeError::Unsupported(_)) => Ok(None),
+            result => result,
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn opencode_preview_message(
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    message: &str,
+    model: Option<&str>,
+) -> Result<Traced<Vec<FileDiff>>, String> {
+    traced("opencode_preview_message", client.preview_message(session_id, message, model)).await
+}
+
+#[tauri::command]
+pub async fn opencode_abort_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<bool>, String> {
+    traced("opencode_abort_session", client.abort_session(session_id)).await
+}
+
+#[tauri::command]
+pub async fn opencode_delete_session(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<Traced<bool>, String> {
+    traced("opencode_delete_session", client.delete_session(session_id)).await
+}
+
+#[tauri::command]
+pub async fn opencode_delete_sessions(
+    client: State<'_, OpenCodeClient>,
+    ids: Vec<String>,
+) -> Result<Vec<SessionDeleteResult>, String> {
+    Ok(client.delete_sessions(ids).await)
+}
+
+#[tauri::command]
+pub async fn opencode_search_files(client: State<'_, OpenCodeClient>, pattern: &str) -> Result<Traced<Vec<String>>, String> {
+    traced("opencode_search_files", client.search_files(pattern)).await
+}
+
+#[tauri::command]
+pub async fn opencode_search(client: State<'_, OpenCodeClient>, pattern: &str) -> Result<Traced<Vec<SearchMatch>>, String> {
+    traced("opencode_search", client.search(pattern)).await
+}
+
+#[tauri::command]
+pub async fn opencode_read_file(client: State<'_, OpenCodeClient>, path: &str) -> Result<Traced<String>, String> {
+    traced("opencode_read_file", client.read_file(path)).await
+}
+
+#[tauri::command]
+pub async fn opencode_preview_edit(client: State<'_, OpenCodeClient>, path: &str, new_content: &str) -> Result<Traced<FileDiff>, String> {
+    traced("opencode_preview_edit", client.preview_edit(path, new_content)).await
+}
+
+#[tauri::command]
+pub async fn opencode_clear_file_cache(client: State<'_, OpenCodeClient>) -> Result<bool, String> {
+    client.clear_file_cache().await;
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn opencode_set_file_cache_limit(client: State<'_, OpenCodeClient>, max_bytes: usize) -> Result<usize, String> {
+    Ok(client.set_file_cache_limit(max_bytes).await)
+}
+
+#[tauri::command]
+pub async fn opencode_snapshot_file(client: State<'_, OpenCodeClient>, path: &str) -> Result<Traced<String>, String> {
+    traced("opencode_snapshot_file", client.snapshot_file(path)).await
+}
+
+/// Unified diff between two `opencode_snapshot_file` ids.
+#[tauri::command]
+pub async fn opencode_diff_snapshots(
+    client: State<'_, OpenCodeClient>,
+    id_a: &str,
+    id_b: &str
+) -> Result<String, String> {
+    client.diff_snapshots(id_a, id_b).map_err(|e| e.to_string())
+}
+
+/// Emits the file as `opencode://file-chunk` events and resolves with the
+/// number of bytes read once the last chunk has gone out.
+#[tauri::command]
+pub async fn opencode_stream_file(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    path: String,
+    range: Option<FileRange>,
+) -> Result<Traced<u64>, String> {
+    let Traced { request_id, value: mut stream } =
+        traced("opencode_stream_file", client.read_file_stream(&path, range)).await?;
+
+    let start = range.map_or(0, |range| range.start);
+    let mut offset = start;
+    let mut pending = Vec::new();
+    while let Some(chunk) = stream.next_chunk().await.map_err(|e| e.to_string())? {
+        pending.extend_from_slice(&chunk);
+        let buffered = pending.len();
+        let data = take_utf8(&mut pending);
+        if data.is_empty() {
+            continue;
+        }
+        let _ = app.emit("opencode://file-chunk", FileChunk { path: path.clone(), offset, data, done: false });
+        offset += (buffered - pending.len()) as u64;
+    }
+
+    // Whatever is left is a truncated character at the end of the range
+    let data = String::from_utf8_lossy(&pending).into_owned();
+    let total = offset - start + pending.len() as u64;
+    let _ = app.emit("opencode://file-chunk", FileChunk { path, offset, data, done: true });
+    Ok(Traced { request_id, value: total })
+}
+
+#[tauri::command]
+pub async fn opencode_list_files(client: State<'_, OpenCodeClient>, path: &str) -> Result<Traced<serde_json::Value>, String> {
+    traced("opencode_list_files", client.list_files(path)).await
+}
+
+/// The agent's enabled tools and permitted capabilities, by id or name.
+#[tauri::command]
+pub async fn opencode_get_agent(client: State<'_, OpenCodeClient>, agent: &str) -> Result<Traced<AgentDetail>, String> {
+    traced("opencode_get_agent", client.get_agent(agent)).await
+}
+
+#[tauri::command]
+pub async fn opencode_set_default_agent(client: State<'_, OpenCodeClient>, agent: &str) -> Result<Traced<String>, String> {
+    let set = traced("opencode_set_default_agent", client.set_default_agent(agent)).await?;
+    Ok(Traced { request_id: set.request_id, value: client.default_agent().await })
+}
+
+/// Sets the directory diff and search paths are shown relative to; omitting
+/// `root` clears it. Returns the normalized root.
+#[tauri::command]
+pub async fn opencode_set_workspace_root(client: State<'_, OpenCodeClient>, root: Option<&str>) -> Result<Option<String>, String> {
+    client.set_workspace_root(root);
+    Ok(client.workspace_root())
+}
+
+/// Whether `path` is inside the server's project, with the project root so
+/// the UI can start a directory picker there.
+#[tauri::command]
+pub async fn opencode_is_within_workspace(client: State<'_, OpenCodeClient>, path: &str) -> Result<Traced<WorkspaceCheck>, String> {
+    traced("opencode_is_within_workspace", client.is_within_workspace(path)).await
+}
+
+#[tauri::command]
+pub async fn opencode_workspace_status(
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    agent: Option<&str>
+) -> Result<Traced<WorkspaceStatus>, String> {
+    traced("opencode_workspace_status", client.workspace_status(session_id, agent)).await
+}
+
+/// Forwards the server's event stream to the frontend as `opencode://event`.
+/// Pass the last seen event id as `since` when resubscribing to backfill the gap.
+/// With `event_types`, only those kinds are emitted; every event still goes
+/// to the recent-events log.
+#[tauri::command]
+pub async fn opencode_subscribe_events(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    since: Option<&str>,
+    event_types: Option<Vec<String>>
+) -> Result<Traced<bool>, String> {
+    let Traced { request_id, value: mut events } = traced("opencode_subscribe_events", client.events(since)).await?;
+    let mut since = since.map(str::to_string);
+    let filter = EventFilter::new(event_types.unwrap_or_default());
+    let task = tokio::spawn(async move {
+        let client = app.state::<OpenCodeClient>();
+        // Reconnect whenever the stream drops, resuming after the last event seen
+        loop {
+            while let Some(event) = events.recv().await {
+                client.record_event(&event);
+                if !filter.allows(&event) {
+                    continue;
+                }
+                if matches!(event, ServerEvent::Progress { .. }) {
+                    let _ = app.emit("opencode://progress", event.clone());
+                }
+                let _ = app.emit("opencode://event", event);
+            }
+            loop {
+                tokio::time::sleep(client.event_retry()).await;
+                since = client.last_event_id().or(since);
+                if let Ok(reconnected) = client.events(since.as_deref()).await {
+                    events = reconnected;
+                    break;
+                }
+            }
+        }
+    });
+    client.set_event_subscription(task).await;
+    Ok(Traced { request_id, value: true })
+}
+
+/// Events the subscription forwarded before the caller was listening,
+/// oldest first.
+#[tauri::command]
+pub async fn opencode_recent_events(client: State<'_, OpenCodeClient>, n: Option<usize>) -> Result<Vec<ServerEvent>, String> {
+    Ok(client.recent_events(n.unwrap_or(DEFAULT_EVENT_LOG_CAPACITY)))
+}
+
+#[tauri::command]
+pub async fn opencode_set_event_log_capacity(client: State<'_, OpenCodeClient>, capacity: usize) -> Result<bool, String> {
+    client.set_event_log_capacity(capacity);
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn opencode_unsubscribe_events(client: State<'_, OpenCodeClient>) -> Result<bool, String> {
+    Ok(client.stop_event_subscription().await)
+}
+
+#[tauri::command]
+pub async fn opencode_watch_diffs(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    session_id: &str
+) -> Result<Traced<bool>, String> {
+    let Traced { request_id, value: mut events } = traced("opencode_watch_diffs", client.events(None)).await?;
+    let session_id = session_id.to_string();
+    let watched = session_id.clone();
+
+    let task = tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if !event.touches_diffs(&watched) {
+                continue;
+            }
+
+            // Debounce: keep pushing the refresh back while edits keep arriving
+            let mut closed = false;
+            let quiet = tokio::time::sleep(DIFF_REFRESH_DEBOUNCE);
+            tokio::pin!(quiet);
+            loop {
+                tokio::select! {
+                    _ = &mut quiet => break,
+                    next = events.recv() => match next {
+                        Some(event) if event.touches_diffs(&watched) => {
+                            quiet.as_mut().reset(tokio::time::Instant::now() + DIFF_REFRESH_DEBOUNCE);
+                        }
+                        Some(_) => {}
+                        None => {
+                            closed = true;
+                            break;
+                        }
+                    },
+                }
+            }
+
+            let client = app.state::<OpenCodeClient>();
+            if let Ok(diffs) = client.get_diffs(&watched).await {
+                let _ = app.emit("opencode://diffs-updated", DiffsUpdated {
+                    session_id: watched.clone(),
+                    diffs,
+                });
+            }
+            if closed {
+                break;
+            }
+        }
+    });
+
+    client.register_diff_watcher(&session_id, task).await;
+    Ok(Traced { request_id, value: true })
+}
+
+/// Like `opencode_watch_diffs` for servers without an event stream: polls
+/// the session's diffs every `interval_ms` and emits `opencode://diffs-updated`
+/// only when they changed. The interval stretches while nothing changes.
+/// Replaces any other watcher on the session; stop it with
+/// `opencode_unwatch_diffs`.
+#[tauri::command]
+pub async fn opencode_poll_diffs(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    interval_ms: Option<u64>
+) -> Result<Traced<bool>, String> {
+    let interval = interval_ms.map(Duration::from_millis).unwrap_or(DEFAULT_DIFF_POLL_INTERVAL);
+    let Traced { request_id, value: mut last } = traced("opencode_poll_diffs", client.get_diffs(session_id)).await?;
+    let watched = session_id.to_string();
+
+    let task = tokio::spawn(async move {
+        let mut backoff = PollBackoff::new(interval);
+        loop {
+            tokio::time::sleep(backoff.interval()).await;
+            let client = app.state::<OpenCodeClient>();
+            let changed = match client.get_diffs(&watched).await {
+                Ok(diffs) if diffs != last => {
+                    last = diffs;
+                    let _ = app.emit("opencode://diffs-updated", DiffsUpdated {
+                        session_id: watched.clone(),
+                        diffs: last.clone(),
+                    });
+                    true
+                }
+                Ok(_) => false,
+                Err(e) => {
+                    tracing::debug!(session_id = %watched, error = %e, "diff poll failed");
+                    false
+                }
+            };
+            backoff.record(changed);
+        }
+    });
+
+    client.register_diff_watcher(session_id, task).await;
+    Ok(Traced { request_id, value: true })
+}
+
+#[tauri::command]
+pub async fn opencode_unwatch_diffs(client: State<'_, OpenCodeClient>, session_id: &str) -> Result<bool, String> {
+    Ok(client.stop_diff_watcher(session_id).await)
+}
+
+/// Replays the session's messages as `opencode://replay` events, spaced as
+/// they were originally written and sped up by `speed`, then emits
+/// `opencode://replay-complete`.
+#[tauri::command]
+pub async fn opencode_replay_session(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    session_id: &str,
+    speed: Option<f64>
+) -> Result<Traced<usize>, String> {
+    let speed = speed.unwrap_or(DEFAULT_REPLAY_SPEED);
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err("Replay speed must be greater than zero".to_string());
+    }
+    let Traced { request_id, value: messages } = traced("opencode_replay_session", client.all_messages(session_id)).await?;
+    let delays = replay_delays(&messages, speed);
+    let total = messages.len();
+    let session_id = session_id.to_string();
+    let replayed = session_id.clone();
+
+    let task = tokio::spawn(async move {
+        // Messages are moved out one at a time, so each is freed once sent
+        for (index, (message, delay)) in messages.into_iter().zip(delays).enumerate() {
+            tokio::time::sleep(delay).await;
+            let _ = app.emit("opencode://replay", ReplayMessage {
+                session_id: replayed.clone(),
+                index,
+                total,
+                message,
+            });
+        }
+        let _ = app.emit("opencode://replay-complete", ReplayComplete {
+            session_id: replayed,
+            stopped: false,
+        });
+    });
+
+    client.register_replay(&session_id, task).await;
+    Ok(Traced { request_id, value: total })
+}
+
+#[tauri::command]
+pub async fn opencode_stop_replay(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    session_id: &str
+) -> Result<bool, String> {
+    let stopped = client.stop_replay(session_id).await;
+    if stopped {
+        let _ = app.emit("opencode://replay-complete", ReplayComplete {
+            session_id: session_id.to_string(),
+            stopped: true,
+        });
+    }
+    Ok(stopped)
+}
+
+#[tauri::command]
+pub async fn opencode_set_auto_abort(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    enabled: bool
+) -> Result<bool, String> {
+    if !enabled {
+        client.auto_abort().stop().await;
+        return Ok(false);
+    }
+
+    let task_app = app.clone();
+    let task = tokio::spawn(async move {
+        let app = task_app;
+        let mut tracker = IdleTracker::default();
+        loop {
+            let client = app.state::<OpenCodeClient>();
+            if let Ok(sessions) = client.list_sessions().await {
+                let now = Instant::now();
+                tracker.observe(&sessions, now);
+                let threshold = client.auto_abort().threshold();
+                let pinned = client.pinned_sessions().await;
+                for session_id in tracker.take_idle(threshold, now, &pinned) {
+                    if client.abort_session(&session_id).await.is_ok() {
+                        let _ = app.emit("opencode://auto-aborted", AutoAborted {
+                            session_id,
+                            idle_secs: threshold.as_secs(),
+                        });
+                    }
+                }
+            }
+            tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+        }
+    });
+
+    client.auto_abort().start(task).await;
+    Ok(true)
+}
+
+/// Polls `/global/health` every `interval_secs` and emits the result as
+/// `opencode://health`.
+#[tauri::command]
+pub async fn opencode_set_health_monitor(
+    app: AppHandle,
+    client: State<'_, OpenCodeClient>,
+    enabled: bool,
+    interval_secs: Option<u64>
+) -> Result<bool, String> {
+    if !enabled {
+        client.health_monitor().stop().await;
+        return Ok(false);
+    }
+    if let Some(secs) = interval_secs {
+        client.health_monitor().set_interval(Duration::from_secs(secs));
+    }
+
+    let task_app = app.clone();
+    let task = tokio::spawn(async move {
+        let app = task_app;
+        loop {
+            let client = app.state::<OpenCodeClient>();
+            client.health_monitor().wait_while_paused().await;
+            let status = match client.health().await {
+                Ok(health) => HealthStatus { healthy: health.healthy, version: Some(health.version), error: None },
+                Err(e) => HealthStatus { healthy: false, version: None, error: Some(e.to_string()) },
+            };
+            let _ = app.emit("opencode://health", status);
+            tokio::time::sleep(client.health_monitor().interval()).await;
+        }
+    });
+
+    client.health_monitor().start(task).await;
+    Ok(true)
+}
+
+/// Pause and resume both return whether polling is now paused.
+#[tauri::command]
+pub async fn opencode_pause_health_monitor(app: AppHandle, client: State<'_, OpenCodeClient>) -> Result<bool, String> {
+    if client.health_monitor().pause() {
+        let _ = app.emit("opencode://health-paused", true);
+    }
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn opencode_resume_health_monitor(app: AppHandle, client: State<'_, OpenCodeClient>) -> Result<bool, String> {
+    if client.health_monitor().resume() {
+        let _ = app.emit("opencode://health-paused", false);
+    }
+    Ok(false)
+}
+
+#[tauri::command]
+pub async fn opencode_set_auto_abort_threshold(client: State<'_, OpenCodeClient>, idle_secs: u64) -> Result<bool, String> {
+    if idle_secs == 0 {
+        return Err("Idle threshold must be at least one second".to_string());
+    }
+    client.auto_abort().set_threshold(Duration::from_secs(idle_secs));
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn opencode_set_log_path(client: State<'_, OpenCodeClient>, path: Option<String>) -> Result<bool, String> {
+    client.set_log_path(path.map(PathBuf::from)).await;
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn opencode_server_logs(client: State<'_, OpenCodeClient>, lines: Option<usize>) -> Result<Traced<Vec<LogLine>>, String> {
+    traced("opencode_server_logs", client.server_logs(lines.unwrap_or(DEFAULT_LOG_LINES))).await
 }
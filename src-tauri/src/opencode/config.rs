@@ -0,0 +1,211 @@
+// Connection settings for the OpenCode HTTP client
+
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::opencode::error::OpenCodeError;
+use crate::opencode::events::EventFormat;
+
+pub const CLIENT_ID_HEADER: &str = "X-Client-Id";
+
+pub const DEFAULT_MAX_MESSAGE_LIMIT: u32 = 10_000;
+
+fn default_user_agent() -> String {
+    format!("opencode-monitor/{}", env!("CARGO_PKG_VERSION"))
+}
+
+fn default_max_message_limit() -> u32 {
+    DEFAULT_MAX_MESSAGE_LIMIT
+}
+
+type RefreshFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
+/// Hands out a new bearer token after the server answers `401`.
+#[derive(Clone)]
+pub struct TokenRefresh(Arc<dyn Fn() -> RefreshFuture + Send + Sync>);
+
+impl TokenRefresh {
+    pub fn new<F, Fut>(refresh: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        Self(Arc::new(move || Box::pin(refresh())))
+    }
+
+    pub async fn refresh(&self) -> Result<String, String> {
+        (self.0)().await
+    }
+}
+
+impl fmt::Debug for TokenRefresh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenRefresh")
+    }
+}
+
+impl PartialEq for TokenRefresh {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// How the monitor identifies itself, so operators can tell its traffic apart
+/// from direct CLI usage in the server logs.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientConfig {
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    /// Sent as `X-Client-Id` on every request when set.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Overrides the `/event` framing for servers that mislabel it.
+    #[serde(default)]
+    pub event_format: EventFormat,
+    /// Logs request and response bodies at debug level. Off by default since
+    /// bodies carry prompts and file contents.
+    #[serde(default)]
+    pub log_bodies: bool,
+    /// How many times a GET answered with `429` is retried after the
+    /// server's `Retry-After`. Zero surfaces the rate limit straight away.
+    #[serde(default)]
+    pub rate_limit_retries: u32,
+    /// Sent as `Authorization: Bearer` on every request when set. Never
+    /// serialized, so the UI can set it but not read it back.
+    #[serde(default, skip_serializing)]
+    pub auth_token: Option<String>,
+    /// Asked for a new `auth_token` when a request gets `401`; the request
+    /// is then retried once. Only settable from Rust.
+    #[serde(skip)]
+    pub token_refresh: Option<TokenRefresh>,
+    /// Message limit used when `get_messages` is called without one.
+    #[serde(default)]
+    pub default_message_limit: Option<u32>,
+    /// Most messages one `get_messages` call asks for, whatever the caller
+    /// requested, so a long session can't stall the UI.
+    #[serde(default = "default_max_message_limit")]
+    pub max_message_limit: u32,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: default_user_agent(),
+            client_id: None,
+            event_format: EventFormat::Auto,
+            log_bodies: false,
+            rate_limit_retries: 0,
+            auth_token: None,
+            token_refresh: None,
+            default_message_limit: None,
+            max_message_limit: DEFAULT_MAX_MESSAGE_LIMIT,
+        }
+    }
+}
+
+// Written out so the token can't end up in a log line
+impl fmt::Debug for ClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("user_agent", &self.user_agent)
+            .field("client_id", &self.client_id)
+            .field("event_format", &self.event_format)
+            .field("log_bodies", &self.log_bodies)
+            .field("rate_limit_retries", &self.rate_limit_retries)
+            .field("auth_token", &self.auth_token.as_ref().map(|_| "<redacted>"))
+            .field("token_refresh", &self.token_refresh)
+            .field("default_message_limit", &self.default_message_limit)
+            .field("max_message_limit", &self.max_message_limit)
+            .finish()
+    }
+}
+
+impl ClientConfig {
+    /// The limit to send for a requested one, and whether it was clamped
+    /// to `max_message_limit`.
+    pub fn message_limit(&self, requested: Option<i32>) -> (i32, bool) {
+        let max = i32::try_from(self.max_message_limit).unwrap_or(i32::MAX);
+        let limit = requested
+            .or_else(|| self.default_message_limit.map(|limit| i32::try_from(limit).unwrap_or(i32::MAX)))
+            .unwrap_or(max)
+            .max(0);
+        (limit.min(max), limit > max)
+    }
+
+    pub fn build_http(&self) -> Result<Client, OpenCodeError> {
+        Ok(Client::builder().default_headers(self.default_headers()?).build()?)
+    }
+
+    /// `User-Agent` and `X-Client-Id`, as sent on every request.
+    pub fn default_headers(&self) -> Result<HeaderMap, OpenCodeError> {
+        let mut headers = HeaderMap::new();
+        let user_agent = HeaderValue::from_str(&self.user_agent)
+            .map_err(|_| OpenCodeError::InvalidConfig(format!("invalid user agent {:?}", self.user_agent)))?;
+        headers.insert(USER_AGENT, user_agent);
+        if let Some(client_id) = self.client_id.as_deref().filter(|id| !id.is_empty()) {
+            let value = HeaderValue::from_str(client_id)
+                .map_err(|_| OpenCodeError::InvalidConfig(format!("invalid client id {:?}", client_id)))?;
+            headers.insert(CLIENT_ID_HEADER, value);
+        }
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_versioned_user_agent() {
+        let config = ClientConfig::default();
+        assert!(config.user_agent.starts_with("opencode-monitor/"));
+        assert!(config.build_http().is_ok());
+
+        let config: ClientConfig = serde_json::from_str(r#"{"client_id":"ci-runner"}"#).unwrap();
+        assert_eq!(config.user_agent, default_user_agent());
+        assert_eq!(config.client_id.as_deref(), Some("ci-runner"));
+    }
+
+    #[test]
+    fn keeps_the_token_out_of_output() {
+        let config = ClientConfig { auth_token: Some("s3cret".to_string()), ..ClientConfig::default() };
+        let json = serde_json::to_value(&config).unwrap();
+        assert!(json.get("auth_token").is_none());
+        let debug = format!("{:?}", config);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("s3cret"));
+
+        let config: ClientConfig = serde_json::from_str(r#"{"auth_token":"s3cret"}"#).unwrap();
+        assert_eq!(config.auth_token.as_deref(), Some("s3cret"));
+    }
+
+    #[test]
+    fn clamps_message_limits() {
+        let config = ClientConfig {
+            default_message_limit: Some(200),
+            max_message_limit: 1000,
+            ..ClientConfig::default()
+        };
+        assert_eq!(config.message_limit(None), (200, false));
+        assert_eq!(config.message_limit(Some(50)), (50, false));
+        assert_eq!(config.message_limit(Some(1000)), (1000, false));
+        assert_eq!(config.message_limit(Some(50_000)), (1000, true));
+
+        let config = ClientConfig { max_message_limit: 500, ..ClientConfig::default() };
+        assert_eq!(config.message_limit(None), (500, false));
+    }
+
+    #[test]
+    fn rejects_client_ids_that_are_not_header_safe() {
+        let config = ClientConfig {
+            client_id: Some("bad\nid".to_string()),
+            ..ClientConfig::default()
+        };
+        assert!(matches!(config.build_http(), Err(OpenCodeError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,264 @@
+// Diff helpers for session changes
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+pub const DEFAULT_DIFF_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Polls in a row with no change before the interval starts doubling
+const UNCHANGED_POLLS_BEFORE_BACKOFF: u32 = 3;
+// The interval never grows past this many times the base
+const MAX_POLL_BACKOFF: u32 = 8;
+
+/// Interval for polling diffs on servers without an event stream. It
+/// doubles while nothing changes and drops back to `base` on a change.
+#[derive(Debug)]
+pub struct PollBackoff {
+    base: Duration,
+    current: Duration,
+    unchanged: u32,
+}
+
+impl PollBackoff {
+    pub fn new(base: Duration) -> Self {
+        let base = base.max(Duration::from_millis(100));
+        Self { base, current: base, unchanged: 0 }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.current
+    }
+
+    /// Records the outcome of a poll and returns the wait before the next.
+    pub fn record(&mut self, changed: bool) -> Duration {
+        if changed {
+            self.unchanged = 0;
+            self.current = self.base;
+        } else {
+            self.unchanged += 1;
+            if self.unchanged >= UNCHANGED_POLLS_BEFORE_BACKOFF {
+                self.current = (self.current * 2).min(self.base * MAX_POLL_BACKOFF);
+            }
+        }
+        self.current
+    }
+}
+
+/// Unified diff from `old` to `new`, empty when they match. Without an
+/// `old_path` the file is shown as added, against `/dev/null`.
+pub fn unified_diff(old_path: Option<&str>, old: &str, new_path: &str, new: &str) -> Result<String, git2::Error> {
+    let mut patch = git2::Patch::from_buffers(
+        old.as_bytes(),
+        old_path.map(Path::new),
+        new.as_bytes(),
+        Some(Path::new(new_path)),
+        None,
+    )?;
+    if patch.num_hunks() == 0 {
+        return Ok(String::new());
+    }
+    let buf = patch.to_buf()?;
+    let diff = String::from_utf8_lossy(&buf).into_owned();
+    if old_path.is_some() {
+        return Ok(diff);
+    }
+    // libgit2 reuses the new path on the old side; mark it as added instead
+    let old_header = format!("--- a/{}\n", new_path);
+    Ok(diff.replacen(&old_header, "--- /dev/null\n", 1))
+}
+
+/// `+N/-M` for one changed file. Binary files count zero lines either way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileStats {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+    pub language: Option<String>,
+    pub binary: bool,
+    /// The path before a rename, when the diff shows one.
+    pub renamed_from: Option<String>,
+}
+
+impl FileStats {
+    /// Counted from `diff` when the server sent one, otherwise from a diff of
+    /// the old and new contents.
+    pub fn new(path: &str, diff: Option<&str>, old: Option<&str>, new: Option<&str>, language: Option<String>) -> Self {
+        let mut stats = Self {
+            path: path.to_string(),
+            added: 0,
+            removed: 0,
+            language,
+            binary: false,
+            renamed_from: None,
+        };
+        let computed;
+        let diff = match diff {
+            Some(diff) => diff,
+            None if [old, new].iter().flatten().any(|content| content.contains('\0')) => {
+                stats.binary = true;
+                return stats;
+            }
+            None => {
+                let old_path = old.map(|_| path);
+                computed = unified_diff(old_path, old.unwrap_or_default(), path, new.unwrap_or_default()).unwrap_or_default();
+                &computed
+            }
+        };
+        stats.count(diff);
+        stats
+    }
+
+    fn count(&mut self, diff: &str) {
+        let mut in_hunk = false;
+        let mut old_path = None;
+        for line in diff.lines() {
+            if line.starts_with("diff --git") {
+                in_hunk = false;
+            } else if line.starts_with("@@") {
+                in_hunk = true;
+            } else if in_hunk {
+                if line.starts_with('+') {
+                    self.added += 1;
+                } else if line.starts_with('-') {
+                    self.removed += 1;
+                }
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                self.binary = true;
+            } else if let Some(from) = line.strip_prefix("rename from ") {
+                self.renamed_from = Some(from.to_string());
+            } else if let Some(old) = line.strip_prefix("--- ") {
+                old_path = header_path(old, "a/");
+            } else if let Some(new) = line.strip_prefix("+++ ") {
+                let new_path = header_path(new, "b/");
+                if self.renamed_from.is_none() && old_path.is_some() && new_path.is_some() && old_path != new_path {
+                    self.renamed_from = old_path.take();
+                }
+            }
+        }
+        if self.binary {
+            self.added = 0;
+            self.removed = 0;
+        }
+    }
+}
+
+// `a/src/lib.rs` -> `src/lib.rs`; `None` for `/dev/null`
+fn header_path(header: &str, prefix: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Maps a file path to the syntax-highlighting language of its extension.
+pub fn language_for_path(path: &str) -> Option<&'static str> {
+    let file_name = Path::new(path).file_name()?.to_str()?;
+    match file_name {
+        "Dockerfile" => return Some("dockerfile"),
+        "Makefile" => return Some("makefile"),
+        _ => {}
+    }
+    let extension = Path::new(file_name).extension()?.to_str()?.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "py" => "python",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "sh" | "bash" | "zsh" => "bash",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "sql" => "sql",
+        "xml" => "xml",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_known_extensions() {
+        assert_eq!(language_for_path("src/lib.rs"), Some("rust"));
+        assert_eq!(language_for_path("web/App.TS"), Some("typescript"));
+        assert_eq!(language_for_path("docker/Dockerfile"), Some("dockerfile"));
+    }
+
+    #[test]
+    fn diffs_new_and_changed_files() {
+        let changed = unified_diff(Some("a.txt"), "one\ntwo\n", "a.txt", "one\n2\n").unwrap();
+        assert!(changed.contains("-two\n+2\n"));
+        assert!(unified_diff(Some("a.txt"), "same\n", "a.txt", "same\n").unwrap().is_empty());
+
+        let added = unified_diff(None, "", "new.txt", "hello\n").unwrap();
+        assert!(added.contains("--- /dev/null"));
+        assert!(added.contains("+hello"));
+    }
+
+    #[test]
+    fn counts_changed_lines() {
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n+--flag\n";
+        let stats = FileStats::new("src/lib.rs", Some(diff), None, None, Some("rust".to_string()));
+        assert_eq!((stats.added, stats.removed, stats.binary), (2, 1, false));
+        assert_eq!(stats.renamed_from, None);
+
+        let stats = FileStats::new("notes.txt", None, Some("a\nb\n"), Some("a\nc\nd\n"), None);
+        assert_eq!((stats.added, stats.removed), (2, 1));
+    }
+
+    #[test]
+    fn marks_binary_and_renamed_files() {
+        let binary = "diff --git a/logo.png b/logo.png\nBinary files a/logo.png and b/logo.png differ\n";
+        let stats = FileStats::new("logo.png", Some(binary), None, None, None);
+        assert!(stats.binary);
+        assert_eq!((stats.added, stats.removed), (0, 0));
+        assert!(FileStats::new("blob.bin", None, None, Some("\0\x01"), None).binary);
+
+        let renamed = "diff --git a/old.rs b/new.rs\nsimilarity index 100%\nrename from old.rs\nrename to new.rs\n";
+        let stats = FileStats::new("new.rs", Some(renamed), None, None, None);
+        assert_eq!(stats.renamed_from.as_deref(), Some("old.rs"));
+        assert_eq!((stats.added, stats.removed), (0, 0));
+
+        let moved = "--- a/src/a.rs\n+++ b/src/b.rs\n@@ -1 +1 @@\n-x\n+y\n";
+        let stats = FileStats::new("src/b.rs", Some(moved), None, None, None);
+        assert_eq!(stats.renamed_from.as_deref(), Some("src/a.rs"));
+    }
+
+    #[test]
+    fn poll_backoff_doubles_then_resets() {
+        let mut backoff = PollBackoff::new(Duration::from_secs(1));
+        assert_eq!(backoff.record(false), Duration::from_secs(1));
+        assert_eq!(backoff.record(false), Duration::from_secs(1));
+        assert_eq!(backoff.record(false), Duration::from_secs(2));
+        assert_eq!(backoff.record(false), Duration::from_secs(4));
+        for _ in 0..5 {
+            backoff.record(false);
+        }
+        assert_eq!(backoff.interval(), Duration::from_secs(8));
+        assert_eq!(backoff.record(true), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn unknown_extensions_have_no_language() {
+        assert_eq!(language_for_path("notes.unknownext"), None);
+        assert_eq!(language_for_path("LICENSE"), None);
+    }
+}
@@ -0,0 +1,78 @@
+// Errors surfaced by the OpenCode client
+
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum OpenCodeError {
+    Http(reqwest::Error),
+    Timeout(Duration),
+    NoReply,
+    /// The connected server doesn't offer this capability.
+    Unsupported(&'static str),
+    InvalidConfig(String),
+    /// A byte range that is empty or runs past the largest offset.
+    InvalidRange(String),
+    /// The server has no session with this id.
+    NotFound(String),
+    /// No snapshot with this id, or it was evicted.
+    UnknownSnapshot(String),
+    /// The server answered with an error envelope or a body we can't use.
+    Server(String),
+    /// `429 Too Many Requests`, with the server's `Retry-After` if it sent one.
+    RateLimited { retry_after: Option<Duration> },
+    /// `401 Unauthorized` that a token refresh didn't fix, or no refresh
+    /// was configured.
+    Unauthorized,
+    /// A custom transport couldn't deliver the request.
+    Transport(String),
+}
+
+impl fmt::Display for OpenCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenCodeError::Http(err) => write!(f, "{}", err),
+            OpenCodeError::Timeout(after) => {
+                write!(f, "Timed out after {}s waiting for OpenCode", after.as_secs())
+            }
+            OpenCodeError::NoReply => write!(f, "OpenCode returned no assistant reply"),
+            OpenCodeError::Unsupported(what) => {
+                write!(f, "This OpenCode server does not support {}", what)
+            }
+            OpenCodeError::InvalidConfig(reason) => write!(f, "Invalid client config: {}", reason),
+            OpenCodeError::InvalidRange(reason) => write!(f, "Invalid byte range: {}", reason),
+            OpenCodeError::NotFound(id) => write!(f, "Session {} not found", id),
+            OpenCodeError::UnknownSnapshot(id) => write!(f, "Snapshot {} not found", id),
+            OpenCodeError::Server(message) => write!(f, "OpenCode server error: {}", message),
+            OpenCodeError::RateLimited { retry_after: Some(after) } => {
+                write!(f, "OpenCode is rate limited, try again in {}s", after.as_secs().max(1))
+            }
+            OpenCodeError::RateLimited { retry_after: None } => write!(f, "OpenCode is rate limited"),
+            OpenCodeError::Unauthorized => write!(f, "OpenCode rejected the credentials"),
+            OpenCodeError::Transport(reason) => write!(f, "OpenCode transport error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for OpenCodeError {}
+
+/// Best-effort message from the `error` field of an error envelope, which is
+/// either a string or an object like `{ "name", "data": { "message" } }`.
+pub fn envelope_message(error: &serde_json::Value) -> String {
+    if let Some(message) = error.as_str() {
+        return message.to_string();
+    }
+    error
+        .get("message")
+        .or_else(|| error.pointer("/data/message"))
+        .or_else(|| error.get("name"))
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| error.to_string())
+}
+
+impl From<reqwest::Error> for OpenCodeError {
+    fn from(err: reqwest::Error) -> Self {
+        OpenCodeError::Http(err)
+    }
+}
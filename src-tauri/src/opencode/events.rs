@@ -0,0 +1,483 @@
+// OpenCode server events
+// The `/event` endpoint streams `data: {"type": ..., "properties": {...}}` frames,
+// or the bare JSON objects one per line on builds that serve NDJSON
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+pub const DEFAULT_EVENT_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    SessionUpdated { session_id: String },
+    SessionDiff { session_id: String },
+    SessionIdle { session_id: String },
+    MessageUpdated { session_id: String, message_id: String },
+    FileEdited { path: String },
+    /// `fraction` is 0.0..=1.0, or `None` when the server gave no amount.
+    Progress { session_id: String, label: String, fraction: Option<f32> },
+    Other { event_type: String, properties: Value },
+}
+
+fn session_id_of(value: &Value) -> Option<String> {
+    value
+        .get("sessionID")
+        .or_else(|| value.get("session_id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+fn progress_label(value: &Value) -> String {
+    ["label", "title", "message"]
+        .iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_str))
+        .unwrap_or_default()
+        .to_string()
+}
+
+// Accepts `fraction` (0-1) or `percent` (0-100)
+fn progress_fraction(value: &Value) -> Option<f32> {
+    let fraction = value
+        .get("fraction")
+        .and_then(Value::as_f64)
+        .or_else(|| value.get("percent").and_then(Value::as_f64).map(|percent| percent / 100.0))?;
+    Some(fraction.clamp(0.0, 1.0) as f32)
+}
+
+impl ServerEvent {
+    pub fn from_value(value: Value) -> Option<Self> {
+        let event_type = value.get("type")?.as_str()?.to_string();
+        let properties = value.get("properties").cloned().unwrap_or(Value::Null);
+        let info = properties.get("info").unwrap_or(&properties);
+
+        let event = match event_type.as_str() {
+            "session.updated" => info
+                .get("id")
+                .and_then(Value::as_str)
+                .map(|id| ServerEvent::SessionUpdated { session_id: id.to_string() }),
+            "session.diff" => session_id_of(&properties)
+                .map(|session_id| ServerEvent::SessionDiff { session_id }),
+            "session.idle" => session_id_of(&properties)
+                .map(|session_id| ServerEvent::SessionIdle { session_id }),
+            "message.updated" => {
+                let session_id = session_id_of(info);
+                let message_id = info.get("id").and_then(Value::as_str).map(str::to_string);
+                session_id.zip(message_id).map(|(session_id, message_id)| {
+                    ServerEvent::MessageUpdated { session_id, message_id }
+                })
+            }
+            "file.edited" => properties
+                .get("file")
+                .and_then(Value::as_str)
+                .map(|path| ServerEvent::FileEdited { path: path.to_string() }),
+            "session.progress" | "progress" => session_id_of(&properties).map(|session_id| {
+                ServerEvent::Progress {
+                    session_id,
+                    label: progress_label(&properties),
+                    fraction: progress_fraction(&properties),
+                }
+            }),
+            _ => None,
+        };
+
+        Some(event.unwrap_or(ServerEvent::Other { event_type, properties }))
+    }
+
+    /// The `type` this event serializes with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServerEvent::SessionUpdated { .. } => "session_updated",
+            ServerEvent::SessionDiff { .. } => "session_diff",
+            ServerEvent::SessionIdle { .. } => "session_idle",
+            ServerEvent::MessageUpdated { .. } => "message_updated",
+            ServerEvent::FileEdited { .. } => "file_edited",
+            ServerEvent::Progress { .. } => "progress",
+            ServerEvent::Other { .. } => "other",
+        }
+    }
+
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            ServerEvent::SessionUpdated { session_id }
+            | ServerEvent::SessionDiff { session_id }
+            | ServerEvent::SessionIdle { session_id }
+            | ServerEvent::MessageUpdated { session_id, .. }
+            | ServerEvent::Progress { session_id, .. } => Some(session_id),
+            ServerEvent::FileEdited { .. } | ServerEvent::Other { .. } => None,
+        }
+    }
+
+    /// Whether this event may have changed the diffs of `session_id`.
+    /// `file.edited` carries no session, so it counts for every watcher.
+    pub fn touches_diffs(&self, session_id: &str) -> bool {
+        match self {
+            ServerEvent::SessionDiff { session_id: id } => id == session_id,
+            ServerEvent::FileEdited { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// The most recent events, oldest evicted first, for subscribers that
+/// attach after the stream started.
+#[derive(Debug)]
+pub struct EventLog {
+    events: VecDeque<ServerEvent>,
+    capacity: usize,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_LOG_CAPACITY)
+    }
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, event: ServerEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Up to `n` of the latest events, oldest first.
+    pub fn recent(&self, n: usize) -> Vec<ServerEvent> {
+        let skip = self.events.len().saturating_sub(n);
+        self.events.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let excess = self.events.len().saturating_sub(capacity);
+        self.events.drain(..excess);
+        self.capacity = capacity;
+    }
+}
+
+/// One dispatched server-sent event. `event` is `None` for the default
+/// `message` type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseFrame {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+impl SseFrame {
+    /// OpenCode puts the type inside the JSON payload; servers that use the
+    /// `event:` field instead get it wrapped into the same shape.
+    pub fn to_server_event(&self) -> Option<ServerEvent> {
+        let value: Value = serde_json::from_str(&self.data).ok()?;
+        if value.get("type").is_some() {
+            return ServerEvent::from_value(value);
+        }
+        let event_type = self.event.as_deref()?;
+        ServerEvent::from_value(serde_json::json!({ "type": event_type, "properties": value }))
+    }
+}
+
+/// Parser state carried between chunks of an SSE stream.
+#[derive(Debug, Default)]
+pub struct SseState {
+    pending: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+    /// Persists across events, as the spec's "last event ID" does.
+    pub last_event_id: Option<String>,
+    /// The most recent `retry:` value, for the reconnect backoff.
+    pub retry: Option<Duration>,
+}
+
+impl SseState {
+    // Splits off the next complete line, accepting `\n`, `\r\n` and `\r`
+    fn next_line(&mut self) -> Option<String> {
+        let pos = self.pending.iter().position(|b| *b == b'\n' || *b == b'\r')?;
+        let terminator = if self.pending[pos] == b'\r' {
+            match self.pending.get(pos + 1) {
+                Some(b'\n') => 2,
+                Some(_) => 1,
+                // A trailing `\r` may be the first half of `\r\n`
+                None => return None,
+            }
+        } else {
+            1
+        };
+        let line: Vec<u8> = self.pending.drain(..pos + terminator).take(pos).collect();
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseFrame> {
+        if line.is_empty() {
+            let event = self.event.take();
+            if self.data.is_empty() {
+                return None;
+            }
+            return Some(SseFrame {
+                event,
+                data: std::mem::take(&mut self.data).join("\n"),
+                id: self.last_event_id.clone(),
+            });
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(ms) = value.parse::<u64>() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Framing of the `/event` stream. `Auto` picks one from the response's
+/// `Content-Type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventFormat {
+    #[default]
+    Auto,
+    Sse,
+    Ndjson,
+}
+
+impl EventFormat {
+    /// Anything that isn't a JSON media type is read as SSE.
+    pub fn resolve(self, content_type: Option<&str>) -> EventFormat {
+        if self != EventFormat::Auto {
+            return self;
+        }
+        let mime = content_type
+            .and_then(|value| value.split(';').next())
+            .map(|mime| mime.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        if mime.ends_with("json") || mime.ends_with("jsonl") {
+            EventFormat::Ndjson
+        } else {
+            EventFormat::Sse
+        }
+    }
+
+    pub fn parse_chunk(self, state: &mut SseState, chunk: &[u8]) -> Vec<SseFrame> {
+        match self {
+            EventFormat::Ndjson => parse_ndjson_chunk(state, chunk),
+            EventFormat::Auto | EventFormat::Sse => parse_sse_chunk(state, chunk),
+        }
+    }
+}
+
+/// Feeds one chunk of an SSE stream through `state`, returning every event
+/// completed by it. Partial lines and events wait for the next chunk.
+pub fn parse_sse_chunk(state: &mut SseState, chunk: &[u8]) -> Vec<SseFrame> {
+    state.pending.extend_from_slice(chunk);
+    let mut frames = Vec::new();
+    while let Some(line) = state.next_line() {
+        frames.extend(state.process_line(&line));
+    }
+    frames
+}
+
+/// Feeds one chunk of an NDJSON stream through `state`. Each non-empty line
+/// becomes a frame of its own; NDJSON carries no ids or retry hints.
+pub fn parse_ndjson_chunk(state: &mut SseState, chunk: &[u8]) -> Vec<SseFrame> {
+    state.pending.extend_from_slice(chunk);
+    let mut frames = Vec::new();
+    while let Some(line) = state.next_line() {
+        let line = line.trim();
+        if !line.is_empty() {
+            frames.push(SseFrame { data: line.to_string(), ..SseFrame::default() });
+        }
+    }
+    frames
+}
+
+/// Which events a subscription forwards, by `kind`. Server-style names
+/// like `message.updated` work too. Empty forwards everything; otherwise
+/// events the monitor doesn't recognize are dropped unless `other` is listed.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    kinds: HashSet<String>,
+}
+
+impl EventFilter {
+    pub fn new<I: IntoIterator<Item = String>>(types: I) -> Self {
+        let kinds = types
+            .into_iter()
+            .map(|kind| kind.trim().to_ascii_lowercase().replace('.', "_"))
+            .filter(|kind| !kind.is_empty())
+            .collect();
+        Self { kinds }
+    }
+
+    pub fn allows(&self, event: &ServerEvent) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(event.kind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn filters_by_kind() {
+        let message = ServerEvent::MessageUpdated { session_id: "ses_1".into(), message_id: "msg_1".into() };
+        let other = ServerEvent::Other { event_type: "lsp.updated".into(), properties: Value::Null };
+        assert_eq!(serde_json::to_value(&message).unwrap()["type"], message.kind());
+        assert_eq!(serde_json::to_value(&other).unwrap()["type"], other.kind());
+
+        let filter = EventFilter::new(vec!["message.updated".to_string()]);
+        assert!(filter.allows(&message));
+        assert!(!filter.allows(&other));
+        assert!(EventFilter::default().allows(&other));
+    }
+
+    #[test]
+    fn parses_known_event_types() {
+        let event = ServerEvent::from_value(json!({
+            "type": "session.diff",
+            "properties": { "sessionID": "ses_1", "diff": [] }
+        }))
+        .unwrap();
+        assert!(event.touches_diffs("ses_1"));
+        assert!(!event.touches_diffs("ses_2"));
+
+        let event = ServerEvent::from_value(json!({
+            "type": "message.updated",
+            "properties": { "info": { "id": "msg_1", "sessionID": "ses_1" } }
+        }))
+        .unwrap();
+        assert_eq!(event.session_id(), Some("ses_1"));
+    }
+
+    #[test]
+    fn parses_progress_shapes() {
+        let event = ServerEvent::from_value(json!({
+            "type": "session.progress",
+            "properties": { "sessionID": "ses_1", "label": "Running tests", "fraction": 0.5 }
+        }))
+        .unwrap();
+        assert!(matches!(
+            event,
+            ServerEvent::Progress { ref label, fraction: Some(f), .. } if label == "Running tests" && f == 0.5
+        ));
+
+        let event = ServerEvent::from_value(json!({
+            "type": "progress",
+            "properties": { "sessionID": "ses_1", "message": "Indexing", "percent": 150 }
+        }))
+        .unwrap();
+        assert!(matches!(event, ServerEvent::Progress { fraction: Some(f), .. } if f == 1.0));
+
+        let event = ServerEvent::from_value(json!({
+            "type": "session.progress",
+            "properties": { "sessionID": "ses_1", "label": "Thinking" }
+        }))
+        .unwrap();
+        assert!(matches!(event, ServerEvent::Progress { fraction: None, .. }));
+        assert_eq!(event.session_id(), Some("ses_1"));
+    }
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut state = SseState::default();
+        assert!(parse_sse_chunk(&mut state, b"data: {\"type\":\"file.ed").is_empty());
+        let frames = parse_sse_chunk(&mut state, b"ited\",\"properties\":{\"file\":\"src/main.rs\"}}\r");
+        assert!(frames.is_empty());
+        let frames = parse_sse_chunk(&mut state, b"\n\r\n");
+        assert_eq!(frames.len(), 1);
+        let event = frames[0].to_server_event().unwrap();
+        assert!(matches!(event, ServerEvent::FileEdited { path } if path == "src/main.rs"));
+    }
+
+    #[test]
+    fn handles_fields_comments_and_multiline_data() {
+        let mut state = SseState::default();
+        let stream = b": keep-alive\n\
+            retry: 5000\n\
+            id: 7\n\
+            event: session.idle\n\
+            data: {\"sessionID\":\n\
+            data: \"ses_1\"}\n\
+            \n\
+            data: no id change\n\
+            \n\
+            event: ignored\n\
+            \n";
+        let frames = parse_sse_chunk(&mut state, stream);
+        assert_eq!(state.retry, Some(Duration::from_secs(5)));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].event.as_deref(), Some("session.idle"));
+        assert_eq!(frames[0].data, "{\"sessionID\":\n\"ses_1\"}");
+        assert_eq!(frames[0].id.as_deref(), Some("7"));
+        let event = frames[0].to_server_event().unwrap();
+        assert!(matches!(event, ServerEvent::SessionIdle { session_id } if session_id == "ses_1"));
+
+        assert_eq!(frames[1], SseFrame { event: None, data: "no id change".into(), id: Some("7".into()) });
+    }
+
+    #[test]
+    fn resolves_format_from_content_type() {
+        assert_eq!(EventFormat::Auto.resolve(Some("text/event-stream")), EventFormat::Sse);
+        assert_eq!(EventFormat::Auto.resolve(Some("application/x-ndjson; charset=utf-8")), EventFormat::Ndjson);
+        assert_eq!(EventFormat::Auto.resolve(Some("application/json")), EventFormat::Ndjson);
+        assert_eq!(EventFormat::Auto.resolve(None), EventFormat::Sse);
+        assert_eq!(EventFormat::Sse.resolve(Some("application/x-ndjson")), EventFormat::Sse);
+    }
+
+    #[test]
+    fn parses_ndjson_split_across_chunks() {
+        let mut state = SseState::default();
+        let frames = parse_ndjson_chunk(&mut state, b"{\"type\":\"session.idle\",\"properties\":{\"sessionID\":\"ses_1\"}}\n{\"type\":");
+        assert_eq!(frames.len(), 1);
+        let event = frames[0].to_server_event().unwrap();
+        assert!(matches!(event, ServerEvent::SessionIdle { session_id } if session_id == "ses_1"));
+
+        let frames = parse_ndjson_chunk(&mut state, b"\"file.edited\",\"properties\":{\"file\":\"a.rs\"}}\r\n\n");
+        assert_eq!(frames.len(), 1);
+        assert!(matches!(frames[0].to_server_event(), Some(ServerEvent::FileEdited { .. })));
+    }
+
+    #[test]
+    fn event_log_keeps_the_latest_events() {
+        let idle = |id: &str| ServerEvent::SessionIdle { session_id: id.to_string() };
+        let ids = |events: Vec<ServerEvent>| -> Vec<String> {
+            events.iter().filter_map(|e| e.session_id().map(str::to_string)).collect()
+        };
+
+        let mut log = EventLog::new(3);
+        for id in ["a", "b", "c", "d"] {
+            log.push(idle(id));
+        }
+        assert_eq!(ids(log.recent(10)), vec!["b", "c", "d"]);
+        assert_eq!(ids(log.recent(2)), vec!["c", "d"]);
+
+        log.set_capacity(1);
+        assert_eq!(ids(log.recent(10)), vec!["d"]);
+        log.push(idle("e"));
+        assert_eq!(ids(log.recent(10)), vec!["e"]);
+    }
+}
@@ -0,0 +1,243 @@
+// Streaming reads of workspace files, for logs and generated files too big
+// to buffer into one string
+
+use reqwest::header::HeaderValue;
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::opencode::error::OpenCodeError;
+
+/// Byte range of a partial read. `length: None` reads to the end; a zero
+/// length is rejected rather than guessed at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileRange {
+    pub start: u64,
+    pub length: Option<u64>,
+}
+
+impl FileRange {
+    /// The `Range` header for this range, which also checks it's one
+    /// `FileStream` can apply the same way when the server ignores it.
+    pub fn header_value(&self) -> Result<HeaderValue, OpenCodeError> {
+        let value = match self.length {
+            None => format!("bytes={}-", self.start),
+            Some(0) => return Err(OpenCodeError::InvalidRange("length must be at least 1".to_string())),
+            Some(length) => {
+                let end = self.start.checked_add(length - 1).ok_or_else(|| {
+                    OpenCodeError::InvalidRange(format!("{} bytes from {} overflows", length, self.start))
+                })?;
+                format!("bytes={}-{}", self.start, end)
+            }
+        };
+        Ok(HeaderValue::from_str(&value).unwrap())
+    }
+}
+
+/// Chunks of a file body. If the server ignored the `Range` header and sent
+/// the whole file, the range is applied here instead.
+pub struct FileStream {
+    response: Response,
+    skip: u64,
+    remaining: Option<u64>,
+}
+
+impl FileStream {
+    pub fn new(response: Response, range: Option<FileRange>) -> Self {
+        let range = match response.status() {
+            StatusCode::PARTIAL_CONTENT => None,
+            _ => range,
+        };
+        Self {
+            response,
+            skip: range.map_or(0, |range| range.start),
+            remaining: range.and_then(|range| range.length),
+        }
+    }
+
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, OpenCodeError> {
+        loop {
+            if self.remaining == Some(0) {
+                return Ok(None);
+            }
+            let Some(chunk) = self.response.chunk().await? else {
+                return Ok(None);
+            };
+            let mut chunk = &chunk[..];
+            if self.skip > 0 {
+                let skipped = self.skip.min(chunk.len() as u64);
+                self.skip -= skipped;
+                chunk = &chunk[skipped as usize..];
+            }
+            if let Some(remaining) = self.remaining.as_mut() {
+                let kept = (*remaining).min(chunk.len() as u64);
+                *remaining -= kept;
+                chunk = &chunk[..kept as usize];
+            }
+            if !chunk.is_empty() {
+                return Ok(Some(chunk.to_vec()));
+            }
+        }
+    }
+}
+
+pub const DEFAULT_FILE_CACHE_BYTES: usize = 8 * 1024 * 1024;
+
+/// A cached `read_file` body with the validators the server sent for it.
+#[derive(Debug, Clone)]
+pub struct CachedFile {
+    pub content: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    last_used: u64,
+}
+
+/// `read_file` results keyed by path, evicting least recently used entries
+/// once the cached content exceeds `max_bytes`.
+#[derive(Debug)]
+pub struct FileCache {
+    entries: HashMap<String, CachedFile>,
+    max_bytes: usize,
+    used_bytes: usize,
+    clock: u64,
+}
+
+impl Default for FileCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILE_CACHE_BYTES)
+    }
+}
+
+impl FileCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_bytes,
+            used_bytes: 0,
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<&CachedFile> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.get_mut(path)?;
+        entry.last_used = clock;
+        Some(entry)
+    }
+
+    /// Files without an `ETag` or `Last-Modified` can't be revalidated, so
+    /// they aren't kept.
+    pub fn insert(&mut self, path: &str, content: String, etag: Option<String>, last_modified: Option<String>) {
+        self.remove(path);
+        if (etag.is_none() && last_modified.is_none()) || content.len() > self.max_bytes {
+            return;
+        }
+        self.clock += 1;
+        self.used_bytes += content.len();
+        self.entries.insert(
+            path.to_string(),
+            CachedFile { content, etag, last_modified, last_used: self.clock },
+        );
+        self.evict();
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.used_bytes -= entry.content.len();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.evict();
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    fn evict(&mut self) {
+        while self.used_bytes > self.max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+}
+
+/// Takes the longest valid UTF-8 prefix of `pending`, leaving an incomplete
+/// trailing character for the next chunk. Invalid bytes become U+FFFD.
+pub fn take_utf8(pending: &mut Vec<u8>) -> String {
+    let end = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let taken: Vec<u8> = pending.drain(..end).collect();
+    String::from_utf8_lossy(&taken).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_range_headers() {
+        let range = FileRange { start: 10, length: Some(5) };
+        assert_eq!(range.header_value().unwrap(), "bytes=10-14");
+        let range = FileRange { start: 10, length: None };
+        assert_eq!(range.header_value().unwrap(), "bytes=10-");
+        let range = FileRange { start: u64::MAX, length: Some(1) };
+        assert_eq!(range.header_value().unwrap(), format!("bytes={}-{}", u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn rejects_empty_and_overflowing_ranges() {
+        let empty = FileRange { start: 10, length: Some(0) };
+        assert!(matches!(empty.header_value(), Err(OpenCodeError::InvalidRange(_))));
+        let overflowing = FileRange { start: u64::MAX, length: Some(2) };
+        assert!(matches!(overflowing.header_value(), Err(OpenCodeError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let mut cache = FileCache::new(10);
+        cache.insert("a", "aaaa".into(), Some("\"1\"".into()), None);
+        cache.insert("b", "bbbb".into(), None, Some("Mon".into()));
+        cache.insert("no-validators", "cc".into(), None, None);
+        assert!(cache.get("no-validators").is_none());
+
+        assert!(cache.get("a").is_some());
+        cache.insert("c", "cccc".into(), Some("\"2\"".into()), None);
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert_eq!(cache.used_bytes(), 8);
+
+        cache.set_max_bytes(4);
+        assert_eq!(cache.used_bytes(), 4);
+        cache.clear();
+        assert_eq!(cache.used_bytes(), 0);
+    }
+
+    #[test]
+    fn holds_back_split_characters() {
+        let mut pending = "héllo".as_bytes()[..2].to_vec();
+        assert_eq!(take_utf8(&mut pending), "h");
+        assert_eq!(pending.len(), 1);
+        pending.extend_from_slice(&"héllo".as_bytes()[2..]);
+        assert_eq!(take_utf8(&mut pending), "éllo");
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+// Background health polling that the UI can pause while the window is hidden
+
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+
+pub const DEFAULT_HEALTH_INTERVAL: Duration = Duration::from_secs(10);
+// Per probe of `deep_health`; a hung endpoint counts as down
+pub const DEEP_HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Payload of `opencode://health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+/// One endpoint probed by `deep_health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Usable only when every probed endpoint answered.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepHealth {
+    pub healthy: bool,
+    pub endpoints: Vec<EndpointHealth>,
+}
+
+impl DeepHealth {
+    pub fn new(endpoints: Vec<EndpointHealth>) -> Self {
+        let healthy = endpoints.iter().all(|endpoint| endpoint.ok);
+        Self { healthy, endpoints }
+    }
+}
+
+/// Runs `check` against `endpoint` with `timeout`, timing it.
+pub async fn probe<F, E>(endpoint: &str, timeout: Duration, check: F) -> EndpointHealth
+where
+    F: Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    let started = Instant::now();
+    let error = match tokio::time::timeout(timeout, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("no answer within {}s", timeout.as_secs())),
+    };
+    EndpointHealth {
+        endpoint: endpoint.to_string(),
+        ok: error.is_none(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+/// The polling task checks `wait_while_paused` each tick, so pausing parks it
+/// instead of tearing it down.
+pub struct HealthMonitor {
+    interval_secs: AtomicU64,
+    paused: AtomicBool,
+    resumed: Notify,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self {
+            interval_secs: AtomicU64::new(DEFAULT_HEALTH_INTERVAL.as_secs()),
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
+            task: Mutex::new(None),
+        }
+    }
+}
+
+impl HealthMonitor {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.load(Ordering::Relaxed))
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.interval_secs.store(interval.as_secs().max(1), Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether this call changed the state.
+    pub fn pause(&self) -> bool {
+        !self.paused.swap(true, Ordering::SeqCst)
+    }
+
+    /// Returns whether this call changed the state.
+    pub fn resume(&self) -> bool {
+        let changed = self.paused.swap(false, Ordering::SeqCst);
+        self.resumed.notify_waiters();
+        changed
+    }
+
+    pub async fn wait_while_paused(&self) {
+        loop {
+            // Register before checking so a resume in between isn't missed
+            let resumed = self.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            resumed.await;
+        }
+    }
+
+    pub async fn start(&self, task: JoinHandle<()>) {
+        if let Some(previous) = self.task.lock().await.replace(task) {
+            previous.abort();
+        }
+    }
+
+    pub async fn stop(&self) -> bool {
+        match self.task.lock().await.take() {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn resume_wakes_a_paused_waiter() {
+        let monitor = Arc::new(HealthMonitor::default());
+        assert!(monitor.pause());
+        assert!(!monitor.pause());
+
+        let waiter = tokio::spawn({
+            let monitor = monitor.clone();
+            async move { monitor.wait_while_paused().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        assert!(monitor.resume());
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert!(!monitor.resume());
+    }
+}
@@ -0,0 +1,133 @@
+// Idle-session auto-abort
+// Activity is inferred from changes to each session's `updated_at` between polls
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::opencode::timestamp::Timestamp;
+use crate::opencode::Session;
+
+pub const DEFAULT_IDLE_THRESHOLD: Duration = Duration::from_secs(30 * 60);
+pub const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Default)]
+pub struct IdleTracker {
+    // session id -> (last seen `updated_at`, when it last changed)
+    last_activity: HashMap<String, (Timestamp, Instant)>,
+    aborted: HashSet<String>,
+}
+
+impl IdleTracker {
+    pub fn observe(&mut self, sessions: &[Session], now: Instant) {
+        let live: HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        self.last_activity.retain(|id, _| live.contains(id.as_str()));
+        self.aborted.retain(|id| live.contains(id.as_str()));
+
+        for session in sessions {
+            let changed = self
+                .last_activity
+                .get(&session.id)
+                .map(|(updated_at, _)| updated_at != &session.updated_at)
+                .unwrap_or(true);
+            if changed {
+                self.last_activity
+                    .insert(session.id.clone(), (session.updated_at.clone(), now));
+                self.aborted.remove(&session.id);
+            }
+        }
+    }
+
+    /// Returns sessions idle for at least `threshold`, each only once until it
+    /// shows activity again.
+    pub fn take_idle(
+        &mut self,
+        threshold: Duration,
+        now: Instant,
+        pinned: &HashSet<String>,
+    ) -> Vec<String> {
+        let mut idle: Vec<String> = self
+            .last_activity
+            .iter()
+            .filter(|(id, (_, at))| {
+                now.duration_since(*at) >= threshold
+                    && !pinned.contains(*id)
+                    && !self.aborted.contains(*id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        idle.sort();
+        self.aborted.extend(idle.iter().cloned());
+        idle
+    }
+}
+
+pub struct AutoAbort {
+    threshold_secs: AtomicU64,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Default for AutoAbort {
+    fn default() -> Self {
+        Self {
+            threshold_secs: AtomicU64::new(DEFAULT_IDLE_THRESHOLD.as_secs()),
+            task: Mutex::new(None),
+        }
+    }
+}
+
+impl AutoAbort {
+    pub fn threshold(&self) -> Duration {
+        Duration::from_secs(self.threshold_secs.load(Ordering::Relaxed))
+    }
+
+    pub fn set_threshold(&self, threshold: Duration) {
+        self.threshold_secs.store(threshold.as_secs(), Ordering::Relaxed);
+    }
+
+    pub async fn start(&self, task: JoinHandle<()>) {
+        if let Some(previous) = self.task.lock().await.replace(task) {
+            previous.abort();
+        }
+    }
+
+    pub async fn stop(&self) -> bool {
+        match self.task.lock().await.take() {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, updated_at: &str) -> Session {
+        Session {
+            id: id.to_string(),
+            updated_at: Timestamp::parse(updated_at),
+            ..Session::default()
+        }
+    }
+
+    #[test]
+    fn reports_idle_sessions_once_and_skips_pinned() {
+        let mut tracker = IdleTracker::default();
+        let start = Instant::now();
+        tracker.observe(&[session("a", "1"), session("b", "1"), session("c", "1")], start);
+
+        let later = start + Duration::from_secs(120);
+        tracker.observe(&[session("a", "1"), session("b", "2"), session("c", "1")], later);
+
+        let pinned: HashSet<String> = ["c".to_string()].into_iter().collect();
+        let idle = tracker.take_idle(Duration::from_secs(60), later, &pinned);
+        assert_eq!(idle, vec!["a".to_string()]);
+        assert!(tracker.take_idle(Duration::from_secs(60), later, &pinned).is_empty());
+    }
+}
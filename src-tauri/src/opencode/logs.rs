@@ -0,0 +1,120 @@
+// OpenCode server logs
+// The server has no endpoint for reading its logs, so they're tailed from disk
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const LEVELS: [&str; 5] = ["DEBUG", "INFO", "WARN", "WARNING", "ERROR"];
+const TAIL_BLOCK: u64 = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogLine {
+    pub timestamp: Option<String>,
+    pub level: Option<String>,
+    pub message: String,
+}
+
+impl LogLine {
+    /// Parses `LEVEL TIMESTAMP message` (either order). Lines that don't match
+    /// come back with only `message` set to the raw line.
+    pub fn parse(line: &str) -> Self {
+        let mut level = None;
+        let mut timestamp = None;
+        let mut rest = line.trim_start();
+        for _ in 0..2 {
+            let (token, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if level.is_none() && LEVELS.contains(&token) {
+                level = Some(token.to_string());
+            } else if timestamp.is_none() && is_timestamp(token) {
+                timestamp = Some(token.to_string());
+            } else {
+                break;
+            }
+            rest = remainder.trim_start();
+        }
+        if level.is_none() && timestamp.is_none() {
+            return Self { timestamp, level, message: line.to_string() };
+        }
+        Self { timestamp, level, message: rest.to_string() }
+    }
+}
+
+fn is_timestamp(token: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(token).is_ok()
+        || chrono::NaiveDateTime::parse_from_str(token, "%Y-%m-%dT%H:%M:%S").is_ok()
+        || chrono::NaiveDateTime::parse_from_str(token, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+}
+
+/// `$XDG_DATA_HOME/opencode/log`, defaulting to `~/.local/share/opencode/log`.
+pub fn default_log_dir() -> Option<PathBuf> {
+    if let Ok(value) = std::env::var("XDG_DATA_HOME") {
+        if !value.trim().is_empty() {
+            return Some(PathBuf::from(value).join("opencode").join("log"));
+        }
+    }
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok()?;
+    Some(PathBuf::from(home).join(".local/share/opencode/log"))
+}
+
+/// The most recently modified `.log` file in `dir`.
+pub fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Reads the last `count` lines of a file, scanning backwards in blocks so
+/// large logs aren't read whole.
+pub fn tail_lines(path: &Path, count: usize) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut buffer: Vec<u8> = Vec::new();
+    while start > 0 && buffer.iter().filter(|b| **b == b'\n').count() <= count {
+        let read = TAIL_BLOCK.min(start);
+        start -= read;
+        file.seek(SeekFrom::Start(start))?;
+        let mut block = vec![0u8; read as usize];
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&buffer);
+        buffer = block;
+    }
+    let text = String::from_utf8_lossy(&buffer);
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_structured_and_raw_lines() {
+        let line = LogLine::parse("INFO  2025-01-09T12:34:56 +12ms service=server request");
+        assert_eq!(line.level.as_deref(), Some("INFO"));
+        assert_eq!(line.timestamp.as_deref(), Some("2025-01-09T12:34:56"));
+        assert_eq!(line.message, "+12ms service=server request");
+
+        let raw = LogLine::parse("panic: something broke");
+        assert_eq!(raw.level, None);
+        assert_eq!(raw.timestamp, None);
+        assert_eq!(raw.message, "panic: something broke");
+    }
+
+    #[test]
+    fn tails_last_lines_across_blocks() {
+        let path = std::env::temp_dir().join(format!("opencode-tail-{}.log", std::process::id()));
+        let content: String = (0..5000).map(|i| format!("INFO line {}\n", i)).collect();
+        std::fs::write(&path, content).unwrap();
+        let lines = tail_lines(&path, 3).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lines, vec!["INFO line 4997", "INFO line 4998", "INFO line 4999"]);
+    }
+}
@@ -1,5 +1,29 @@
+pub mod activity;
+pub mod agents;
 pub mod commands;
 pub mod client;
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod events;
+pub mod files;
+pub mod health;
+pub mod idle;
+pub mod logs;
+pub mod paths;
+pub mod pins;
+pub mod replay;
+pub mod request;
+pub mod search;
+pub mod snapshots;
+pub mod tags;
+pub mod timestamp;
+pub mod titles;
+pub mod trace;
+pub mod transport;
+pub mod usage;
+pub mod version;
+pub mod workspace;
 
 pub use client::OpenCodeClient;
 pub use client::{Session, Message, MessagePart, FileDiff, Agent, HealthResponse};
@@ -0,0 +1,152 @@
+// Workspace-relative paths for display
+// The server mixes absolute and relative paths, and on Windows either
+// separator; everything is compared and shown with `/`
+
+/// Forward slashes, no trailing slash (other than a bare root).
+pub fn normalize_separators(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    match path.trim_end_matches('/') {
+        "" if path.starts_with('/') => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+// Drive-letter paths compare case-insensitively, like the filesystem
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+pub fn is_absolute(path: &str) -> bool {
+    let path = normalize_separators(path);
+    path.starts_with('/') || is_windows_path(&path)
+}
+
+/// `path` relative to `root`. Paths outside the root, and paths that are
+/// already relative, come back normalized but otherwise unchanged.
+pub fn to_relative(root: &str, path: &str) -> String {
+    let path = normalize_separators(path);
+    let root = normalize_separators(root);
+    if !is_absolute(&path) {
+        return path.trim_start_matches("./").to_string();
+    }
+    let matches_root = if is_windows_path(&root) {
+        path.len() >= root.len() && path.is_char_boundary(root.len()) && path[..root.len()].eq_ignore_ascii_case(&root)
+    } else {
+        path.starts_with(&root)
+    };
+    if !matches_root {
+        return path;
+    }
+    match &path[root.len()..] {
+        "" => ".".to_string(),
+        rest if rest.starts_with('/') => rest.trim_start_matches('/').to_string(),
+        // `/work/app-old` is not inside `/work/app`
+        _ if root.ends_with('/') => path[root.len()..].to_string(),
+        _ => path,
+    }
+}
+
+/// `path` joined onto `root` unless it's already absolute.
+pub fn to_absolute(root: &str, path: &str) -> String {
+    let path = normalize_separators(path);
+    if is_absolute(&path) {
+        return path;
+    }
+    let root = normalize_separators(root);
+    match path.trim_start_matches("./") {
+        "" | "." => root,
+        rest if root.ends_with('/') => format!("{}{}", root, rest),
+        rest => format!("{}/{}", root, rest),
+    }
+}
+
+/// Resolves `.` and `..` segments without touching the filesystem. A `..`
+/// above the root of an absolute path is dropped.
+pub fn lexical_normalize(path: &str) -> String {
+    let path = normalize_separators(path);
+    let absolute = is_absolute(&path);
+    let mut segments: Vec<&str> = Vec::new();
+    for (i, segment) in path.split('/').enumerate() {
+        match segment {
+            "" | "." if i > 0 || !absolute => {}
+            ".." => match segments.last() {
+                Some(&last) if last != ".." && !(absolute && segments.len() == 1) => {
+                    segments.pop();
+                }
+                _ if absolute => {}
+                _ => segments.push(segment),
+            },
+            _ => segments.push(segment),
+        }
+    }
+    match segments.as_slice() {
+        [] => ".".to_string(),
+        [""] => "/".to_string(),
+        [drive] if is_windows_path(drive) => format!("{}/", drive),
+        _ => segments.join("/"),
+    }
+}
+
+/// The real path if it exists, resolving symlinks; otherwise the lexically
+/// normalized one.
+pub fn canonicalize(path: &str) -> String {
+    match std::fs::canonicalize(path) {
+        Ok(real) => {
+            let real = real.to_string_lossy();
+            // Windows hands back `\\?\C:\...`
+            normalize_separators(real.strip_prefix(r"\\?\").unwrap_or(&real))
+        }
+        Err(_) => lexical_normalize(path),
+    }
+}
+
+/// Whether `path`, taken relative to `root` if it isn't absolute, is `root`
+/// itself or somewhere under it.
+pub fn is_within(root: &str, path: &str) -> bool {
+    let root = lexical_normalize(root);
+    let path = lexical_normalize(&to_absolute(&root, path));
+    !is_absolute(&to_relative(&root, &path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relativizes_unix_paths() {
+        assert_eq!(to_relative("/work/app/", "/work/app/src/main.rs"), "src/main.rs");
+        assert_eq!(to_relative("/work/app", "/work/app"), ".");
+        assert_eq!(to_relative("/work/app", "/work/app-old/main.rs"), "/work/app-old/main.rs");
+        assert_eq!(to_relative("/work/app", "./src/lib.rs"), "src/lib.rs");
+        assert_eq!(to_relative("/", "/etc/hosts"), "etc/hosts");
+    }
+
+    #[test]
+    fn relativizes_windows_paths() {
+        assert_eq!(to_relative("C:\\Work\\App", "c:\\work\\app\\src\\main.rs"), "src/main.rs");
+        assert_eq!(to_relative("C:/Work/App", "D:/Work/App/main.rs"), "D:/Work/App/main.rs");
+        assert_eq!(to_absolute("C:\\Work\\App\\", "src\\main.rs"), "C:/Work/App/src/main.rs");
+    }
+
+    #[test]
+    fn checks_containment() {
+        assert_eq!(lexical_normalize("/work/app/../lib/./x"), "/work/lib/x");
+        assert_eq!(lexical_normalize("/../etc"), "/etc");
+        assert_eq!(lexical_normalize("C:\\Work\\.."), "C:/");
+        assert!(is_within("/work/app", "/work/app/src"));
+        assert!(is_within("/work/app", "src/../tests"));
+        assert!(is_within("/work/app/", "/work/app"));
+        assert!(!is_within("/work/app", "/work/app/../other"));
+        assert!(!is_within("/work/app", "../app-old"));
+        assert!(is_within("C:\\Work", "c:/work/src"));
+    }
+
+    #[test]
+    fn absolutizes_relative_paths() {
+        assert_eq!(to_absolute("/work/app", "src/main.rs"), "/work/app/src/main.rs");
+        assert_eq!(to_absolute("/work/app", "."), "/work/app");
+        assert_eq!(to_absolute("/work/app", "/etc/hosts"), "/etc/hosts");
+        assert_eq!(to_absolute("/", "etc/hosts"), "/etc/hosts");
+    }
+}
@@ -0,0 +1,92 @@
+// Pinned sessions
+// Kept by the monitor in a JSON file of session ids; pinned sessions are
+// also exempt from auto-abort
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::PathBuf;
+
+#[derive(Debug, Default)]
+pub struct PinStore {
+    path: Option<PathBuf>,
+    ids: BTreeSet<String>,
+}
+
+impl PinStore {
+    /// Reads pins saved at `path`; a missing or unreadable file starts empty.
+    pub fn load(path: PathBuf) -> Self {
+        let ids = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Self { path: Some(path), ids }
+    }
+
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.ids.contains(session_id)
+    }
+
+    pub fn ids(&self) -> HashSet<String> {
+        self.ids.iter().cloned().collect()
+    }
+
+    /// Returns whether anything changed.
+    pub fn set(&mut self, session_id: &str, pinned: bool) -> std::io::Result<bool> {
+        let changed = if pinned {
+            self.ids.insert(session_id.to_string())
+        } else {
+            self.ids.remove(session_id)
+        };
+        if changed {
+            self.save()?;
+        }
+        Ok(changed)
+    }
+
+    /// Drops pins for sessions not in `existing`, e.g. ones deleted on the
+    /// server. Returns the ids removed.
+    pub fn retain_existing(&mut self, existing: &HashSet<&str>) -> std::io::Result<Vec<String>> {
+        let stale: Vec<String> = self
+            .ids
+            .iter()
+            .filter(|id| !existing.contains(id.as_str()))
+            .cloned()
+            .collect();
+        if !stale.is_empty() {
+            self.ids.retain(|id| existing.contains(id.as_str()));
+            self.save()?;
+        }
+        Ok(stale)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_string_pretty(&self.ids)?;
+        std::fs::write(path, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_persist_and_prune() {
+        let path = std::env::temp_dir().join(format!("opencode-pins-{}.json", uuid::Uuid::new_v4()));
+        let mut pins = PinStore::load(path.clone());
+        assert!(pins.set("ses_1", true).unwrap());
+        assert!(pins.set("ses_2", true).unwrap());
+        assert!(!pins.set("ses_2", true).unwrap());
+
+        let mut reloaded = PinStore::load(path.clone());
+        assert!(reloaded.contains("ses_1") && reloaded.contains("ses_2"));
+        let stale = reloaded.retain_existing(&HashSet::from(["ses_2"])).unwrap();
+        assert_eq!(stale, vec!["ses_1"]);
+        assert!(!PinStore::load(path.clone()).contains("ses_1"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+// Timing for replaying a session's messages at the pace they were written
+
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::opencode::client::Message;
+
+pub const DEFAULT_REPLAY_SPEED: f64 = 1.0;
+// Used when either side of a gap has no usable timestamp
+const FALLBACK_REPLAY_GAP: Duration = Duration::from_secs(1);
+// A session left overnight shouldn't stall the replay, whatever the speed
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayMessage {
+    pub session_id: String,
+    pub index: usize,
+    pub total: usize,
+    pub message: Message,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayComplete {
+    pub session_id: String,
+    /// True when the replay was stopped before the last message.
+    pub stopped: bool,
+}
+
+/// How long to wait before each message: zero for the first, then the
+/// original gap (capped) divided by `speed`. A speed so small the result
+/// doesn't fit in a `Duration` waits `Duration::MAX`.
+pub fn replay_delays(messages: &[Message], speed: f64) -> Vec<Duration> {
+    let mut delays = Vec::with_capacity(messages.len());
+    let mut previous: Option<&Message> = None;
+    for message in messages {
+        let gap = match previous {
+            None => Duration::ZERO,
+            Some(previous) => match (previous.created_at.as_datetime(), message.created_at.as_datetime()) {
+                (Some(before), Some(after)) => (after - before)
+                    .to_std()
+                    .unwrap_or(Duration::ZERO)
+                    .min(MAX_REPLAY_GAP),
+                _ => FALLBACK_REPLAY_GAP,
+            },
+        };
+        delays.push(Duration::try_from_secs_f64(gap.as_secs_f64() / speed).unwrap_or(Duration::MAX));
+        previous = Some(message);
+    }
+    delays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(created_at: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "id": created_at,
+            "role": "assistant",
+            "content": "",
+            "parts": [],
+            "created_at": created_at,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn scales_and_caps_gaps() {
+        let messages = [
+            message("2025-01-01T00:00:00Z"),
+            message("2025-01-01T00:00:04Z"),
+            message("2025-01-01T05:00:00Z"),
+            message("not a time"),
+        ];
+        assert_eq!(
+            replay_delays(&messages, 2.0),
+            vec![
+                Duration::ZERO,
+                Duration::from_secs(2),
+                Duration::from_secs(15),
+                Duration::from_millis(500),
+            ]
+        );
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        let messages = [message("2025-01-01T00:00:00Z"), message("2025-01-01T00:00:04Z")];
+        assert_eq!(replay_delays(&messages, 1e-300), vec![Duration::ZERO, Duration::MAX]);
+    }
+}
@@ -0,0 +1,288 @@
+// Outgoing OpenCode requests
+// Every call goes through `Request::send`, which tags the correlation id,
+// adds the bearer token, turns `429`s into `RateLimited` (retrying GETs if
+// configured) and `401`s into `Unauthorized` (after one retry with a
+// refreshed token, if there's a refresh hook) and, with `log_bodies` on,
+// logs what was sent and received. The request is then handed to the
+// transport, `reqwest` unless the client was given another
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, IntoUrl, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::opencode::config::ClientConfig;
+use crate::opencode::error::OpenCodeError;
+use crate::opencode::trace::{current_request_id, REQUEST_ID_HEADER};
+use crate::opencode::transport::{dispatch, ReqwestTransport, Transport};
+
+const REDACTED_HEADERS: [HeaderName; 3] = [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE];
+// For a `429` without `Retry-After`
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(1);
+// Longer waits are handed back to the caller rather than slept through
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+pub(crate) struct Http {
+    client: Client,
+    transport: Arc<dyn Transport>,
+    // Set for custom transports, which don't get the client's default headers
+    headers: Option<HeaderMap>,
+    config: Arc<RwLock<ClientConfig>>,
+    log_bodies: bool,
+    rate_limit_retries: u32,
+}
+
+impl Http {
+    /// `config` is shared so a refreshed token reaches later requests too.
+    /// Requests are built with `client` and sent through `transport`, or
+    /// through `client` itself when there's none.
+    pub(crate) fn new(client: Client, transport: Option<Arc<dyn Transport>>, config: Arc<RwLock<ClientConfig>>) -> Self {
+        let (log_bodies, rate_limit_retries, headers) = {
+            let config = config.read().unwrap();
+            let headers = transport.as_ref().map(|_| config.default_headers().unwrap_or_default());
+            (config.log_bodies, config.rate_limit_retries, headers)
+        };
+        let transport = transport.unwrap_or_else(|| Arc::new(ReqwestTransport::new(client.clone())));
+        Self { client, transport, headers, config, log_bodies, rate_limit_retries }
+    }
+
+    pub(crate) fn get<U: IntoUrl>(&self, url: U) -> Request {
+        self.request(self.client.get(url), self.rate_limit_retries)
+    }
+
+    pub(crate) fn post<U: IntoUrl>(&self, url: U) -> Request {
+        self.request(self.client.post(url), 0)
+    }
+
+    pub(crate) fn delete<U: IntoUrl>(&self, url: U) -> Request {
+        self.request(self.client.delete(url), 0)
+    }
+
+    fn request(&self, builder: RequestBuilder, rate_limit_retries: u32) -> Request {
+        let builder = match current_request_id() {
+            Some(id) => builder.header(REQUEST_ID_HEADER, id),
+            None => builder,
+        };
+        let builder = match &self.headers {
+            Some(headers) => builder.headers(headers.clone()),
+            None => builder,
+        };
+        Request {
+            builder,
+            transport: self.transport.clone(),
+            config: self.config.clone(),
+            log_bodies: self.log_bodies,
+            streaming: false,
+            rate_limit_retries,
+        }
+    }
+}
+
+pub(crate) struct Request {
+    builder: RequestBuilder,
+    transport: Arc<dyn Transport>,
+    config: Arc<RwLock<ClientConfig>>,
+    log_bodies: bool,
+    streaming: bool,
+    // Only GETs get any; other methods aren't safe to repeat
+    rate_limit_retries: u32,
+}
+
+impl Request {
+    pub(crate) fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        self.map(|builder| builder.query(query))
+    }
+
+    pub(crate) fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        self.map(|builder| builder.json(json))
+    }
+
+    pub(crate) fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.map(|builder| builder.header(key, value))
+    }
+
+    /// Marks the response as a stream, whose body is never buffered for
+    /// logging.
+    pub(crate) fn streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn build(self) -> reqwest::Result<reqwest::Request> {
+        let token = self.config.read().unwrap().auth_token.clone();
+        authorized(self.builder, token.as_deref()).build()
+    }
+
+    fn map(mut self, f: impl FnOnce(RequestBuilder) -> RequestBuilder) -> Self {
+        self.builder = f(self.builder);
+        self
+    }
+
+    pub(crate) async fn send(self) -> Result<Response, OpenCodeError> {
+        let Request { mut builder, transport, config, log_bodies, streaming, rate_limit_retries } = self;
+        let (mut token, mut refresh) = {
+            let config = config.read().unwrap();
+            (config.auth_token.clone(), config.token_refresh.clone())
+        };
+        let mut retries_left = rate_limit_retries;
+        loop {
+            let retry = if retries_left > 0 || refresh.is_some() { builder.try_clone() } else { None };
+            let response = send_once(transport.as_ref(), authorized(builder, token.as_deref()), log_bodies, streaming).await?;
+            if response.status() == StatusCode::UNAUTHORIZED {
+                // Only one refresh per request, so a bad hook can't loop
+                let (Some(next), Some(hook)) = (retry, refresh.take()) else {
+                    return Err(OpenCodeError::Unauthorized);
+                };
+                let fresh = match hook.refresh().await {
+                    Ok(fresh) => fresh,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "opencode token refresh failed");
+                        return Err(OpenCodeError::Unauthorized);
+                    }
+                };
+                config.write().unwrap().auth_token = Some(fresh.clone());
+                token = Some(fresh);
+                builder = next;
+                continue;
+            }
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+            let retry_after = retry_after(response.headers(), chrono::Utc::now());
+            let wait = retry_after.unwrap_or(DEFAULT_RATE_LIMIT_WAIT);
+            // The clone may only be there for a token refresh; a request out
+            // of rate limit retries, or never given any, isn't sent again
+            match retry {
+                Some(next) if retries_left > 0 && wait <= MAX_RATE_LIMIT_WAIT => {
+                    tracing::debug!(wait_ms = wait.as_millis() as u64, "opencode rate limited, retrying");
+                    tokio::time::sleep(wait).await;
+                    builder = next;
+                    retries_left -= 1;
+                }
+                _ => return Err(OpenCodeError::RateLimited { retry_after }),
+            }
+        }
+    }
+}
+
+fn authorized(builder: RequestBuilder, token: Option<&str>) -> RequestBuilder {
+    match token.filter(|token| !token.is_empty()) {
+        Some(token) => builder.bearer_auth(token),
+        None => builder,
+    }
+}
+
+async fn send_once(
+    transport: &dyn Transport,
+    builder: RequestBuilder,
+    log_bodies: bool,
+    streaming: bool,
+) -> Result<Response, OpenCodeError> {
+    let request = builder.build()?;
+    if !log_bodies {
+        return dispatch(transport, request, streaming).await;
+    }
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    tracing::debug!(
+        method = %request.method(),
+        url = %request.url(),
+        headers = %redacted(request.headers()),
+        body = %body,
+        "opencode request"
+    );
+
+    let response = dispatch(transport, request, streaming).await?;
+    if streaming {
+        tracing::debug!(status = %response.status(), "opencode response (streamed)");
+        return Ok(response);
+    }
+    Ok(log_response(response).await?)
+}
+
+/// `Retry-After` as either delay seconds or an HTTP date; dates in the past
+/// mean no wait.
+pub(crate) fn retry_after(headers: &HeaderMap, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+fn redacted(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if REDACTED_HEADERS.contains(name) {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Reads the body for the log, then hands callers an equivalent response
+async fn log_response(response: Response) -> reqwest::Result<Response> {
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    tracing::debug!(
+        status = %status,
+        headers = %redacted(&headers),
+        body = %String::from_utf8_lossy(&body),
+        "opencode response"
+    );
+
+    let mut rebuilt = http::Response::new(body);
+    *rebuilt.status_mut() = status;
+    *rebuilt.version_mut() = version;
+    *rebuilt.headers_mut() = headers;
+    Ok(Response::from(rebuilt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers.insert("x-client-id", HeaderValue::from_static("ci"));
+        let logged = redacted(&headers);
+        assert!(logged.contains("authorization: <redacted>"));
+        assert!(logged.contains("x-client-id: ci"));
+        assert!(!logged.contains("secret"));
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&chrono::Utc);
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:30 GMT"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(30)));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:00:00 GMT"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,90 @@
+// Results of the `/find` endpoint
+// Older servers send bare paths; newer ones send ripgrep-style matches with
+// `{ "text": ... }` wrappers, line numbers and submatch offsets
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One hit. `line` and `column` are 1-based.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub path: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub snippet: Option<String>,
+}
+
+impl SearchMatch {
+    /// `None` for entries without a usable path.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        if let Some(path) = value.as_str() {
+            return Some(Self { path: path.to_string(), ..Self::default() });
+        }
+        let path = text(value.get("path")?)?;
+        let line = number(value.get("line").or_else(|| value.get("line_number")));
+        let column = number(value.get("column")).or_else(|| {
+            // Submatch offsets are 0-based bytes into the line
+            value
+                .get("submatches")?
+                .get(0)?
+                .get("start")?
+                .as_u64()
+                .map(|start| start as u32 + 1)
+        });
+        let snippet = value
+            .get("snippet")
+            .or_else(|| value.get("lines"))
+            .and_then(text)
+            .map(|snippet| snippet.trim_end_matches(['\r', '\n']).to_string());
+        Some(Self { path, line, column, snippet })
+    }
+}
+
+pub fn parse_matches(values: &[Value]) -> Vec<SearchMatch> {
+    values.iter().filter_map(SearchMatch::from_value).collect()
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Object(fields) => fields.get("text")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn number(value: Option<&Value>) -> Option<u32> {
+    value?.as_u64().map(|n| n as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_plain_and_ripgrep_matches() {
+        let matches = parse_matches(&[
+            json!("src/main.rs"),
+            json!({
+                "path": { "text": "src/lib.rs" },
+                "lines": { "text": "fn run() {\n" },
+                "line_number": 12,
+                "submatches": [{ "match": { "text": "run" }, "start": 3, "end": 6 }]
+            }),
+            json!({ "path": "README.md", "line": 4, "column": 2, "snippet": "hello" }),
+            json!({ "line": 1 }),
+        ]);
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[0], SearchMatch { path: "src/main.rs".into(), ..SearchMatch::default() });
+        assert_eq!(
+            matches[1],
+            SearchMatch {
+                path: "src/lib.rs".into(),
+                line: Some(12),
+                column: Some(4),
+                snippet: Some("fn run() {".into()),
+            }
+        );
+        assert_eq!((matches[2].line, matches[2].column), (Some(4), Some(2)));
+    }
+}
@@ -0,0 +1,110 @@
+// Ad-hoc before/after captures of workspace files, for reviewing edits that
+// fall outside a session's own diff
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use crate::opencode::diff::unified_diff;
+use crate::opencode::error::OpenCodeError;
+
+pub const DEFAULT_SNAPSHOT_LIMIT: usize = 32;
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    id: String,
+    path: String,
+    content: String,
+}
+
+/// The most recent snapshots, oldest evicted first. Ids hash the path and
+/// content, so capturing an unchanged file again reuses its snapshot.
+#[derive(Debug)]
+pub struct SnapshotStore {
+    snapshots: VecDeque<Snapshot>,
+    limit: usize,
+}
+
+impl Default for SnapshotStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_SNAPSHOT_LIMIT)
+    }
+}
+
+fn snapshot_id(path: &str, content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+impl SnapshotStore {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    pub fn insert(&mut self, path: &str, content: String) -> String {
+        let id = snapshot_id(path, &content);
+        if let Some(pos) = self.snapshots.iter().position(|snapshot| snapshot.id == id) {
+            let snapshot = self.snapshots.remove(pos).unwrap();
+            self.snapshots.push_back(snapshot);
+            return id;
+        }
+        while self.snapshots.len() >= self.limit {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot {
+            id: id.clone(),
+            path: path.to_string(),
+            content,
+        });
+        id
+    }
+
+    fn get(&self, id: &str) -> Result<&Snapshot, OpenCodeError> {
+        self.snapshots
+            .iter()
+            .find(|snapshot| snapshot.id == id)
+            .ok_or_else(|| OpenCodeError::UnknownSnapshot(id.to_string()))
+    }
+
+    /// Unified diff from snapshot `id_a` to `id_b`; empty when they match.
+    pub fn diff(&self, id_a: &str, id_b: &str) -> Result<String, OpenCodeError> {
+        let a = self.get(id_a)?;
+        let b = self.get(id_b)?;
+        unified_diff(Some(&a.path), &a.content, &b.path, &b.content)
+            .map_err(|err| OpenCodeError::Server(err.message().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_two_snapshots_of_a_file() {
+        let mut store = SnapshotStore::default();
+        let before = store.insert("src/lib.rs", "fn a() {}\n".to_string());
+        let after = store.insert("src/lib.rs", "fn a() {}\nfn b() {}\n".to_string());
+        assert_ne!(before, after);
+        assert_eq!(store.insert("src/lib.rs", "fn a() {}\n".to_string()), before);
+
+        let diff = store.diff(&before, &after).unwrap();
+        assert!(diff.contains("+++ b/src/lib.rs"));
+        assert!(diff.contains("+fn b() {}"));
+        assert!(store.diff(&before, &before).unwrap().is_empty());
+    }
+
+    #[test]
+    fn evicts_the_oldest_snapshot() {
+        let mut store = SnapshotStore::new(2);
+        let first = store.insert("a.txt", "1".to_string());
+        let second = store.insert("a.txt", "2".to_string());
+        store.insert("a.txt", "3".to_string());
+        assert!(matches!(store.diff(&first, &second), Err(OpenCodeError::UnknownSnapshot(id)) if id == first));
+        assert!(store.diff(&second, &second).is_ok());
+    }
+}
@@ -0,0 +1,86 @@
+// Session tags kept by the monitor
+// OpenCode has no tag API, so tags live in a JSON file keyed by session id
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+#[derive(Debug, Default)]
+pub struct TagStore {
+    path: Option<PathBuf>,
+    tags: HashMap<String, BTreeSet<String>>,
+}
+
+impl TagStore {
+    /// Reads tags saved at `path`; a missing or unreadable file starts empty.
+    pub fn load(path: PathBuf) -> Self {
+        let tags = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Self { path: Some(path), tags }
+    }
+
+    /// The session's tags, sorted.
+    pub fn tags(&self, session_id: &str) -> Vec<String> {
+        self.tags
+            .get(session_id)
+            .map(|tags| tags.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, session_id: &str, tag: &str) -> std::io::Result<Vec<String>> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Tag is empty"));
+        }
+        if self.tags.entry(session_id.to_string()).or_default().insert(tag.to_string()) {
+            self.save()?;
+        }
+        Ok(self.tags(session_id))
+    }
+
+    pub fn remove(&mut self, session_id: &str, tag: &str) -> std::io::Result<Vec<String>> {
+        let Some(tags) = self.tags.get_mut(session_id) else {
+            return Ok(Vec::new());
+        };
+        if tags.remove(tag.trim()) {
+            if tags.is_empty() {
+                self.tags.remove(session_id);
+            }
+            self.save()?;
+        }
+        Ok(self.tags(session_id))
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_string_pretty(&self.tags)?;
+        std::fs::write(path, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_persist_across_loads() {
+        let path = std::env::temp_dir().join(format!("opencode-tags-{}.json", uuid::Uuid::new_v4()));
+        let mut store = TagStore::load(path.clone());
+        assert_eq!(store.add("ses_1", " review ").unwrap(), vec!["review"]);
+        assert_eq!(store.add("ses_1", "bug").unwrap(), vec!["bug", "review"]);
+        assert!(store.add("ses_1", "  ").is_err());
+
+        let mut reloaded = TagStore::load(path.clone());
+        assert_eq!(reloaded.tags("ses_1"), vec!["bug", "review"]);
+        assert_eq!(reloaded.remove("ses_1", "bug").unwrap(), vec!["review"]);
+        assert_eq!(reloaded.remove("ses_2", "bug").unwrap(), Vec::<String>::new());
+        assert_eq!(TagStore::load(path.clone()).tags("ses_1"), vec!["review"]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
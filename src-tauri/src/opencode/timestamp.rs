@@ -0,0 +1,149 @@
+// Timestamps as sent by OpenCode
+// Servers use RFC3339 strings or epoch milliseconds depending on the build
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A parsed timestamp, or the raw value when it couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Timestamp {
+    Parsed(DateTime<Utc>),
+    Unparsed(String),
+}
+
+impl Default for Timestamp {
+    fn default() -> Self {
+        Timestamp::Unparsed(String::new())
+    }
+}
+
+// Values above this are milliseconds; below it, seconds (year ~5138 in seconds)
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+fn from_epoch(value: i64) -> Option<DateTime<Utc>> {
+    if value.abs() >= EPOCH_MILLIS_THRESHOLD {
+        Utc.timestamp_millis_opt(value).single()
+    } else {
+        Utc.timestamp_opt(value, 0).single()
+    }
+}
+
+impl Timestamp {
+    pub fn parse(raw: &str) -> Self {
+        let trimmed = raw.trim();
+        if let Ok(parsed) = DateTime::parse_from_rfc3339(trimmed) {
+            return Timestamp::Parsed(parsed.with_timezone(&Utc));
+        }
+        if let Some(parsed) = trimmed.parse::<i64>().ok().and_then(from_epoch) {
+            return Timestamp::Parsed(parsed);
+        }
+        Timestamp::Unparsed(raw.to_string())
+    }
+
+    pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Timestamp::Parsed(parsed) => Some(*parsed),
+            Timestamp::Unparsed(_) => None,
+        }
+    }
+}
+
+/// Unparsed timestamps sort before every parsed one.
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Timestamp::Parsed(a), Timestamp::Parsed(b)) => a.cmp(b),
+            (Timestamp::Parsed(_), Timestamp::Unparsed(_)) => Ordering::Greater,
+            (Timestamp::Unparsed(_), Timestamp::Parsed(_)) => Ordering::Less,
+            (Timestamp::Unparsed(a), Timestamp::Unparsed(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Timestamp::Parsed(parsed) => serializer.serialize_str(&parsed.to_rfc3339()),
+            Timestamp::Unparsed(raw) => serializer.serialize_str(raw),
+        }
+    }
+}
+
+struct TimestampVisitor;
+
+impl<'de> Visitor<'de> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an RFC3339 string or epoch milliseconds")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Timestamp, E> {
+        Ok(Timestamp::parse(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Timestamp, E> {
+        Ok(from_epoch(value)
+            .map(Timestamp::Parsed)
+            .unwrap_or_else(|| Timestamp::Unparsed(value.to_string())))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Timestamp, E> {
+        match i64::try_from(value) {
+            Ok(value) => self.visit_i64(value),
+            Err(_) => Ok(Timestamp::Unparsed(value.to_string())),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Timestamp, E> {
+        self.visit_i64(value as i64)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Timestamp, E> {
+        Ok(Timestamp::default())
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn from_json(value: serde_json::Value) -> Timestamp {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_rfc3339_and_epoch_forms() {
+        let expected = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(from_json(json!("2024-01-02T03:04:05Z")), Timestamp::Parsed(expected));
+        assert_eq!(from_json(json!("2024-01-02T05:04:05+02:00")), Timestamp::Parsed(expected));
+        assert_eq!(from_json(json!(expected.timestamp_millis())), Timestamp::Parsed(expected));
+        assert_eq!(from_json(json!(expected.timestamp())), Timestamp::Parsed(expected));
+        assert_eq!(from_json(json!(expected.timestamp_millis().to_string())), Timestamp::Parsed(expected));
+    }
+
+    #[test]
+    fn keeps_raw_value_when_unparseable() {
+        let raw = from_json(json!("yesterday"));
+        assert_eq!(raw, Timestamp::Unparsed("yesterday".to_string()));
+        assert_eq!(serde_json::to_value(&raw).unwrap(), json!("yesterday"));
+        assert_eq!(from_json(json!(null)), Timestamp::default());
+        assert!(raw < Timestamp::parse("2024-01-01T00:00:00Z"));
+    }
+}
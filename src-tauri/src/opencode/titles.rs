@@ -0,0 +1,56 @@
+// Default titles for sessions created without one
+// Templates take `{date}` (local, YYYY-MM-DD) and `{n}`, a counter that
+// starts at 1 each time the template is set
+
+use chrono::NaiveDate;
+
+pub const DEFAULT_TITLE_TEMPLATE: &str = "New Session";
+
+#[derive(Debug)]
+pub struct TitleTemplate {
+    template: String,
+    next: u64,
+}
+
+impl Default for TitleTemplate {
+    fn default() -> Self {
+        Self::new(DEFAULT_TITLE_TEMPLATE)
+    }
+}
+
+impl TitleTemplate {
+    pub fn new(template: &str) -> Self {
+        Self { template: template.to_string(), next: 1 }
+    }
+
+    /// The next title; `{n}` only advances when the template uses it.
+    pub fn next_title(&mut self, date: NaiveDate) -> String {
+        let title = render_title(&self.template, date, self.next);
+        if self.template.contains("{n}") {
+            self.next += 1;
+        }
+        title
+    }
+}
+
+pub fn render_title(template: &str, date: NaiveDate, n: u64) -> String {
+    template
+        .replace("{date}", &date.format("%Y-%m-%d").to_string())
+        .replace("{n}", &n.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders_and_counts() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+        let mut titles = TitleTemplate::new("Run {n} ({date})");
+        assert_eq!(titles.next_title(date), "Run 1 (2025-03-09)");
+        assert_eq!(titles.next_title(date), "Run 2 (2025-03-09)");
+
+        let mut plain = TitleTemplate::default();
+        assert_eq!(plain.next_title(date), "New Session");
+    }
+}
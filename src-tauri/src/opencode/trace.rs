@@ -0,0 +1,77 @@
+// Correlation ids tying a UI action to the OpenCode requests it caused
+
+use serde::Serialize;
+use std::fmt::Display;
+use std::future::Future;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// A command's result along with the correlation id its requests carried.
+#[derive(Debug, Clone, Serialize)]
+pub struct Traced<T> {
+    pub request_id: String,
+    pub value: T,
+}
+
+impl<T> Traced<T> {
+    /// The same id with the value transformed, for commands that post-process
+    /// what the client returned.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Traced<U> {
+        Traced { request_id: self.request_id, value: f(self.value) }
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The correlation id of the command currently running on this task, if any.
+/// Tasks spawned from a command don't inherit it.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Runs one command invocation under a fresh correlation id. Every OpenCode
+/// request it makes carries the id as `X-Request-Id`. The id comes back with
+/// the result, or in the error message, so either can be quoted in bug
+/// reports.
+pub async fn traced<T, E, F>(command: &'static str, call: F) -> Result<Traced<T>, String>
+where
+    E: Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let span = tracing::info_span!("opencode_command", command, request_id = %request_id);
+    match REQUEST_ID.scope(request_id.clone(), call.instrument(span)).await {
+        Ok(value) => Ok(Traced { request_id, value }),
+        Err(e) => Err(format!("{} (request id {})", e, request_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scopes_the_id_to_the_command() {
+        assert_eq!(current_request_id(), None);
+        let result: Result<Traced<()>, String> = traced("test", async {
+            assert!(current_request_id().is_some());
+            Err("boom")
+        })
+        .await;
+        assert!(result.unwrap_err().starts_with("boom (request id "));
+        assert_eq!(current_request_id(), None);
+    }
+
+    #[tokio::test]
+    async fn returns_the_id_with_a_successful_result() {
+        let first = traced("test", async { Ok::<_, String>(current_request_id()) }).await.unwrap();
+        assert_eq!(first.value.as_deref(), Some(first.request_id.as_str()));
+
+        let second = traced("test", async { Ok::<_, String>(2) }).await.unwrap().map(|n| n * 2);
+        assert_eq!(second.value, 4);
+        assert_ne!(second.request_id, first.request_id);
+    }
+}
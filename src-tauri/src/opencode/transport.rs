@@ -0,0 +1,65 @@
+// Pluggable HTTP backend for the OpenCode client
+// Requests reach the transport fully built, with auth, correlation id and
+// client headers set. Responses go back as `reqwest::Response`, which a
+// custom transport can make from an `http::Response` with `Response::from`
+
+use reqwest::{Client, Method, Request, Response};
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::opencode::error::OpenCodeError;
+
+pub type TransportFuture = Pin<Box<dyn Future<Output = Result<Response, OpenCodeError>> + Send>>;
+
+pub trait Transport: Send + Sync {
+    fn get(&self, request: Request) -> TransportFuture;
+    fn post(&self, request: Request) -> TransportFuture;
+    fn delete(&self, request: Request) -> TransportFuture;
+
+    /// A GET whose body is read as it arrives, like `/event`. Plain `get`
+    /// unless the transport needs to treat long-lived responses differently.
+    fn stream(&self, request: Request) -> TransportFuture {
+        self.get(request)
+    }
+}
+
+/// Sends through a `reqwest` client. Used unless the client is given
+/// another transport.
+#[derive(Clone)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    fn execute(&self, request: Request) -> TransportFuture {
+        let client = self.client.clone();
+        Box::pin(async move { Ok(client.execute(request).await?) })
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn get(&self, request: Request) -> TransportFuture {
+        self.execute(request)
+    }
+
+    fn post(&self, request: Request) -> TransportFuture {
+        self.execute(request)
+    }
+
+    fn delete(&self, request: Request) -> TransportFuture {
+        self.execute(request)
+    }
+}
+
+pub(crate) fn dispatch(transport: &dyn Transport, request: Request, streaming: bool) -> TransportFuture {
+    match *request.method() {
+        Method::POST => transport.post(request),
+        Method::DELETE => transport.delete(request),
+        _ if streaming => transport.stream(request),
+        _ => transport.get(request),
+    }
+}
@@ -0,0 +1,104 @@
+// Token usage and cost, summed from assistant messages
+// OpenCode puts `tokens` (input, output, reasoning, cache read/write) and
+// `cost` on each assistant message, sometimes nested under `info`; older
+// servers send a flat `usage` object instead
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ops::AddAssign;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+    /// As estimated by the server, in its billing currency (USD for the
+    /// built-in providers).
+    pub cost: f64,
+}
+
+impl Usage {
+    /// `None` for messages that carry no usage at all, like user messages.
+    pub fn from_message(message: &Value) -> Option<Self> {
+        let info = message.get("info").unwrap_or(message);
+        let tokens = info.get("tokens").or_else(|| info.get("usage"));
+        let cost = info.get("cost").and_then(Value::as_f64);
+        if tokens.is_none() && cost.is_none() {
+            return None;
+        }
+        let count = |pointers: &[&str]| {
+            tokens
+                .and_then(|tokens| pointers.iter().find_map(|pointer| tokens.pointer(pointer)))
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        };
+        Some(Self {
+            input_tokens: count(&["/input", "/input_tokens", "/prompt_tokens"]),
+            output_tokens: count(&["/output", "/output_tokens", "/completion_tokens"]),
+            reasoning_tokens: count(&["/reasoning", "/reasoning_tokens"]),
+            cache_read_tokens: count(&["/cache/read", "/cache_read_tokens"]),
+            cache_write_tokens: count(&["/cache/write", "/cache_write_tokens"]),
+            cost: cost.unwrap_or(0.0),
+        })
+    }
+
+    /// `None` when no message had usage.
+    pub fn from_messages(messages: &[Value]) -> Option<Self> {
+        messages.iter().filter_map(Self::from_message).fold(None, |total, usage| {
+            let mut total = total.unwrap_or_default();
+            total += usage;
+            Some(total)
+        })
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.reasoning_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage summed over every session. Sessions whose messages couldn't be
+/// fetched are listed in `failed` rather than failing the whole total.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TotalUsage {
+    pub usage: Usage,
+    pub total_tokens: u64,
+    pub sessions: usize,
+    pub sessions_without_usage: usize,
+    pub failed: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sums_message_usage() {
+        let messages = [
+            json!({ "role": "user", "content": "hi" }),
+            json!({ "info": { "tokens": { "input": 100, "output": 20, "reasoning": 5, "cache": { "read": 50, "write": 10 } }, "cost": 0.25 } }),
+            json!({ "usage": { "prompt_tokens": 10, "completion_tokens": 2 } }),
+        ];
+        let usage = Usage::from_messages(&messages).unwrap();
+        assert_eq!(usage.input_tokens, 110);
+        assert_eq!(usage.output_tokens, 22);
+        assert_eq!(usage.cache_read_tokens, 50);
+        assert_eq!(usage.total_tokens(), 197);
+        assert_eq!(usage.cost, 0.25);
+
+        assert_eq!(Usage::from_messages(&messages[..1]), None);
+    }
+}
@@ -0,0 +1,80 @@
+// OpenCode server version parsing and feature gating
+
+use semver::Version;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServerFeature {
+    Streaming,
+    MessageAbort,
+    FileWrite,
+    MessagePreview,
+}
+
+impl ServerFeature {
+    pub const ALL: [ServerFeature; 4] = [
+        ServerFeature::Streaming,
+        ServerFeature::MessageAbort,
+        ServerFeature::FileWrite,
+        ServerFeature::MessagePreview,
+    ];
+
+    pub fn min_version(self) -> Version {
+        match self {
+            ServerFeature::Streaming => Version::new(0, 3, 0),
+            ServerFeature::MessageAbort => Version::new(0, 4, 0),
+            ServerFeature::FileWrite => Version::new(0, 5, 0),
+            ServerFeature::MessagePreview => Version::new(0, 6, 0),
+        }
+    }
+}
+
+/// Parses a server version string, tolerating a leading `v` and a missing
+/// patch component. Pre-release and build metadata are kept.
+pub fn parse_version(raw: &str) -> Option<Version> {
+    let trimmed = raw.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    if let Ok(version) = Version::parse(trimmed) {
+        return Some(version);
+    }
+    // "1.2" or "1.2-beta": insert the missing patch before any suffix
+    let split = trimmed.find(['-', '+']).unwrap_or(trimmed.len());
+    let (core, suffix) = trimmed.split_at(split);
+    if core.split('.').count() == 2 {
+        return Version::parse(&format!("{}.0{}", core, suffix)).ok();
+    }
+    None
+}
+
+/// Pre-release builds of a version count as that version, so dev servers get
+/// the features of the release they're heading towards.
+pub fn supports(version: &Version, feature: ServerFeature) -> bool {
+    let release = Version::new(version.major, version.minor, version.patch);
+    release >= feature.min_version()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prerelease_and_build_metadata() {
+        let version = parse_version("v0.4.1-beta.2+abc123").unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (0, 4, 1));
+        assert_eq!(version.pre.as_str(), "beta.2");
+        assert_eq!(version.build.as_str(), "abc123");
+        assert_eq!(parse_version("1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(parse_version("dev"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn gates_features_by_minimum_version() {
+        let version = parse_version("0.4.0-rc.1").unwrap();
+        assert!(supports(&version, ServerFeature::Streaming));
+        assert!(supports(&version, ServerFeature::MessageAbort));
+        assert!(!supports(&version, ServerFeature::FileWrite));
+        assert!(!supports(&version, ServerFeature::MessagePreview));
+    }
+}
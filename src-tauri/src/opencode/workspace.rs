@@ -0,0 +1,101 @@
+// Workspace context for the project the OpenCode server is editing
+
+use serde::{Deserialize, Serialize};
+
+use crate::opencode::Message;
+
+pub const GIT_STATUS_COMMAND: &str = "git status --porcelain=v2 --branch";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceStatus {
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+    pub detached: bool,
+    pub dirty: bool,
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+impl WorkspaceStatus {
+    /// Parses `git status --porcelain=v2 --branch` output.
+    pub fn parse_porcelain_v2(output: &str) -> Self {
+        let mut status = WorkspaceStatus::default();
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                if let Some(head) = header.strip_prefix("branch.head ") {
+                    let head = head.trim();
+                    if head == "(detached)" {
+                        status.detached = true;
+                    } else {
+                        status.branch = Some(head.to_string());
+                    }
+                } else if let Some(counts) = header.strip_prefix("branch.ab ") {
+                    for count in counts.split_whitespace() {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or(0);
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                continue;
+            }
+            // Ordinary, renamed, unmerged and untracked entries all count as dirty
+            if matches!(line.chars().next(), Some('1' | '2' | 'u' | '?')) {
+                status.dirty = true;
+            }
+        }
+        status
+    }
+}
+
+/// Collects the text a shell command produced from the messages `run_shell` returns.
+pub fn shell_output(messages: &[Message]) -> String {
+    let mut output = String::new();
+    for message in messages {
+        let parts: Vec<&str> = message
+            .parts
+            .iter()
+            .filter_map(|part| part.content.as_deref())
+            .collect();
+        if parts.is_empty() {
+            output.push_str(&message.content);
+            output.push('\n');
+        } else {
+            for part in parts {
+                output.push_str(part);
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_branch_with_tracking_counts() {
+        let output = "# branch.oid 1f2e3d\n\
+                      # branch.head main\n\
+                      # branch.upstream origin/main\n\
+                      # branch.ab +2 -1\n\
+                      1 .M N... 100644 100644 100644 aaa bbb src/lib.rs\n";
+        let status = WorkspaceStatus::parse_porcelain_v2(output);
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert!(!status.detached);
+        assert!(status.dirty);
+        assert_eq!((status.ahead, status.behind), (2, 1));
+    }
+
+    #[test]
+    fn parses_detached_clean_head() {
+        let output = "# branch.oid 1f2e3d\n# branch.head (detached)\n";
+        let status = WorkspaceStatus::parse_porcelain_v2(output);
+        assert_eq!(status.branch, None);
+        assert!(status.detached);
+        assert!(!status.dirty);
+        assert_eq!((status.ahead, status.behind), (0, 0));
+    }
+}
@@ -0,0 +1,77 @@
+// Approval prompts from Pi runs that ask before risky actions
+// A stdout line matching the configured pattern opens a request; the answer
+// is written to the run's stdin, by the user or, after the timeout, as a deny
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::ChildStdin;
+use tokio::sync::Mutex as TokioMutex;
+
+pub const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(120);
+const APPROVE_ANSWER: &[u8] = b"y\n";
+const DENY_ANSWER: &[u8] = b"n\n";
+
+/// One run's stdin and the request waiting on it, if any.
+#[derive(Debug, Default)]
+pub struct ApprovalGate {
+    stdin: TokioMutex<Option<ChildStdin>>,
+    pending: StdMutex<Option<u64>>,
+    next_id: AtomicU64,
+}
+
+impl ApprovalGate {
+    /// Switches the gate to a new run, dropping anything left pending.
+    pub async fn attach(&self, stdin: Option<ChildStdin>) {
+        *self.pending.lock().unwrap() = None;
+        *self.stdin.lock().await = stdin;
+    }
+
+    /// Opens a request and returns its id, or `None` while one is already
+    /// waiting, so a prompt printed twice asks once.
+    pub fn open(&self) -> Option<u64> {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_some() {
+            return None;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        *pending = Some(id);
+        Some(id)
+    }
+
+    /// Answers the pending request, or only request `id` when given, so a
+    /// late timeout can't answer a newer prompt. `false` when there was
+    /// nothing to answer.
+    pub async fn answer(&self, id: Option<u64>, approve: bool) -> std::io::Result<bool> {
+        if !self.claim(id) {
+            return Ok(false);
+        }
+        self.write_answer(approve).await?;
+        Ok(true)
+    }
+
+    /// Closes the pending request (only request `id` when given) without
+    /// answering it yet; `false` when there was nothing to close. The caller
+    /// then owes the run a `write_answer`.
+    pub fn claim(&self, id: Option<u64>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match (*pending, id) {
+            (None, _) => false,
+            (Some(current), Some(id)) if current != id => false,
+            _ => {
+                *pending = None;
+                true
+            }
+        }
+    }
+
+    pub async fn write_answer(&self, approve: bool) -> std::io::Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        let Some(stdin) = stdin.as_mut() else {
+            return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Pi run has no stdin"));
+        };
+        stdin.write_all(if approve { APPROVE_ANSWER } else { DENY_ANSWER }).await?;
+        stdin.flush().await
+    }
+}
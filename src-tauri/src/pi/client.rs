@@ -2,19 +2,215 @@
 // Runs Pi CLI with gpt-5.2-codex and custom Copilot prompt
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::process::Stdio;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex as TokioMutex;
+use tokio::sync::{broadcast, mpsc, watch, Mutex as TokioMutex, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::opencode::paths;
+use crate::pi::approval::{ApprovalGate, DEFAULT_APPROVAL_TIMEOUT};
+use crate::pi::env;
+use crate::pi::events::{parse_events, PiEvent};
+use crate::pi::log::{self as pi_log, PiLog, DEFAULT_LOG_MAX_BYTES};
+use crate::pi::models::{parse_models_json, ModelInfo};
+use crate::pi::output::{PiApprovalRequest, PiLine, PiRunStatus, PiState, PiStream, PiTail, TaggedLine};
+use crate::pi::queue::{PiQueueStatus, QueuedRun, RunQueue};
+use crate::pi::summary::{parse_summary, PiRunSummary};
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_MAX_CONCURRENT_RUNS: usize = 4;
+const LINE_CHANNEL_CAPACITY: usize = 256;
+const LINE_BROADCAST_CAPACITY: usize = 1024;
+const STATE_BROADCAST_CAPACITY: usize = 64;
+const APPROVAL_BROADCAST_CAPACITY: usize = 64;
+const NO_OUTPUT_TIMEOUT_MARKER: &str = "[pi: no output timeout]";
+const APPROVAL_TIMEOUT_MARKER: &str = "[pi: approval timed out, denied]";
+// Well under Linux's 128 KiB limit on a single argument
+const DEFAULT_PROMPT_FILE_THRESHOLD: usize = 32 * 1024;
+const DEFAULT_PI_BINARY: &str = "pi";
+
+fn default_prompt_file_threshold() -> usize {
+    DEFAULT_PROMPT_FILE_THRESHOLD
+}
+
+fn default_log_max_bytes() -> u64 {
+    DEFAULT_LOG_MAX_BYTES
+}
+
+fn default_approval_timeout() -> Duration {
+    DEFAULT_APPROVAL_TIMEOUT
+}
+
+/// How the `pi` binary is started. `Shell` goes through a login shell, for
+/// setups where `pi` is only on PATH (or an alias) in the user's profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PiLauncher {
+    #[default]
+    Direct,
+    Shell,
+}
+
+impl PiLauncher {
+    fn command(self, binary: &str, args: &[String]) -> Result<Command, std::io::Error> {
+        match self {
+            PiLauncher::Direct => {
+                let mut cmd = Command::new(binary);
+                cmd.args(args);
+                Ok(cmd)
+            }
+            #[cfg(not(target_os = "windows"))]
+            PiLauncher::Shell => {
+                let mut cmd = Command::new("sh");
+                cmd.arg("-lc").arg(shell_command_line(binary, args));
+                Ok(cmd)
+            }
+            #[cfg(target_os = "windows")]
+            PiLauncher::Shell => {
+                // Passed raw: std's quoting is for argv parsers, not for cmd
+                let line = cmd_command_line(binary, args)?;
+                let mut cmd = Command::new("cmd");
+                cmd.raw_arg("/D /S /C").raw_arg(format!("\"{}\"", line));
+                Ok(cmd)
+            }
+        }
+    }
+}
+
+/// Checks that `path` is an executable file before it's saved as
+/// `binary_path`, so a typo shows up now rather than on the next run.
+pub fn validate_binary_path(path: &str) -> Result<(), std::io::Error> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("Pi binary {}: {}", path, e)))?;
+    if !metadata.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Pi binary {} is not a file", path),
+        ));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("Pi binary {} is not executable", path),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `binary` plus `args`, quoted for `sh -c`.
+fn shell_command_line(binary: &str, args: &[String]) -> String {
+    let mut line = shell_words::quote(binary).into_owned();
+    for arg in args {
+        line.push(' ');
+        line.push_str(&shell_words::quote(arg));
+    }
+    line
+}
+
+/// `binary` plus `args` for `cmd /S /C`: each argument quoted the way the
+/// program will parse it, then every cmd metacharacter `^`-escaped so cmd
+/// passes the line through untouched. Fails on line breaks, which cmd can't
+/// carry inside an argument.
+#[cfg(target_os = "windows")]
+fn cmd_command_line(binary: &str, args: &[String]) -> Result<String, std::io::Error> {
+    let mut quoted = Vec::with_capacity(args.len() + 1);
+    for arg in std::iter::once(binary).chain(args.iter().map(String::as_str)) {
+        if arg.contains(['\n', '\r']) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the shell launcher can't pass line breaks to cmd; use the direct launcher",
+            ));
+        }
+        quoted.push(quote_windows_arg(arg));
+    }
+    let mut line = String::new();
+    for c in quoted.join(" ").chars() {
+        if "()%!^\"<>&|".contains(c) {
+            line.push('^');
+        }
+        line.push(c);
+    }
+    Ok(line)
+}
+
+// Quoted for the usual Windows argv rules: backslashes only escape when
+// they come before a quote
+#[cfg(target_os = "windows")]
+fn quote_windows_arg(arg: &str) -> String {
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        let escapes = if c == '"' { backslashes * 2 + 1 } else { backslashes };
+        quoted.push_str(&"\\".repeat(escapes));
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PiConfig {
     pub model: String,
     pub thinking: String,
     pub system_prompt: String,
     pub provider: String,
+    /// When set, the system prompt is read from this file at spawn time.
+    #[serde(default)]
+    pub system_prompt_path: Option<String>,
+    /// Short names expanded to full model ids, e.g. `codex` -> `gpt-5.2-codex`.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Prompts longer than this many bytes are passed to Pi as an `@file`
+    /// argument instead of inline, to stay clear of argument length limits.
+    #[serde(default = "default_prompt_file_threshold")]
+    pub prompt_file_threshold: usize,
+    /// A stdout line containing this marks the run as waiting for input,
+    /// before the process exits. Pi prints no fixed end-of-turn line, so
+    /// this is off until set.
+    #[serde(default)]
+    pub completion_marker: Option<String>,
+    #[serde(default)]
+    pub launcher: PiLauncher,
+    /// Full path to `pi` for installs that aren't on PATH.
+    #[serde(default)]
+    pub binary_path: Option<String>,
+    /// Kills a run that prints nothing, on either stream, for this long.
+    /// Off by default, since a long think can be silent. Taken from the
+    /// manager at each run, like the approval settings.
+    #[serde(default)]
+    pub no_output_timeout: Option<Duration>,
+    /// Each session's output is also appended to `<log_dir>/<session>.log`.
+    /// The manager's setting, with `log_max_bytes`, is read at each run.
+    #[serde(default)]
+    pub log_dir: Option<String>,
+    /// Size a log file may reach before it's rotated; 0 never rotates.
+    #[serde(default = "default_log_max_bytes")]
+    pub log_max_bytes: u64,
+    /// A stdout line containing this is an approval prompt, answered on
+    /// stdin through `pi_respond_approval`. Off until set; runs only get a
+    /// piped stdin while it is. Like `approval_timeout`, the manager's value
+    /// applies to every session's next run.
+    #[serde(default)]
+    pub approval_pattern: Option<String>,
+    /// Unanswered approval prompts are denied after this long.
+    #[serde(default = "default_approval_timeout")]
+    pub approval_timeout: Duration,
 }
 
 impl Default for PiConfig {
@@ -24,11 +220,59 @@ impl Default for PiConfig {
             thinking: "xhigh".to_string(),
             system_prompt: PiConfig::default_system_prompt(),
             provider: "github-copilot".to_string(),
+            system_prompt_path: None,
+            aliases: HashMap::new(),
+            prompt_file_threshold: DEFAULT_PROMPT_FILE_THRESHOLD,
+            completion_marker: None,
+            launcher: PiLauncher::Direct,
+            binary_path: None,
+            no_output_timeout: None,
+            log_dir: None,
+            log_max_bytes: DEFAULT_LOG_MAX_BYTES,
+            approval_pattern: None,
+            approval_timeout: DEFAULT_APPROVAL_TIMEOUT,
         }
     }
 }
 
 impl PiConfig {
+    /// The prompt from `system_prompt_path`, falling back to the inline
+    /// `system_prompt` when no path is set or the file can't be read.
+    pub async fn resolve_system_prompt(&self) -> String {
+        if let Some(path) = self.system_prompt_path.as_deref() {
+            if let Ok(content) = tokio::fs::read_to_string(path).await {
+                return content;
+            }
+        }
+        self.system_prompt.clone()
+    }
+
+    /// Takes the settings the manager holds for every run, whatever config
+    /// the session was created with.
+    fn apply_run_settings(&mut self, manager: &PiConfig) {
+        self.no_output_timeout = manager.no_output_timeout;
+        self.log_dir = manager.log_dir.clone();
+        self.log_max_bytes = manager.log_max_bytes;
+        self.approval_pattern = manager.approval_pattern.clone();
+        self.approval_timeout = manager.approval_timeout;
+    }
+
+    /// Expands `model` if it's an alias; anything else passes through as-is.
+    pub fn resolve_model(&self, model: &str) -> String {
+        self.aliases.get(model).cloned().unwrap_or_else(|| model.to_string())
+    }
+
+    /// Where `session_id`'s output is logged, when `log_dir` is set.
+    pub fn log_path(&self, session_id: &str) -> Option<PathBuf> {
+        let dir = self.log_dir.as_deref().filter(|dir| !dir.is_empty())?;
+        Some(pi_log::log_path(dir, session_id))
+    }
+
+    /// `binary_path` if set, otherwise `pi` from PATH.
+    pub fn binary(&self) -> &str {
+        self.binary_path.as_deref().unwrap_or(DEFAULT_PI_BINARY)
+    }
+
     pub fn default_system_prompt() -> String {
         r#"You are a coding agent based on GPT-5-Codex.
 
@@ -76,6 +320,41 @@ pub struct PiSession {
     pub config: PiConfig,
     pub process: Option<Child>,
     pub output: Arc<TokioMutex<Vec<String>>>,
+    /// Stdout and stderr interleaved in arrival order.
+    pub combined: Arc<TokioMutex<Vec<TaggedLine>>>,
+    /// Model id the current run was started with, after alias expansion.
+    pub resolved_model: Option<String>,
+    line_sink: Option<broadcast::Sender<PiLine>>,
+    state_sink: Option<broadcast::Sender<PiState>>,
+    approval_sink: Option<broadcast::Sender<PiApprovalRequest>>,
+    approval: Arc<ApprovalGate>,
+    awaiting_input: Arc<AtomicBool>,
+    // Set by the watchdog when it stops a silent run
+    stalled: Arc<AtomicBool>,
+    killed: bool,
+    readers: Vec<JoinHandle<()>>,
+    // Dropping the sender also wakes the readers, so a dropped session never
+    // leaves them parked on an open pipe
+    reader_cancel: Option<watch::Sender<bool>>,
+    // Set for failures that don't show up in the exit status: spawn errors,
+    // kills and timeouts
+    last_error: Option<String>,
+    exit_status: Option<ExitStatus>,
+    last_stderr_line: Arc<TokioMutex<Option<String>>>,
+    // Temp file holding an oversized prompt, removed once the run exits
+    prompt_file: Option<PathBuf>,
+    /// Saved prompt the current run was started from, if any.
+    pub prompt_name: Option<String>,
+    // Prompt and workdir of the latest spawn, for restarts
+    last_run: Option<(String, String)>,
+    // Pids of live children across the manager's sessions. A pid stays in
+    // here only until its exit is reaped, so it can't have been reused
+    known_pids: Option<Arc<StdMutex<HashSet<u32>>>>,
+    pid: Option<u32>,
+    // Environment of the latest spawn, unredacted
+    spawn_env: Option<BTreeMap<String, String>>,
+    // The manager's config, re-read on each spawn for its run-wide settings
+    manager_config: Option<Arc<StdMutex<PiConfig>>>,
 }
 
 impl PiSession {
@@ -85,127 +364,842 @@ impl PiSession {
             config: config.unwrap_or_default(),
             process: None,
             output: Arc::new(TokioMutex::new(Vec::new())),
+            combined: Arc::new(TokioMutex::new(Vec::new())),
+            resolved_model: None,
+            line_sink: None,
+            state_sink: None,
+            approval_sink: None,
+            approval: Arc::new(ApprovalGate::default()),
+            awaiting_input: Arc::new(AtomicBool::new(false)),
+            stalled: Arc::new(AtomicBool::new(false)),
+            killed: false,
+            readers: Vec::new(),
+            reader_cancel: None,
+            last_error: None,
+            exit_status: None,
+            last_stderr_line: Arc::new(TokioMutex::new(None)),
+            prompt_file: None,
+            prompt_name: None,
+            last_run: None,
+            known_pids: None,
+            pid: None,
+            spawn_env: None,
+            manager_config: None,
+        }
+    }
+
+    /// The prompt argument for `-p`: the prompt itself, or `@<file>` with the
+    /// prompt written to a temp file when it's over the configured threshold.
+    fn prompt_arg(&mut self, prompt: &str) -> Result<String, std::io::Error> {
+        if prompt.len() <= self.config.prompt_file_threshold {
+            return Ok(prompt.to_string());
+        }
+        let path = std::env::temp_dir().join(format!("pi-prompt-{}.md", uuid::Uuid::new_v4()));
+        std::fs::write(&path, prompt)?;
+        let arg = format!("@{}", path.display());
+        self.prompt_file = Some(path);
+        Ok(arg)
+    }
+
+    fn forget_pid(&mut self) {
+        if let (Some(pid), Some(known)) = (self.pid.take(), &self.known_pids) {
+            known.lock().unwrap().remove(&pid);
+        }
+    }
+
+    fn remove_prompt_file(&mut self) {
+        if let Some(path) = self.prompt_file.take() {
+            std::fs::remove_file(path).ok();
         }
     }
 
     pub async fn spawn(&mut self, prompt: &str, workdir: &str) -> Result<(), std::io::Error> {
-        let mut cmd = Command::new("pi");
-        
+        self.stop_readers().await;
+        self.remove_prompt_file();
+        self.last_error = None;
+        self.exit_status = None;
+        self.awaiting_input.store(false, Ordering::SeqCst);
+        self.stalled.store(false, Ordering::SeqCst);
+        self.killed = false;
+        *self.last_stderr_line.lock().await = None;
+        if let Some(manager) = self.manager_config.as_ref() {
+            self.config.apply_run_settings(&manager.lock().unwrap());
+        }
+        let model = self.config.resolve_model(&self.config.model);
         // Configure Pi with our settings
-        cmd.arg("--provider").arg(&self.config.provider);
-        cmd.arg("--model").arg(&self.config.model);
-        cmd.arg("--thinking").arg(&self.config.thinking);
-        cmd.arg("-p").arg(prompt);
+        let args = vec![
+            "--provider".to_string(),
+            self.config.provider.clone(),
+            "--model".to_string(),
+            model.clone(),
+            "--thinking".to_string(),
+            self.config.thinking.clone(),
+            "--system-prompt".to_string(),
+            self.config.resolve_system_prompt().await,
+            "-p".to_string(),
+            self.prompt_arg(prompt)?,
+        ];
+        let mut cmd = match self.config.launcher.command(self.config.binary(), &args) {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                self.last_error = Some(format!("Failed to start pi: {}", err));
+                self.remove_prompt_file();
+                return Err(err);
+            }
+        };
         
         // Set GitHub token if available
-        if let Ok(token) = std::env::var("GITHUB_TOKEN") {
-            cmd.env("GITHUB_TOKEN", token);
-        }
+        cmd.envs(env::forwarded_env());
+        self.spawn_env = Some(env::spawn_env());
         
         // Set working directory
         cmd.current_dir(workdir);
+
+        // Approval prompts are answered on stdin
+        let approval_pattern = self.config.approval_pattern.clone().filter(|pattern| !pattern.is_empty());
+        if approval_pattern.is_some() {
+            cmd.stdin(Stdio::piped());
+        }
         
         // Pipe output
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
         
-        let mut child = cmd.spawn()?;
-        self.process = Some(child);
-        
-        // Read output in background
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                self.last_error = Some(format!("Failed to start pi: {}", err));
+                self.remove_prompt_file();
+                return Err(err);
+            }
+        };
+        self.forget_pid();
+        self.pid = child.id();
+        if let (Some(pid), Some(known)) = (self.pid, &self.known_pids) {
+            known.lock().unwrap().insert(pid);
+        }
+        self.approval.attach(child.stdin.take()).await;
+        let (cancel, cancelled) = watch::channel(false);
+        self.reader_cancel = Some(cancel);
+
+        // Both pipes feed one channel so the combined view keeps arrival order
+        let (tx, mut rx) = mpsc::channel::<TaggedLine>(LINE_CHANNEL_CAPACITY);
+        // For lines of our own, without keeping the loop alive past the pipes
+        let own_lines = tx.downgrade();
+        if let Some(stdout) = child.stdout.take() {
+            self.readers.push(spawn_pipe_reader(stdout, PiStream::Stdout, tx.clone(), cancelled.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            self.readers.push(spawn_pipe_reader(stderr, PiStream::Stderr, tx, cancelled));
+        }
+
         let output = self.output.clone();
-        if let Some(stdout) = self.process.as_mut().unwrap().stdout.take() {
-            tokio::spawn(async move {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
-                while let Some(line) = lines.next_line().await.ok().flatten() {
-                    output.lock().await.push(line);
+        let combined = self.combined.clone();
+        let last_stderr_line = self.last_stderr_line.clone();
+        let line_sink = self.line_sink.clone();
+        let state_sink = self.state_sink.clone();
+        let awaiting_input = self.awaiting_input.clone();
+        let marker = self.config.completion_marker.clone().filter(|marker| !marker.is_empty());
+        let session_id = self.id.clone();
+        let watchdog = self.config.no_output_timeout.filter(|limit| !limit.is_zero());
+        let stalled = self.stalled.clone();
+        let pid = self.pid;
+        let known_pids = self.known_pids.clone();
+        let mut log = self.config.log_path(&self.id).map(|path| PiLog::new(path, self.config.log_max_bytes));
+        let approval = self.approval.clone();
+        let approval_sink = self.approval_sink.clone();
+        let approval_timeout = self.config.approval_timeout;
+        self.readers.push(tokio::spawn(async move {
+            loop {
+                let next = match watchdog {
+                    Some(limit) if !stalled.load(Ordering::SeqCst) => {
+                        match tokio::time::timeout(limit, rx.recv()).await {
+                            Ok(next) => next,
+                            Err(_) => {
+                                // A reaped pid may already belong to something else
+                                let live = pid.filter(|pid| match &known_pids {
+                                    Some(known) => known.lock().unwrap().contains(pid),
+                                    None => true,
+                                });
+                                if let Some(pid) = live {
+                                    stalled.store(true, Ordering::SeqCst);
+                                    let line = NO_OUTPUT_TIMEOUT_MARKER.to_string();
+                                    if let Some(log) = log.as_mut() {
+                                        log.append(PiStream::Stdout, &line);
+                                    }
+                                    output.lock().await.push(line.clone());
+                                    combined.lock().await.push(TaggedLine { stream: PiStream::Stdout, line: line.clone() });
+                                    if let Some(sink) = &line_sink {
+                                        sink.send(PiLine { session_id: session_id.clone(), stream: PiStream::Stdout, line }).ok();
+                                    }
+                                    kill_process(pid).ok();
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    _ => rx.recv().await,
+                };
+                let Some(tagged) = next else { break };
+                if let Some(log) = log.as_mut() {
+                    log.append(tagged.stream, &tagged.line);
                 }
-            });
-        }
+                if let (Some(marker), PiStream::Stdout) = (&marker, tagged.stream) {
+                    let awaiting = tagged.line.contains(marker.as_str());
+                    if awaiting_input.swap(awaiting, Ordering::SeqCst) != awaiting {
+                        if let Some(sink) = &state_sink {
+                            sink.send(PiState { session_id: session_id.clone(), awaiting_input: awaiting }).ok();
+                        }
+                    }
+                }
+                if let (Some(pattern), PiStream::Stdout) = (&approval_pattern, tagged.stream) {
+                    if tagged.line.contains(pattern.as_str()) && tagged.line != APPROVAL_TIMEOUT_MARKER {
+                        if let Some(id) = approval.open() {
+                            if let Some(sink) = &approval_sink {
+                                sink.send(PiApprovalRequest {
+                                    session_id: session_id.clone(),
+                                    id,
+                                    prompt: tagged.line.clone(),
+                                    timeout_secs: approval_timeout.as_secs(),
+                                })
+                                .ok();
+                            }
+                            deny_after_timeout(approval.clone(), id, approval_timeout, own_lines.clone());
+                        }
+                    }
+                }
+                match tagged.stream {
+                    PiStream::Stdout => output.lock().await.push(tagged.line.clone()),
+                    // Only the last stderr line is kept, as the failure reason
+                    PiStream::Stderr if !tagged.line.trim().is_empty() => {
+                        *last_stderr_line.lock().await = Some(tagged.line.clone());
+                    }
+                    PiStream::Stderr => {}
+                }
+                combined.lock().await.push(tagged.clone());
+                if let Some(sink) = &line_sink {
+                    sink.send(PiLine {
+                        session_id: session_id.clone(),
+                        stream: tagged.stream,
+                        line: tagged.line,
+                    })
+                    .ok();
+                }
+            }
+        }));
+        self.process = Some(child);
+        self.resolved_model = Some(model);
+        self.last_run = Some((prompt.to_string(), workdir.to_string()));
         
         Ok(())
     }
 
+    /// Drops everything the previous runs printed and their error state.
+    async fn clear_output(&mut self) {
+        self.output.lock().await.clear();
+        self.combined.lock().await.clear();
+        *self.last_stderr_line.lock().await = None;
+        self.last_error = None;
+        self.awaiting_input.store(false, Ordering::SeqCst);
+    }
+
+    /// Signals the output readers to stop and waits for them to exit.
+    async fn stop_readers(&mut self) {
+        if let Some(cancel) = self.reader_cancel.take() {
+            cancel.send(true).ok();
+        }
+        for reader in self.readers.drain(..) {
+            reader.abort();
+            reader.await.ok();
+        }
+    }
+
+    /// Whether the run has printed its completion marker and gone quiet.
+    pub fn is_awaiting_input(&self) -> bool {
+        self.awaiting_input.load(Ordering::SeqCst)
+    }
+
     pub async fn wait(&mut self) -> Result<(), std::io::Error> {
         if let Some(proc) = self.process.as_mut() {
-            proc.wait().await?;
+            self.exit_status = Some(proc.wait().await?);
+            self.remove_prompt_file();
+            self.forget_pid();
+            self.approval.attach(None).await;
         }
         Ok(())
     }
 
     pub async fn kill(&mut self) {
         if let Some(proc) = self.process.as_mut() {
+            if proc.try_wait().ok().flatten().is_none() {
+                self.last_error = Some("Run was killed".to_string());
+                self.killed = true;
+            }
             proc.kill().await.ok();
         }
+        self.forget_pid();
+        self.stop_readers().await;
+        self.remove_prompt_file();
+        self.approval.attach(None).await;
     }
 
     pub fn is_running(&mut self) -> bool {
         match self.process {
-            Some(ref mut proc) => proc.try_wait().ok().flatten().is_none(),
+            Some(ref mut proc) => match proc.try_wait() {
+                Ok(Some(status)) => {
+                    self.exit_status = Some(status);
+                    self.remove_prompt_file();
+                    self.forget_pid();
+                    false
+                }
+                _ => true,
+            },
             None => false,
         }
     }
 
+    /// One-line reason the last run failed, if it did.
+    pub async fn last_error(&mut self) -> Option<String> {
+        if self.stalled.load(Ordering::SeqCst) {
+            let limit = self.config.no_output_timeout.unwrap_or_default();
+            return Some(format!("Run was stopped after {}s without output", limit.as_secs()));
+        }
+        if self.last_error.is_some() {
+            return self.last_error.clone();
+        }
+        self.is_running();
+        let status = self.exit_status.filter(|status| !status.success())?;
+        Some(match self.last_stderr_line.lock().await.as_deref() {
+            Some(line) => format!("Pi exited with {}: {}", status, line),
+            None => format!("Pi exited with {}", status),
+        })
+    }
+
+    /// Working directory of the latest run.
+    pub fn workdir(&self) -> Option<&str> {
+        self.last_run.as_ref().map(|(_, workdir)| workdir.as_str())
+    }
+
+    /// The environment the latest run was spawned with, or the one the
+    /// next run would get, with secrets redacted.
+    pub fn effective_env(&self) -> BTreeMap<String, String> {
+        env::redact_env(self.spawn_env.clone().unwrap_or_else(env::spawn_env))
+    }
+
+    pub fn status(&mut self) -> PiRunStatus {
+        if self.stalled.load(Ordering::SeqCst) {
+            return PiRunStatus::NoOutputTimeout;
+        }
+        if self.killed {
+            return PiRunStatus::Killed;
+        }
+        if self.is_running() {
+            return PiRunStatus::Running;
+        }
+        match self.exit_status {
+            Some(status) if status.success() => PiRunStatus::Succeeded,
+            Some(_) => PiRunStatus::Failed,
+            None if self.last_error.is_some() => PiRunStatus::Failed,
+            None => PiRunStatus::Idle,
+        }
+    }
+
     pub async fn get_output(&self) -> Vec<String> {
         self.output.lock().await.clone()
     }
+
+    pub async fn get_combined_output(&self) -> Vec<TaggedLine> {
+        self.combined.lock().await.clone()
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn kill_process(pid: u32) -> Result<bool, std::io::Error> {
+    if unsafe { libc::kill(pid as i32, libc::SIGKILL) } == 0 {
+        return Ok(true);
+    }
+    let err = std::io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::ESRCH) => Ok(false),
+        _ => Err(err),
+    }
 }
 
+#[cfg(target_os = "windows")]
+fn kill_process(pid: u32) -> Result<bool, std::io::Error> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .status()?;
+    Ok(status.success())
+}
+
+fn spawn_pipe_reader<R>(
+    pipe: R,
+    stream: PiStream,
+    tx: mpsc::Sender<TaggedLine>,
+    mut cancelled: watch::Receiver<bool>,
+) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    // Reads raw bytes and decodes lossily: `lines()` would end the reader on
+    // the first byte that isn't valid UTF-8
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            tokio::select! {
+                _ = cancelled.changed() => break,
+                read = reader.read_until(b'\n', &mut buf) => match read {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        let line = decode_line(&buf);
+                        if tx.send(TaggedLine { stream, line }).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, ?stream, "pi output reader stopped");
+                        break;
+                    }
+                },
+            }
+        }
+    })
+}
+
+// Denies approval request `id` if it's still open after `timeout`. The
+// marker goes through the reader loop like any output line, and ahead of
+// the answer so it comes before whatever the run prints in response
+fn deny_after_timeout(approval: Arc<ApprovalGate>, id: u64, timeout: Duration, lines: mpsc::WeakSender<TaggedLine>) {
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        if !approval.claim(Some(id)) {
+            return;
+        }
+        if let Some(lines) = lines.upgrade() {
+            let line = APPROVAL_TIMEOUT_MARKER.to_string();
+            lines.send(TaggedLine { stream: PiStream::Stdout, line }).await.ok();
+        }
+        if let Err(e) = approval.write_answer(false).await {
+            tracing::warn!(error = %e, "failed to deny timed out pi approval");
+        }
+    });
+}
+
+// Strips the line ending like `lines()` does, replacing invalid UTF-8 with U+FFFD
+fn decode_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+impl Drop for PiSession {
+    fn drop(&mut self) {
+        self.remove_prompt_file();
+        self.forget_pid();
+        for reader in self.readers.drain(..) {
+            reader.abort();
+        }
+    }
+}
+
+type SessionMap = Arc<TokioMutex<HashMap<String, Arc<TokioMutex<PiSession>>>>>;
+
+/// Each session sits behind its own lock so operations on one session
+/// serialize while different sessions run in parallel. At most
+/// `max_concurrent` runs execute at once; the rest wait in a FIFO queue.
 pub struct PiManager {
-    sessions: Arc<TokioMutex<HashMap<String, PiSession>>>,
+    sessions: SessionMap,
     default_config: Arc<StdMutex<PiConfig>>,
+    queue: Arc<StdMutex<RunQueue>>,
+    slots: Arc<Semaphore>,
+    max_concurrent: usize,
+    lines: broadcast::Sender<PiLine>,
+    states: broadcast::Sender<PiState>,
+    approvals: broadcast::Sender<PiApprovalRequest>,
+    known_pids: Arc<StdMutex<HashSet<u32>>>,
 }
 
 impl PiManager {
     pub fn new() -> Self {
+        Self::with_max_concurrent(DEFAULT_MAX_CONCURRENT_RUNS)
+    }
+
+    pub fn with_max_concurrent(max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
         Self {
             sessions: Arc::new(TokioMutex::new(HashMap::new())),
             default_config: Arc::new(StdMutex::new(PiConfig::default())),
+            queue: Arc::new(StdMutex::new(RunQueue::default())),
+            slots: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            lines: broadcast::channel(LINE_BROADCAST_CAPACITY).0,
+            states: broadcast::channel(STATE_BROADCAST_CAPACITY).0,
+            approvals: broadcast::channel(APPROVAL_BROADCAST_CAPACITY).0,
+            known_pids: Arc::new(StdMutex::new(HashSet::new())),
         }
     }
 
+    fn new_session(&self, id: &str, config: Option<PiConfig>) -> Arc<TokioMutex<PiSession>> {
+        let mut session = PiSession::new(id, config);
+        session.line_sink = Some(self.lines.clone());
+        session.state_sink = Some(self.states.clone());
+        session.approval_sink = Some(self.approvals.clone());
+        session.known_pids = Some(self.known_pids.clone());
+        session.manager_config = Some(self.default_config.clone());
+        Arc::new(TokioMutex::new(session))
+    }
+
+    /// Every output line of every session, as it arrives.
+    pub fn subscribe_lines(&self) -> broadcast::Receiver<PiLine> {
+        self.lines.subscribe()
+    }
+
+    /// Completion-marker state changes of every session.
+    pub fn subscribe_states(&self) -> broadcast::Receiver<PiState> {
+        self.states.subscribe()
+    }
+
+    /// Approval prompts of every session, as they're printed.
+    pub fn subscribe_approvals(&self) -> broadcast::Receiver<PiApprovalRequest> {
+        self.approvals.subscribe()
+    }
+
     pub async fn create_session(&self, id: &str, config: Option<PiConfig>) {
+        let session = self.new_session(id, config);
         let mut sessions = self.sessions.lock().await;
-        sessions.insert(id.to_string(), PiSession::new(id, config));
+        sessions.insert(id.to_string(), session);
     }
 
-    pub async fn run(&self, session_id: &str, prompt: &str, workdir: &str) -> Result<(), std::io::Error> {
+    async fn session(&self, id: &str) -> Option<Arc<TokioMutex<PiSession>>> {
+        self.sessions.lock().await.get(id).cloned()
+    }
+
+    async fn session_or_default(&self, id: &str) -> Arc<TokioMutex<PiSession>> {
+        let config = self.get_config();
         let mut sessions = self.sessions.lock().await;
-        if let Some(session) = sessions.get_mut(session_id) {
-            session.spawn(prompt, workdir).await
+        sessions
+            .entry(id.to_string())
+            .or_insert_with(|| self.new_session(id, Some(config)))
+            .clone()
+    }
+
+    fn is_queued(&self, session_id: &str) -> bool {
+        self.queue.lock().unwrap().contains(session_id)
+    }
+
+    /// Starts the run right away when a slot is free, otherwise queues it.
+    pub async fn run(&self, session_id: &str, prompt: &str, workdir: &str) -> Result<(), std::io::Error> {
+        self.run_as(session_id, prompt, workdir, None).await
+    }
+
+    /// Like `run`, recording that `prompt` was rendered from the saved prompt
+    /// `prompt_name`.
+    pub async fn run_prompt(
+        &self,
+        session_id: &str,
+        prompt_name: &str,
+        prompt: &str,
+        workdir: &str,
+    ) -> Result<(), std::io::Error> {
+        self.run_as(session_id, prompt, workdir, Some(prompt_name)).await
+    }
+
+    async fn run_as(
+        &self,
+        session_id: &str,
+        prompt: &str,
+        workdir: &str,
+        prompt_name: Option<&str>,
+    ) -> Result<(), std::io::Error> {
+        let handle = self.session_or_default(session_id).await;
+        let mut session = handle.lock().await;
+        if session.is_running() || self.is_queued(session_id) {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "Session is already running"));
+        }
+        session.prompt_name = prompt_name.map(str::to_string);
+
+        let queue_empty = self.queue.lock().unwrap().is_empty();
+        if queue_empty {
+            if let Ok(permit) = self.slots.clone().try_acquire_owned() {
+                session.spawn(prompt, workdir).await?;
+                drop(session);
+                tokio::spawn(supervise_slot(self.sessions.clone(), self.queue.clone(), handle, permit));
+                return Ok(());
+            }
+        }
+
+        self.queue.lock().unwrap().push_back(QueuedRun {
+            session_id: session_id.to_string(),
+            prompt: prompt.to_string(),
+            workdir: workdir.to_string(),
+        });
+        drop(session);
+        self.start_queued().await;
+        Ok(())
+    }
+
+    // Covers the window where a slot frees up between the queue check in
+    // `run` and the push.
+    async fn start_queued(&self) {
+        while let Ok(permit) = self.slots.clone().try_acquire_owned() {
+            let next = {
+                let mut queue = self.queue.lock().unwrap();
+                match queue.pop_front() {
+                    Some(next) => next,
+                    None => {
+                        drop(permit);
+                        return;
+                    }
+                }
+            };
+            let Some(handle) = self.session(&next.session_id).await else {
+                continue;
+            };
+            let spawned = handle.lock().await.spawn(&next.prompt, &next.workdir).await;
+            if spawned.is_ok() {
+                tokio::spawn(supervise_slot(self.sessions.clone(), self.queue.clone(), handle, permit));
+            }
+        }
+    }
+
+    pub async fn queue_status(&self) -> PiQueueStatus {
+        let handles: Vec<(String, Arc<TokioMutex<PiSession>>)> = self.sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, handle)| (id.clone(), handle.clone()))
+            .collect();
+        let mut running = Vec::new();
+        for (id, handle) in handles {
+            if handle.lock().await.is_running() {
+                running.push(id);
+            }
+        }
+        running.sort();
+        PiQueueStatus {
+            running,
+            queued: self.queue.lock().unwrap().session_ids(),
+            max_concurrent: self.max_concurrent,
+        }
+    }
+
+    /// Moves a queued (not yet running) session to `position` in the queue.
+    pub fn reorder_queue(&self, session_id: &str, position: usize) -> Result<(), std::io::Error> {
+        if self.queue.lock().unwrap().reorder(session_id, position) {
+            Ok(())
         } else {
-            Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Session not found"))
+            Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Session is not queued"))
         }
     }
 
     pub async fn wait(&self, session_id: &str) -> Result<(), std::io::Error> {
-        let mut sessions = self.sessions.lock().await;
-        if let Some(session) = sessions.get_mut(session_id) {
-            session.wait().await
-        } else {
-            Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Session not found"))
+        let Some(session) = self.session(session_id).await else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Session not found"));
+        };
+        // Poll instead of holding the session lock for the whole run, so a
+        // concurrent kill can still get in
+        loop {
+            {
+                let mut session = session.lock().await;
+                if !session.is_running() && !self.is_queued(session_id) {
+                    return session.wait().await;
+                }
+            }
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
         }
     }
 
     pub async fn kill(&self, session_id: &str) {
-        let mut sessions = self.sessions.lock().await;
-        if let Some(session) = sessions.get_mut(session_id) {
+        self.queue.lock().unwrap().remove(session_id);
+        if let Some(session) = self.session(session_id).await {
+            session.lock().await.kill().await;
+        }
+    }
+
+    /// Kills the session's run if it's still going, clears its output and
+    /// starts it again with the same prompt, workdir and config. Fails for a
+    /// session that has never been run.
+    pub async fn restart(&self, session_id: &str) -> Result<(), std::io::Error> {
+        let Some(handle) = self.session(session_id).await else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Session not found"));
+        };
+        let (prompt, workdir, prompt_name) = {
+            let mut session = handle.lock().await;
+            let Some((prompt, workdir)) = session.last_run.clone() else {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Session has never been run"));
+            };
+            self.queue.lock().unwrap().remove(session_id);
             session.kill().await;
+            session.clear_output().await;
+            (prompt, workdir, session.prompt_name.clone())
+        };
+        self.run_as(session_id, &prompt, &workdir, prompt_name.as_deref()).await
+    }
+
+    /// Drops every queued run and kills every running session. Returns the
+    /// ids that were stopped, sorted.
+    pub async fn shutdown(&self) -> Vec<String> {
+        let mut stopped = self.queue.lock().unwrap().drain();
+        let handles: Vec<(String, Arc<TokioMutex<PiSession>>)> = self.sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, handle)| (id.clone(), handle.clone()))
+            .collect();
+        for (id, handle) in handles {
+            let mut session = handle.lock().await;
+            if session.is_running() {
+                session.kill().await;
+                stopped.push(id);
+            }
+        }
+        stopped.sort();
+        stopped.dedup();
+        stopped
+    }
+
+    /// Stops the runs of one project: every running or queued session whose
+    /// workdir is `workdir` or under it, compared after canonicalizing both.
+    /// Returns the ids that were stopped, sorted.
+    pub async fn kill_by_workdir(&self, workdir: &str) -> Vec<String> {
+        let root = paths::canonicalize(workdir);
+        let matches = |dir: &str| paths::is_within(&root, &paths::canonicalize(dir));
+        let mut stopped = self.queue.lock().unwrap().remove_where(|run| matches(&run.workdir));
+        let handles: Vec<(String, Arc<TokioMutex<PiSession>>)> = self.sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, handle)| (id.clone(), handle.clone()))
+            .collect();
+        for (id, handle) in handles {
+            let mut session = handle.lock().await;
+            if session.workdir().is_some_and(matches) && session.is_running() {
+                session.kill().await;
+                stopped.push(id);
+            }
         }
+        stopped.sort();
+        stopped.dedup();
+        stopped
+    }
+
+    /// Kills `pid` directly, for when a session's own `kill` can't reach it.
+    /// Only pids of Pi processes this manager started and hasn't yet seen
+    /// exit are accepted. Returns whether the signal was delivered.
+    pub fn kill_pid(&self, pid: u32) -> Result<bool, std::io::Error> {
+        if !self.known_pids.lock().unwrap().contains(&pid) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("Process {} is not a Pi run started by this app", pid),
+            ));
+        }
+        kill_process(pid)
     }
 
     pub async fn output(&self, session_id: &str) -> Vec<String> {
-        let sessions = self.sessions.lock().await;
-        if let Some(session) = sessions.get(session_id) {
-            session.get_output().await
-        } else {
-            Vec::new()
+        match self.session(session_id).await {
+            Some(session) => session.lock().await.get_output().await,
+            None => Vec::new(),
         }
     }
 
+    /// Stdout lines added to every session since the index in `since`
+    /// (0 for sessions not in it). Sessions with nothing new are left out.
+    /// An index past the end means the output was cleared by a restart, so
+    /// that session starts over from the first line.
+    pub async fn tail_all(&self, since: &HashMap<String, usize>) -> HashMap<String, PiTail> {
+        let handles: Vec<(String, Arc<TokioMutex<PiSession>>)> = self.sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, handle)| (id.clone(), handle.clone()))
+            .collect();
+        let mut tails = HashMap::new();
+        for (id, handle) in handles {
+            let output = handle.lock().await.output.clone();
+            let output = output.lock().await;
+            let start = since.get(&id).copied().unwrap_or(0);
+            let start = if start > output.len() { 0 } else { start };
+            if start == output.len() {
+                continue;
+            }
+            tails.insert(id, PiTail { lines: output[start..].to_vec(), next: output.len() });
+        }
+        tails
+    }
+
+    /// The session's JSON output lines parsed into events; plain text lines
+    /// are skipped.
+    pub async fn structured_events(&self, session_id: &str) -> Vec<PiEvent> {
+        parse_events(&self.output(session_id).await)
+    }
+
+    pub async fn combined_output(&self, session_id: &str) -> Vec<TaggedLine> {
+        match self.session(session_id).await {
+            Some(session) => session.lock().await.get_combined_output().await,
+            None => Vec::new(),
+        }
+    }
+
+    pub async fn last_error(&self, session_id: &str) -> Option<String> {
+        self.session(session_id).await?.lock().await.last_error().await
+    }
+
+    /// `None` for an unknown session.
+    pub async fn status(&self, session_id: &str) -> Option<PiRunStatus> {
+        Some(self.session(session_id).await?.lock().await.status())
+    }
+
+    /// The session's log file, once `log_dir` is set. `None` for an unknown
+    /// session or with logging off.
+    pub async fn log_path(&self, session_id: &str) -> Option<PathBuf> {
+        let session = self.session(session_id).await?;
+        let session = session.lock().await;
+        session.config.log_path(&session.id)
+    }
+
+    /// Writes the answer to the session's open approval prompt. `false` when
+    /// none is open, e.g. it already timed out.
+    pub async fn respond_approval(&self, session_id: &str, approve: bool) -> Result<bool, std::io::Error> {
+        let Some(session) = self.session(session_id).await else {
+            return Ok(false);
+        };
+        // Not held across the write, so a blocked pipe can't stall the session
+        let approval = session.lock().await.approval.clone();
+        approval.answer(None, approve).await
+    }
+
+    /// `None` for an unknown session.
+    pub async fn effective_env(&self, session_id: &str) -> Option<BTreeMap<String, String>> {
+        Some(self.session(session_id).await?.lock().await.effective_env())
+    }
+
+    pub async fn awaiting_input(&self, session_id: &str) -> bool {
+        match self.session(session_id).await {
+            Some(session) => session.lock().await.is_awaiting_input(),
+            None => false,
+        }
+    }
+
+    pub async fn prompt_name(&self, session_id: &str) -> Option<String> {
+        self.session(session_id).await?.lock().await.prompt_name.clone()
+    }
+
+    /// The run's final summary block, once the process has exited.
+    pub async fn summary(&self, session_id: &str) -> Option<PiRunSummary> {
+        let session = self.session(session_id).await?;
+        let mut session = session.lock().await;
+        if session.is_running() {
+            return None;
+        }
+        parse_summary(&session.get_output().await)
+    }
+
     pub async fn list_models(&self) -> Result<Vec<String>, std::io::Error> {
-        let output = Command::new("pi")
+        let output = Command::new(self.get_config().binary())
             .arg("--list-models")
             .output()
             .await?;
@@ -219,17 +1213,82 @@ impl PiManager {
         Ok(models)
     }
 
-    pub fn update_config(&self, config: PiConfig) {
+    /// Models with their context window and tool support, from
+    /// `--list-models --json`. Builds without JSON output fall back to the
+    /// plain list, with only what the id itself says.
+    pub async fn list_models_detailed(&self) -> Result<Vec<ModelInfo>, std::io::Error> {
+        let output = Command::new(self.get_config().binary())
+            .args(["--list-models", "--json"])
+            .output()
+            .await?;
+        if output.status.success() {
+            if let Some(models) = parse_models_json(&String::from_utf8_lossy(&output.stdout)) {
+                return Ok(models);
+            }
+        }
+        let models = self.list_models().await?;
+        Ok(models.iter().map(|id| ModelInfo::from_id(id)).collect())
+    }
+
+    pub fn update_config(&self, mut config: PiConfig) {
+        config.model = config.resolve_model(&config.model);
         let mut default = self.default_config.lock().unwrap();
         *default = config;
     }
 
+    /// Adds or (with `model: None`) removes an alias, returning the new map.
+    pub fn set_model_alias(&self, alias: &str, model: Option<&str>) -> HashMap<String, String> {
+        let mut default = self.default_config.lock().unwrap();
+        match model {
+            Some(model) => default.aliases.insert(alias.to_string(), model.to_string()),
+            None => default.aliases.remove(alias),
+        };
+        default.aliases.clone()
+    }
+
     pub fn get_config(&self) -> PiConfig {
         let default = self.default_config.lock().unwrap();
         default.clone()
     }
 }
 
+/// Holds a concurrency slot while a run executes, then hands the slot to the
+/// next queued run. The slot is released under the queue lock so a concurrent
+/// `run` either sees the queued item taken or finds the slot free.
+async fn supervise_slot(
+    sessions: SessionMap,
+    queue: Arc<StdMutex<RunQueue>>,
+    mut current: Arc<TokioMutex<PiSession>>,
+    permit: OwnedSemaphorePermit,
+) {
+    loop {
+        while current.lock().await.is_running() {
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+        }
+
+        loop {
+            let next = {
+                let mut queue = queue.lock().unwrap();
+                match queue.pop_front() {
+                    Some(next) => next,
+                    None => {
+                        drop(permit);
+                        return;
+                    }
+                }
+            };
+            let Some(handle) = sessions.lock().await.get(&next.session_id).cloned() else {
+                continue;
+            };
+            let spawned = handle.lock().await.spawn(&next.prompt, &next.workdir).await;
+            if spawned.is_ok() {
+                current = handle;
+                break;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,4 +1301,452 @@ mod tests {
         assert_eq!(config.model, "gpt-5.2-codex");
         assert_eq!(config.thinking, "xhigh");
     }
+
+    #[tokio::test]
+    async fn test_system_prompt_path_falls_back_to_inline() {
+        let path = std::env::temp_dir().join(format!("pi-system-prompt-{}.md", std::process::id()));
+        std::fs::write(&path, "From file").unwrap();
+
+        let mut config = PiConfig {
+            system_prompt_path: Some(path.to_string_lossy().to_string()),
+            ..PiConfig::default()
+        };
+        assert_eq!(config.resolve_system_prompt().await, "From file");
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(config.resolve_system_prompt().await, config.system_prompt);
+
+        config.system_prompt_path = None;
+        assert_eq!(config.resolve_system_prompt().await, config.system_prompt);
+    }
+
+    #[test]
+    fn test_model_aliases_expand() {
+        let mut config = PiConfig::default();
+        config.aliases.insert("codex".to_string(), "gpt-5.2-codex".to_string());
+        assert_eq!(config.resolve_model("codex"), "gpt-5.2-codex");
+        assert_eq!(config.resolve_model("claude-sonnet-4"), "claude-sonnet-4");
+
+        let manager = PiManager::new();
+        manager.set_model_alias("fast", Some("gpt-5-mini"));
+        let mut config = manager.get_config();
+        config.model = "fast".to_string();
+        manager.update_config(config);
+        assert_eq!(manager.get_config().model, "gpt-5-mini");
+    }
+
+    // A stand-in `pi` that prints "done" after a second, picked through
+    // `binary_path` since tests running in parallel read PATH
+    #[cfg(unix)]
+    fn fake_pi_config() -> PiConfig {
+        use std::os::unix::fs::PermissionsExt;
+        use std::sync::OnceLock;
+
+        static SCRIPT: OnceLock<String> = OnceLock::new();
+        let script = SCRIPT.get_or_init(|| {
+            let dir = std::env::temp_dir().join(format!("fake-pi-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let script = dir.join("pi");
+            std::fs::write(&script, "#!/bin/sh\nsleep 1\necho done\n").unwrap();
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+            script.to_str().unwrap().to_string()
+        });
+        PiConfig { binary_path: Some(script.clone()), ..PiConfig::default() }
+    }
+
+    #[cfg(unix)]
+    fn fake_pi_manager(max_concurrent: usize) -> PiManager {
+        let manager = PiManager::with_max_concurrent(max_concurrent);
+        manager.update_config(fake_pi_config());
+        manager
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_concurrent_runs_on_same_session_serialize() {
+        let manager = fake_pi_manager(DEFAULT_MAX_CONCURRENT_RUNS);
+        let (first, second) = tokio::join!(
+            manager.run("same", "hello", "."),
+            manager.run("same", "hello", ".")
+        );
+        assert!(first.is_ok() != second.is_ok());
+        let err = first.err().or(second.err()).unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        manager.kill("same").await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_runs_beyond_capacity_queue_in_order() {
+        let manager = fake_pi_manager(1);
+        for id in ["first", "second", "third"] {
+            manager.run(id, "hello", ".").await.unwrap();
+        }
+
+        let status = manager.queue_status().await;
+        assert_eq!(status.running, vec!["first"]);
+        assert_eq!(status.queued, vec!["second", "third"]);
+
+        manager.reorder_queue("third", 0).unwrap();
+        assert_eq!(manager.queue_status().await.queued, vec!["third", "second"]);
+        assert!(manager.reorder_queue("first", 0).is_err());
+
+        for id in ["first", "second", "third"] {
+            manager.kill(id).await;
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restart_reruns_with_fresh_output() {
+        let manager = PiManager::new();
+        manager.create_session("again", Some(fake_pi_config())).await;
+        assert_eq!(manager.restart("again").await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(manager.restart("missing").await.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+        manager.run("again", "hello", ".").await.unwrap();
+        manager.wait("again").await.unwrap();
+        assert_eq!(manager.output("again").await, vec!["done"]);
+
+        manager.restart("again").await.unwrap();
+        assert!(manager.output("again").await.is_empty());
+        manager.wait("again").await.unwrap();
+        assert_eq!(manager.output("again").await, vec!["done"]);
+    }
+
+    #[tokio::test]
+    async fn test_tail_all_returns_only_new_lines() {
+        let manager = PiManager::new();
+        for (id, lines) in [("a", vec!["one", "two"]), ("b", vec!["only"]), ("idle", vec![])] {
+            manager.create_session(id, None).await;
+            let session = manager.session(id).await.unwrap();
+            let output = session.lock().await.output.clone();
+            output.lock().await.extend(lines.into_iter().map(str::to_string));
+        }
+
+        let tails = manager.tail_all(&HashMap::from([("a".to_string(), 1)])).await;
+        assert_eq!(tails.len(), 2);
+        assert_eq!(tails["a"], PiTail { lines: vec!["two".to_string()], next: 2 });
+        assert_eq!(tails["b"].lines, vec!["only"]);
+
+        let since = HashMap::from([("a".to_string(), 2), ("b".to_string(), 5)]);
+        let tails = manager.tail_all(&since).await;
+        assert_eq!(tails.keys().collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(tails["b"].next, 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shutdown_stops_running_and_queued() {
+        let manager = fake_pi_manager(1);
+        manager.run("running", "hello", ".").await.unwrap();
+        manager.run("waiting", "hello", ".").await.unwrap();
+
+        assert_eq!(manager.shutdown().await, vec!["running", "waiting"]);
+        let status = manager.queue_status().await;
+        assert!(status.running.is_empty() && status.queued.is_empty());
+        assert!(manager.shutdown().await.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_invalid_utf8_output_keeps_reading() {
+        let mut child = Command::new("printf")
+            .arg("bad \\377 byte\\r\\nstill here\\n")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (tx, mut rx) = mpsc::channel(8);
+        let (_cancel, cancelled) = watch::channel(false);
+        let reader = spawn_pipe_reader(child.stdout.take().unwrap(), PiStream::Stdout, tx, cancelled);
+
+        assert_eq!(rx.recv().await.unwrap().line, "bad \u{FFFD} byte");
+        assert_eq!(rx.recv().await.unwrap().line, "still here");
+        assert!(rx.recv().await.is_none());
+        reader.await.unwrap();
+        child.wait().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_last_error_tracks_latest_run() {
+        let mut session = PiSession::new("errors", Some(fake_pi_config()));
+        assert!(session.spawn("hello", "/nonexistent-workdir").await.is_err());
+        assert!(session.last_error().await.unwrap().starts_with("Failed to start pi"));
+
+        session.spawn("hello", ".").await.unwrap();
+        assert_eq!(session.last_error().await, None);
+        session.kill().await;
+        assert_eq!(session.last_error().await.as_deref(), Some("Run was killed"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_no_output_timeout_kills_silent_runs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join(format!("silent-pi-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&script, "#!/bin/sh\necho start\nexec sleep 30\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let config = PiConfig {
+            binary_path: Some(script.to_str().unwrap().to_string()),
+            no_output_timeout: Some(Duration::from_millis(300)),
+            ..PiConfig::default()
+        };
+
+        let mut session = PiSession::new("silent", Some(config));
+        session.spawn("hello", ".").await.unwrap();
+        assert_eq!(session.status(), PiRunStatus::Running);
+        tokio::time::timeout(Duration::from_secs(5), session.wait()).await.unwrap().unwrap();
+        assert_eq!(session.status(), PiRunStatus::NoOutputTimeout);
+        assert_eq!(session.get_output().await, vec!["start", NO_OUTPUT_TIMEOUT_MARKER]);
+        assert!(session.last_error().await.unwrap().contains("without output"));
+
+        // A managed session gets the watchdog turned on after it was created
+        let manager = PiManager::new();
+        let config = PiConfig { no_output_timeout: None, ..session.config.clone() };
+        manager.create_session("silent", Some(config)).await;
+        manager.update_config(PiConfig { no_output_timeout: Some(Duration::from_millis(300)), ..manager.get_config() });
+        manager.run("silent", "hello", ".").await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), manager.wait("silent")).await.unwrap().unwrap();
+        assert_eq!(manager.status("silent").await, Some(PiRunStatus::NoOutputTimeout));
+        std::fs::remove_file(script).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_approval_prompts_are_answered_on_stdin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join(format!("asking-pi-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&script, "#!/bin/sh\necho 'Run rm -rf build? [y/n]'\nread answer\necho \"got $answer\"\nsleep 0.3\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let manager = PiManager::new();
+        let binary_path = Some(script.to_str().unwrap().to_string());
+        // Created before approvals are turned on, which must still reach it
+        manager.create_session("asking", Some(PiConfig { binary_path: binary_path.clone(), ..PiConfig::default() })).await;
+        manager.update_config(PiConfig {
+            binary_path,
+            approval_pattern: Some("[y/n]".to_string()),
+            ..manager.get_config()
+        });
+        let mut approvals = manager.subscribe_approvals();
+
+        manager.run("asking", "hello", ".").await.unwrap();
+        let request = tokio::time::timeout(Duration::from_secs(5), approvals.recv()).await.unwrap().unwrap();
+        assert_eq!((request.session_id.as_str(), request.prompt.as_str()), ("asking", "Run rm -rf build? [y/n]"));
+        assert!(manager.respond_approval("asking", true).await.unwrap());
+        assert!(!manager.respond_approval("asking", false).await.unwrap());
+        manager.wait("asking").await.unwrap();
+        assert_eq!(manager.output("asking").await, vec!["Run rm -rf build? [y/n]", "got y"]);
+
+        // The auto-deny shows up everywhere a printed line would
+        let log_dir = std::env::temp_dir().join(format!("pi-approval-logs-{}", uuid::Uuid::new_v4()));
+        manager.update_config(PiConfig {
+            approval_timeout: Duration::from_millis(200),
+            log_dir: Some(log_dir.display().to_string()),
+            ..manager.get_config()
+        });
+        let mut lines = manager.subscribe_lines();
+        manager.run("ignored", "hello", ".").await.unwrap();
+        manager.wait("ignored").await.unwrap();
+        let expected = vec!["Run rm -rf build? [y/n]", APPROVAL_TIMEOUT_MARKER, "got n"];
+        assert_eq!(manager.output("ignored").await, expected);
+        let log = std::fs::read_to_string(log_dir.join("ignored.log")).unwrap();
+        assert_eq!(log.lines().collect::<Vec<_>>(), expected);
+        let mut sent = Vec::new();
+        while let Ok(line) = lines.try_recv() {
+            sent.push(line.line);
+        }
+        assert_eq!(sent, expected);
+        std::fs::remove_dir_all(log_dir).ok();
+        std::fs::remove_file(script).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_detailed_models_fall_back_to_ids() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join(format!("models-pi-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&script, "#!/bin/sh\n[ \"$2\" = --json ] && exit 1\necho openai/gpt-5\necho o3\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let manager = PiManager::new();
+        manager.update_config(PiConfig {
+            binary_path: Some(script.to_str().unwrap().to_string()),
+            ..manager.get_config()
+        });
+
+        let models = manager.list_models_detailed().await.unwrap();
+        assert_eq!(models, vec![ModelInfo::from_id("openai/gpt-5"), ModelInfo::from_id("o3")]);
+        assert_eq!(models[0].provider.as_deref(), Some("openai"));
+        std::fs::remove_file(script).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kill_by_workdir_stops_only_that_project() {
+        let root = std::env::temp_dir().join(format!("pi-projects-{}", uuid::Uuid::new_v4()));
+        let (app, app_src, other) = (root.join("app"), root.join("app/src"), root.join("app-old"));
+        for dir in [&app_src, &other] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let manager = fake_pi_manager(DEFAULT_MAX_CONCURRENT_RUNS);
+        manager.run("in-app", "hello", app_src.to_str().unwrap()).await.unwrap();
+        manager.run("in-other", "hello", other.to_str().unwrap()).await.unwrap();
+
+        let project = format!("{}/..", app_src.display());
+        assert_eq!(manager.kill_by_workdir(&project).await, vec!["in-app"]);
+        assert_eq!(manager.status("in-app").await, Some(PiRunStatus::Killed));
+        assert_eq!(manager.status("in-other").await, Some(PiRunStatus::Running));
+        assert!(manager.kill_by_workdir(app.to_str().unwrap()).await.is_empty());
+
+        manager.kill("in-other").await;
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_combined_output_tags_streams() {
+        let manager = fake_pi_manager(DEFAULT_MAX_CONCURRENT_RUNS);
+        let mut lines = manager.subscribe_lines();
+        manager.run("tagged", "hello", ".").await.unwrap();
+        manager.wait("tagged").await.unwrap();
+
+        let line = lines.recv().await.unwrap();
+        assert_eq!((line.session_id.as_str(), line.stream, line.line.as_str()), ("tagged", PiStream::Stdout, "done"));
+        let combined = manager.combined_output("tagged").await;
+        assert_eq!(combined.len(), 1);
+        assert_eq!(manager.output("tagged").await, vec!["done"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_is_appended_to_log_file() {
+        let dir = std::env::temp_dir().join(format!("pi-logs-{}", uuid::Uuid::new_v4()));
+        let manager = fake_pi_manager(DEFAULT_MAX_CONCURRENT_RUNS);
+        // Logging is turned on after the session exists
+        manager.create_session("logged", Some(fake_pi_config())).await;
+        let config = PiConfig { log_dir: Some(dir.display().to_string()), ..manager.get_config() };
+        manager.update_config(config);
+        manager.run("logged", "hello", ".").await.unwrap();
+        manager.wait("logged").await.unwrap();
+
+        let path = manager.log_path("logged").await.unwrap();
+        assert_eq!(path, dir.join("logged.log"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "done\n");
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_oversized_prompt_goes_through_temp_file() {
+        let config = PiConfig { prompt_file_threshold: 16, ..fake_pi_config() };
+        let mut session = PiSession::new("big-prompt", Some(config));
+        let prompt = "x".repeat(64);
+
+        session.spawn(&prompt, ".").await.unwrap();
+        let path = session.prompt_file.clone().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), prompt);
+
+        session.wait().await.unwrap();
+        assert!(!path.exists());
+        assert_eq!(session.prompt_arg("short").unwrap(), "short");
+        assert!(session.prompt_file.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_killed_sessions_leave_no_reader_tasks() {
+        let metrics = tokio::runtime::Handle::current().metrics();
+        let baseline = metrics.num_alive_tasks();
+
+        for i in 0..20 {
+            let mut session = PiSession::new(&format!("short-{i}"), Some(fake_pi_config()));
+            session.spawn("hello", ".").await.unwrap();
+            if i % 2 == 0 {
+                session.kill().await;
+            }
+            // Odd sessions are dropped with the child still running
+        }
+
+        tokio::task::yield_now().await;
+        assert_eq!(metrics.num_alive_tasks(), baseline);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_completion_marker_emits_state() {
+        let manager = PiManager::new();
+        let config = PiConfig { completion_marker: Some("done".to_string()), ..fake_pi_config() };
+        manager.create_session("marked", Some(config)).await;
+        let mut states = manager.subscribe_states();
+        manager.run("marked", "hello", ".").await.unwrap();
+
+        let state = states.recv().await.unwrap();
+        assert_eq!(state.session_id, "marked");
+        assert!(state.awaiting_input);
+        assert!(manager.awaiting_input("marked").await);
+        manager.kill("marked").await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kill_pid_only_accepts_live_pi_runs() {
+        let manager = fake_pi_manager(DEFAULT_MAX_CONCURRENT_RUNS);
+        assert_eq!(manager.kill_pid(1).unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+
+        manager.run("by-pid", "hello", ".").await.unwrap();
+        let pid = *manager.known_pids.lock().unwrap().iter().next().unwrap();
+        assert!(manager.kill_pid(pid).unwrap());
+
+        manager.wait("by-pid").await.unwrap();
+        assert!(manager.known_pids.lock().unwrap().is_empty());
+        assert!(manager.kill_pid(pid).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_binary_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("pi-binary-{}", uuid::Uuid::new_v4()));
+        let path_str = path.to_str().unwrap();
+        assert_eq!(validate_binary_path(path_str).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        assert_eq!(validate_binary_path(path_str).unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(validate_binary_path(path_str).is_ok());
+        let dir = std::env::temp_dir();
+        assert_eq!(validate_binary_path(dir.to_str().unwrap()).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_shell_launcher_quotes_arguments() {
+        let args: Vec<String> = ["--model", "gpt-5.2-codex", "-p", "it's \"quoted\" $HOME; rm -rf /", ""]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let line = shell_command_line("pi", &args);
+        assert!(line.starts_with("pi --model gpt-5.2-codex -p '"));
+        let parsed = shell_words::split(&line).unwrap();
+        assert_eq!(parsed[0], "pi");
+        assert_eq!(parsed[1..], args[..]);
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_cmd_launcher_escapes_arguments() {
+        let args: Vec<String> = ["-p", "a&b|c", "say \"hi\" ^ <in >out", "C:\\dir\\", "100%"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let line = cmd_command_line("pi", &args).unwrap();
+        assert_eq!(
+            line,
+            r#"^"pi^" ^"-p^" ^"a^&b^|c^" ^"say \^"hi\^" ^^ ^<in ^>out^" ^"C:\dir\\^" ^"100^%^""#
+        );
+        assert!(cmd_command_line("pi", &["line\nbreak".to_string()]).is_err());
+    }
 }
@@ -1,15 +1,201 @@
 // Pi Tauri Commands
 
 use crate::pi::PiManager;
-use tauri::State;
-use serde::Serialize;
+use crate::pi::client::{validate_binary_path, PiConfig, PiLauncher};
+use crate::pi::events::PiEvent;
+use crate::pi::models::ModelInfo;
+use crate::pi::output::{PiApprovalRequest, PiLine, PiRunStatus, PiState, PiTail, TaggedLine};
+use crate::pi::queue::PiQueueStatus;
+use crate::pi::summary::PiRunSummary;
+use crate::prompts::{find_prompt_for_workdir, render_prompt};
+use crate::state::AppState;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
-#[derive(Serialize, Clone)]
+/// Re-emits Pi output lines to the frontend as `pi://line` events.
+pub fn forward_lines(app: AppHandle, mut lines: broadcast::Receiver<PiLine>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match lines.recv().await {
+                Ok(line) => {
+                    let _ = app.emit("pi://line", line);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Re-emits completion-marker state changes as `pi://state` events.
+pub fn forward_states(app: AppHandle, mut states: broadcast::Receiver<PiState>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match states.recv().await {
+                Ok(state) => {
+                    let _ = app.emit("pi://state", state);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Re-emits approval prompts as `pi://approval-request` events.
+pub fn forward_approvals(app: AppHandle, mut approvals: broadcast::Receiver<PiApprovalRequest>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match approvals.recv().await {
+                Ok(request) => {
+                    let _ = app.emit("pi://approval-request", request);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// `PiConfig` as the frontend sees it. Both conversions destructure every
+/// field, so a field added to one side won't compile until the other has it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PiConfigDto {
     pub model: String,
     pub thinking: String,
     pub system_prompt: String,
     pub provider: String,
+    pub system_prompt_path: Option<String>,
+    pub aliases: HashMap<String, String>,
+    pub prompt_file_threshold: usize,
+    pub completion_marker: Option<String>,
+    pub launcher: PiLauncher,
+    pub binary_path: Option<String>,
+    pub no_output_timeout_secs: Option<u64>,
+    pub log_dir: Option<String>,
+    pub log_max_bytes: u64,
+    pub approval_pattern: Option<String>,
+    pub approval_timeout_secs: u64,
+}
+
+impl From<PiConfig> for PiConfigDto {
+    fn from(config: PiConfig) -> Self {
+        let PiConfig {
+            model,
+            thinking,
+            system_prompt,
+            provider,
+            system_prompt_path,
+            aliases,
+            prompt_file_threshold,
+            completion_marker,
+            launcher,
+            binary_path,
+            no_output_timeout,
+            log_dir,
+            log_max_bytes,
+            approval_pattern,
+            approval_timeout,
+        } = config;
+        Self {
+            model,
+            thinking,
+            system_prompt,
+            provider,
+            system_prompt_path,
+            aliases,
+            prompt_file_threshold,
+            completion_marker,
+            launcher,
+            binary_path,
+            no_output_timeout_secs: no_output_timeout.map(|limit| limit.as_secs()),
+            log_dir,
+            log_max_bytes,
+            approval_pattern,
+            approval_timeout_secs: approval_timeout.as_secs(),
+        }
+    }
+}
+
+impl From<PiConfigDto> for PiConfig {
+    fn from(dto: PiConfigDto) -> Self {
+        let PiConfigDto {
+            model,
+            thinking,
+            system_prompt,
+            provider,
+            system_prompt_path,
+            aliases,
+            prompt_file_threshold,
+            completion_marker,
+            launcher,
+            binary_path,
+            no_output_timeout_secs,
+            log_dir,
+            log_max_bytes,
+            approval_pattern,
+            approval_timeout_secs,
+        } = dto;
+        Self {
+            model,
+            thinking,
+            system_prompt,
+            provider,
+            system_prompt_path,
+            aliases,
+            prompt_file_threshold,
+            completion_marker,
+            launcher,
+            binary_path,
+            no_output_timeout: no_output_timeout_secs.map(Duration::from_secs),
+            log_dir,
+            log_max_bytes,
+            approval_pattern,
+            approval_timeout: Duration::from_secs(approval_timeout_secs),
+        }
+    }
+}
+
+/// One field of `pi_config_diff`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PiConfigField {
+    pub current: Value,
+    pub default: Value,
+    pub overridden: bool,
+}
+
+fn config_fields(config: &PiConfigDto) -> serde_json::Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// Every field of `current` next to its default.
+pub fn config_diff(current: &PiConfigDto, default: &PiConfigDto) -> BTreeMap<String, PiConfigField> {
+    let defaults = config_fields(default);
+    config_fields(current)
+        .into_iter()
+        .map(|(name, current)| {
+            let default = defaults.get(&name).cloned().unwrap_or(Value::Null);
+            let overridden = current != default;
+            (name, PiConfigField { current, default, overridden })
+        })
+        .collect()
+}
+
+/// `current` with `field` put back to its value in `default`.
+pub fn reset_config_field(current: PiConfigDto, default: &PiConfigDto, field: &str) -> Result<PiConfigDto, String> {
+    let mut fields = config_fields(&current);
+    let value = config_fields(default)
+        .remove(field)
+        .ok_or_else(|| format!("Unknown Pi config field: {}", field))?;
+    fields.insert(field.to_string(), value);
+    serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -18,35 +204,153 @@ pub async fn pi_list_models(manager: State<'_, PiManager>) -> Result<Vec<String>
         .map_err(|e| e.to_string())
 }
 
+/// `pi_list_models` with context windows and tool support, where this Pi
+/// build reports them.
+#[tauri::command]
+pub async fn pi_list_models_detailed(manager: State<'_, PiManager>) -> Result<Vec<ModelInfo>, String> {
+    manager.list_models_detailed().await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn pi_get_config(manager: State<'_, PiManager>) -> Result<PiConfigDto, String> {
-    let config = manager.get_config();
-    Ok(PiConfigDto {
-        model: config.model,
-        thinking: config.thinking,
-        system_prompt: config.system_prompt,
-        provider: config.provider,
-    })
+    Ok(manager.get_config().into())
 }
 
+/// An empty `system_prompt_path` or `binary_path` clears it. A `binary_path`
+/// that isn't an executable file is rejected and nothing is changed.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pi_update_config(
     manager: State<'_, PiManager>,
     model: Option<&str>,
     thinking: Option<&str>,
     system_prompt: Option<&str>,
-    provider: Option<&str>
+    provider: Option<&str>,
+    system_prompt_path: Option<&str>,
+    prompt_file_threshold: Option<usize>,
+    binary_path: Option<&str>
 ) -> Result<bool, String> {
-    let mut config = manager.get_config();
+    let mut config = PiConfigDto::from(manager.get_config());
     if let Some(m) = model { config.model = m.to_string(); }
     if let Some(t) = thinking { config.thinking = t.to_string(); }
     if let Some(sp) = system_prompt { config.system_prompt = sp.to_string(); }
     if let Some(p) = provider { config.provider = p.to_string(); }
+    // An empty path clears it and goes back to the inline prompt
+    if let Some(path) = system_prompt_path {
+        config.system_prompt_path = if path.is_empty() { None } else { Some(path.to_string()) };
+    }
+    if let Some(threshold) = prompt_file_threshold { config.prompt_file_threshold = threshold; }
+    if let Some(path) = binary_path {
+        config.binary_path = if path.is_empty() {
+            None
+        } else {
+            validate_binary_path(path).map_err(|e| e.to_string())?;
+            Some(path.to_string())
+        };
+    }
     
+    manager.update_config(config.into());
+    Ok(true)
+}
+
+/// Which config fields differ from `PiConfig::default()`, keyed by field
+/// name.
+#[tauri::command]
+pub async fn pi_config_diff(manager: State<'_, PiManager>) -> Result<BTreeMap<String, PiConfigField>, String> {
+    let default = PiConfigDto::from(PiConfig::default());
+    Ok(config_diff(&manager.get_config().into(), &default))
+}
+
+/// Puts one field back to its default and returns the updated config.
+#[tauri::command]
+pub async fn pi_reset_config_field(manager: State<'_, PiManager>, field: &str) -> Result<PiConfigDto, String> {
+    let default = PiConfigDto::from(PiConfig::default());
+    let config = reset_config_field(manager.get_config().into(), &default, field)?;
+    manager.update_config(config.into());
+    Ok(manager.get_config().into())
+}
+
+/// Maps `alias` to `model`, or removes the alias when `model` is omitted.
+#[tauri::command]
+pub async fn pi_set_model_alias(
+    manager: State<'_, PiManager>,
+    alias: &str,
+    model: Option<&str>
+) -> Result<HashMap<String, String>, String> {
+    Ok(manager.set_model_alias(alias, model))
+}
+
+/// Sets the line that marks a run as waiting for input; omitting `marker`
+/// (or passing an empty one) turns detection off. Applies to new sessions.
+#[tauri::command]
+pub async fn pi_set_completion_marker(manager: State<'_, PiManager>, marker: Option<&str>) -> Result<bool, String> {
+    let mut config = manager.get_config();
+    config.completion_marker = marker.filter(|marker| !marker.is_empty()).map(str::to_string);
+    manager.update_config(config);
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn pi_set_launcher(manager: State<'_, PiManager>, launcher: PiLauncher) -> Result<bool, String> {
+    let mut config = manager.get_config();
+    config.launcher = launcher;
+    manager.update_config(config);
+    Ok(true)
+}
+
+/// Kills runs that go `secs` seconds without printing anything; omitting
+/// `secs` (or passing 0) turns the watchdog off. Runs started from now on
+/// use it, in existing sessions too.
+#[tauri::command]
+pub async fn pi_set_no_output_timeout(manager: State<'_, PiManager>, secs: Option<u64>) -> Result<bool, String> {
+    let mut config = manager.get_config();
+    config.no_output_timeout = secs.filter(|secs| *secs > 0).map(Duration::from_secs);
+    manager.update_config(config);
+    Ok(true)
+}
+
+/// Logs each session's output under `log_dir`; an empty `log_dir` turns
+/// logging off. `max_bytes` sets the rotation size. Takes effect at each
+/// session's next run.
+#[tauri::command]
+pub async fn pi_set_log_dir(manager: State<'_, PiManager>, log_dir: Option<&str>, max_bytes: Option<u64>) -> Result<bool, String> {
+    let mut config = manager.get_config();
+    if let Some(dir) = log_dir {
+        config.log_dir = if dir.is_empty() { None } else { Some(dir.to_string()) };
+    }
+    if let Some(max_bytes) = max_bytes { config.log_max_bytes = max_bytes; }
     manager.update_config(config);
     Ok(true)
 }
 
+/// Sets the line that marks an approval prompt (empty turns the gate off)
+/// and how many seconds a prompt waits before it's denied. Every session
+/// picks it up on its next run.
+#[tauri::command]
+pub async fn pi_set_approval(manager: State<'_, PiManager>, pattern: Option<&str>, timeout_secs: Option<u64>) -> Result<bool, String> {
+    let mut config = manager.get_config();
+    if let Some(pattern) = pattern {
+        config.approval_pattern = if pattern.is_empty() { None } else { Some(pattern.to_string()) };
+    }
+    if let Some(secs) = timeout_secs { config.approval_timeout = Duration::from_secs(secs); }
+    manager.update_config(config);
+    Ok(true)
+}
+
+/// Approves or denies the session's open approval prompt. `false` when no
+/// prompt is open, e.g. it already timed out.
+#[tauri::command]
+pub async fn pi_respond_approval(manager: State<'_, PiManager>, session_id: &str, approve: bool) -> Result<bool, String> {
+    manager.respond_approval(session_id, approve).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn pi_log_path(manager: State<'_, PiManager>, session_id: &str) -> Result<Option<String>, String> {
+    Ok(manager.log_path(session_id).await.map(|path| path.display().to_string()))
+}
+
 #[tauri::command]
 pub async fn pi_run_session(
     manager: State<'_, PiManager>,
@@ -59,6 +363,35 @@ pub async fn pi_run_session(
     Ok(true)
 }
 
+/// Runs the saved prompt `prompt_name`, rendered with `args` as the composer
+/// would expand `/prompts:<name> <args>`.
+#[tauri::command]
+pub(crate) async fn pi_run_prompt(
+    state: State<'_, AppState>,
+    manager: State<'_, PiManager>,
+    session_id: &str,
+    prompt_name: &str,
+    args: Option<&str>,
+    workdir: &str
+) -> Result<bool, String> {
+    let prompt = find_prompt_for_workdir(&state, workdir, prompt_name).await?;
+    let rendered = render_prompt(&prompt.content, args.unwrap_or_default())?;
+    manager.run_prompt(session_id, &prompt.name, &rendered, workdir).await
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn pi_awaiting_input(manager: State<'_, PiManager>, session_id: &str) -> Result<bool, String> {
+    Ok(manager.awaiting_input(session_id).await)
+}
+
+/// Name of the saved prompt the session's run was started from.
+#[tauri::command]
+pub async fn pi_get_prompt_name(manager: State<'_, PiManager>, session_id: &str) -> Result<Option<String>, String> {
+    Ok(manager.prompt_name(session_id).await)
+}
+
 #[tauri::command]
 pub async fn pi_wait_session(manager: State<'_, PiManager>, session_id: &str) -> Result<bool, String> {
     manager.wait(session_id).await
@@ -72,7 +405,139 @@ pub async fn pi_kill_session(manager: State<'_, PiManager>, session_id: &str) ->
     Ok(true)
 }
 
+/// Stops every running or queued session working in `workdir` or below it,
+/// e.g. when its project is closed. Returns the ids stopped.
+#[tauri::command]
+pub async fn pi_kill_by_workdir(manager: State<'_, PiManager>, workdir: &str) -> Result<Vec<String>, String> {
+    Ok(manager.kill_by_workdir(workdir).await)
+}
+
+/// Re-runs the session's last prompt from a clean slate and tells the UI to
+/// clear its view with a `pi://restarted` event.
+#[tauri::command]
+pub async fn pi_restart_session(app: AppHandle, manager: State<'_, PiManager>, session_id: &str) -> Result<bool, String> {
+    manager.restart(session_id).await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("pi://restarted", session_id);
+    Ok(true)
+}
+
+/// Recovery path for a run the session no longer tracks. Refuses any pid
+/// that isn't a live Pi process started by this app.
+#[tauri::command]
+pub async fn pi_kill_pid(manager: State<'_, PiManager>, pid: u32) -> Result<bool, String> {
+    manager.kill_pid(pid)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn pi_get_output(manager: State<'_, PiManager>, session_id: &str) -> Result<Vec<String>, String> {
     Ok(manager.output(session_id).await)
 }
+
+/// New output of every session since the per-session indices in `since`,
+/// for polling a multi-session live view. Only sessions with new lines are
+/// returned; pass each `next` back as that session's index.
+#[tauri::command]
+pub async fn pi_tail_all(
+    manager: State<'_, PiManager>,
+    since: HashMap<String, usize>
+) -> Result<HashMap<String, PiTail>, String> {
+    Ok(manager.tail_all(&since).await)
+}
+
+#[tauri::command]
+pub async fn pi_get_structured_events(manager: State<'_, PiManager>, session_id: &str) -> Result<Vec<PiEvent>, String> {
+    Ok(manager.structured_events(session_id).await)
+}
+
+#[tauri::command]
+pub async fn pi_get_combined_output(manager: State<'_, PiManager>, session_id: &str) -> Result<Vec<TaggedLine>, String> {
+    Ok(manager.combined_output(session_id).await)
+}
+
+#[tauri::command]
+pub async fn pi_load_system_prompt(path: &str) -> Result<String, String> {
+    tokio::fs::read_to_string(path).await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn pi_last_error(manager: State<'_, PiManager>, session_id: &str) -> Result<Option<String>, String> {
+    Ok(manager.last_error(session_id).await)
+}
+
+/// `None` for a session that doesn't exist.
+#[tauri::command]
+pub async fn pi_session_status(manager: State<'_, PiManager>, session_id: &str) -> Result<Option<PiRunStatus>, String> {
+    Ok(manager.status(session_id).await)
+}
+
+#[tauri::command]
+pub async fn pi_effective_env(manager: State<'_, PiManager>, session_id: &str) -> Result<BTreeMap<String, String>, String> {
+    manager
+        .effective_env(session_id)
+        .await
+        .ok_or_else(|| format!("Unknown Pi session: {}", session_id))
+}
+
+#[tauri::command]
+pub async fn pi_get_summary(manager: State<'_, PiManager>, session_id: &str) -> Result<Option<PiRunSummary>, String> {
+    Ok(manager.summary(session_id).await)
+}
+
+#[tauri::command]
+pub async fn pi_queue_status(manager: State<'_, PiManager>) -> Result<PiQueueStatus, String> {
+    Ok(manager.queue_status().await)
+}
+
+#[tauri::command]
+pub async fn pi_queue_reorder(manager: State<'_, PiManager>, session_id: &str, position: usize) -> Result<bool, String> {
+    manager.reorder_queue(session_id, position)
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_dto_round_trips() {
+        let mut config = PiConfig {
+            system_prompt_path: Some("/tmp/prompt.md".to_string()),
+            completion_marker: Some("READY".to_string()),
+            launcher: PiLauncher::Shell,
+            binary_path: Some("/opt/pi/bin/pi".to_string()),
+            no_output_timeout: Some(Duration::from_secs(90)),
+            ..PiConfig::default()
+        };
+        config.aliases.insert("fast".to_string(), "gpt-5-mini".to_string());
+
+        let dto = PiConfigDto::from(config.clone());
+        assert_eq!(PiConfig::from(dto.clone()), config);
+        assert_eq!(PiConfigDto::from(PiConfig::from(dto.clone())), dto);
+    }
+
+    #[test]
+    fn config_diff_marks_overrides_and_resets_them() {
+        let default = PiConfigDto::from(PiConfig::default());
+        let current = PiConfigDto::from(PiConfig {
+            model: "claude-sonnet".to_string(),
+            launcher: PiLauncher::Shell,
+            ..PiConfig::default()
+        });
+
+        let diff = config_diff(&current, &default);
+        assert!(!diff["binary_path"].overridden);
+        let overridden: Vec<&str> = diff.iter().filter(|(_, f)| f.overridden).map(|(name, _)| name.as_str()).collect();
+        assert_eq!(overridden, vec!["launcher", "model"]);
+        assert_eq!(diff["launcher"].current, "shell");
+        assert_eq!(diff["launcher"].default, "direct");
+
+        let reset = reset_config_field(current, &default, "model").unwrap();
+        assert_eq!(reset.model, default.model);
+        assert_eq!(reset.launcher, PiLauncher::Shell);
+        assert!(reset_config_field(reset, &default, "nope").is_err());
+    }
+}
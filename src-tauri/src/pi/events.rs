@@ -0,0 +1,128 @@
+// Structured events from Pi's JSON output
+// Each stdout line that is a JSON object is one event, dispatched on its
+// `type`; Pi's own event names are accepted next to the generic ones
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PiEvent {
+    Thinking { text: Option<String> },
+    ToolCall { name: String, args: Value },
+    ToolResult { name: String, output: Value },
+    Message { text: String },
+    Done,
+    /// Any other object, passed through untouched.
+    #[serde(untagged)]
+    Raw(Value),
+}
+
+impl PiEvent {
+    /// `None` for lines that aren't a JSON object, like plain text output.
+    pub fn parse(line: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(line.trim()).ok()?;
+        value.is_object().then(|| Self::from_value(value))
+    }
+
+    pub fn from_value(value: Value) -> Self {
+        let kind = value.get("type").and_then(Value::as_str).unwrap_or_default();
+        match kind {
+            "thinking" | "thinking_delta" => PiEvent::Thinking {
+                text: field(&value, &["text", "thinking", "delta"]).and_then(text_of),
+            },
+            "tool_call" | "tool_execution_start" => PiEvent::ToolCall {
+                name: tool_name(&value),
+                args: field(&value, &["args", "arguments", "input"]).cloned().unwrap_or(Value::Null),
+            },
+            "tool_result" | "tool_execution_end" => PiEvent::ToolResult {
+                name: tool_name(&value),
+                output: field(&value, &["output", "result"]).cloned().unwrap_or(Value::Null),
+            },
+            "message" | "text" | "message_end" => {
+                let text = field(&value, &["text", "content"])
+                    .or_else(|| value.pointer("/message/content"))
+                    .and_then(text_of);
+                match text {
+                    Some(text) => PiEvent::Message { text },
+                    None => PiEvent::Raw(value),
+                }
+            }
+            "done" | "agent_end" => PiEvent::Done,
+            _ => PiEvent::Raw(value),
+        }
+    }
+}
+
+pub fn parse_events(lines: &[String]) -> Vec<PiEvent> {
+    lines.iter().filter_map(|line| PiEvent::parse(line)).collect()
+}
+
+fn field<'a>(value: &'a Value, names: &[&str]) -> Option<&'a Value> {
+    names.iter().find_map(|name| value.get(*name))
+}
+
+fn tool_name(value: &Value) -> String {
+    field(value, &["name", "toolName", "tool"])
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+// A string, or the `text` of each part in a content array
+fn text_of(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(parts) => {
+            let texts: Vec<&str> = parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(Value::as_str))
+                .collect();
+            (!texts.is_empty()).then(|| texts.join(""))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn dispatches_on_type() {
+        let lines: Vec<String> = [
+            "plain text",
+            r#"{"type":"thinking","text":"hmm"}"#,
+            r#"{"type":"tool_execution_start","toolName":"bash","args":{"command":"ls"}}"#,
+            r#"{"type":"tool_result","name":"bash","output":"a.txt"}"#,
+            r#"{"type":"message_end","message":{"content":[{"type":"text","text":"Hi "},{"type":"text","text":"there"}]}}"#,
+            r#"{"type":"agent_end"}"#,
+            r#"{"type":"turn_start","turn":2}"#,
+            "[1, 2]",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        assert_eq!(
+            parse_events(&lines),
+            vec![
+                PiEvent::Thinking { text: Some("hmm".into()) },
+                PiEvent::ToolCall { name: "bash".into(), args: json!({ "command": "ls" }) },
+                PiEvent::ToolResult { name: "bash".into(), output: json!("a.txt") },
+                PiEvent::Message { text: "Hi there".into() },
+                PiEvent::Done,
+                PiEvent::Raw(json!({ "type": "turn_start", "turn": 2 })),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_events_serialize_as_is() {
+        let raw = PiEvent::Raw(json!({ "type": "turn_start" }));
+        assert_eq!(serde_json::to_value(&raw).unwrap(), json!({ "type": "turn_start" }));
+        let call = PiEvent::ToolCall { name: "bash".into(), args: Value::Null };
+        assert_eq!(serde_json::to_value(&call).unwrap(), json!({ "type": "tool_call", "name": "bash", "args": null }));
+    }
+}
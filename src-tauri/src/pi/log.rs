@@ -0,0 +1,114 @@
+// Per-session log files of Pi output, kept alongside the in-memory buffer
+// A file over the size cap is moved to `<session>.log.1` (replacing the
+// previous one) and a fresh file started, so at most two are kept
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::pi::output::PiStream;
+
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// `<log_dir>/<session_id>.log`, with anything but letters, digits, `-` and
+/// `_` in the id replaced so it can't point outside `log_dir`.
+pub fn log_path(log_dir: &str, session_id: &str) -> PathBuf {
+    let name: String = session_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    Path::new(log_dir).join(format!("{}.log", name))
+}
+
+pub struct PiLog {
+    path: PathBuf,
+    max_bytes: u64,
+    file: Option<File>,
+    written: u64,
+    failed: bool,
+}
+
+impl PiLog {
+    pub fn new(path: PathBuf, max_bytes: u64) -> Self {
+        Self { path, max_bytes, file: None, written: 0, failed: false }
+    }
+
+    /// Appends one line. Errors are logged once and turn the log off for the
+    /// rest of the run, leaving the output readers unaffected.
+    pub fn append(&mut self, stream: PiStream, line: &str) {
+        if self.failed {
+            return;
+        }
+        if let Err(e) = self.write_line(stream, line) {
+            tracing::warn!(path = %self.path.display(), error = %e, "pi log disabled after write error");
+            self.failed = true;
+            self.file = None;
+        }
+    }
+
+    fn write_line(&mut self, stream: PiStream, line: &str) -> std::io::Result<()> {
+        let entry = match stream {
+            PiStream::Stdout => format!("{}\n", line),
+            PiStream::Stderr => format!("[stderr] {}\n", line),
+        };
+        if self.file.is_none() {
+            self.open()?;
+        }
+        // Also catches a file already past the cap from an earlier run
+        if self.max_bytes > 0 && self.written > 0 && self.written + entry.len() as u64 > self.max_bytes {
+            self.file = None;
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            fs::rename(&self.path, rotated)?;
+            self.open()?;
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(entry.as_bytes())?;
+            self.written += entry.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_past_the_size_cap() {
+        let dir = std::env::temp_dir().join(format!("pi-log-{}", uuid::Uuid::new_v4()));
+        let path = log_path(dir.to_str().unwrap(), "../run 1");
+        assert_eq!(path, dir.join("___run_1.log"));
+
+        let mut log = PiLog::new(path.clone(), 16);
+        log.append(PiStream::Stdout, "first line");
+        log.append(PiStream::Stderr, "oops");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[stderr] oops\n");
+        assert_eq!(fs::read_to_string(dir.join("___run_1.log.1")).unwrap(), "first line\n");
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn rotates_an_oversized_file_left_from_before() {
+        let dir = std::env::temp_dir().join(format!("pi-log-{}", uuid::Uuid::new_v4()));
+        let path = log_path(dir.to_str().unwrap(), "run");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "x".repeat(64)).unwrap();
+
+        let mut log = PiLog::new(path.clone(), 16);
+        log.append(PiStream::Stdout, "fresh");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fresh\n");
+        assert_eq!(fs::read_to_string(dir.join("run.log.1")).unwrap().len(), 64);
+        fs::remove_dir_all(dir).ok();
+    }
+}
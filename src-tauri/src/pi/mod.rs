@@ -1,4 +1,12 @@
+pub mod approval;
 pub mod commands;
 pub mod client;
+pub mod env;
+pub mod events;
+pub mod log;
+pub mod models;
+pub mod output;
+pub mod queue;
+pub mod summary;
 
 pub use client::{PiManager, PiSession, PiConfig};
@@ -0,0 +1,77 @@
+// Model metadata from `pi --list-models`
+// Builds that support `--json` describe each model; older ones print bare
+// ids, which still make a `ModelInfo` with the details left unknown
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub provider: Option<String>,
+    pub context_window: Option<u32>,
+    pub supports_tools: Option<bool>,
+}
+
+impl ModelInfo {
+    /// From a bare id, taking the provider from a `provider/model` prefix.
+    pub fn from_id(id: &str) -> Self {
+        let provider = id.split_once('/').map(|(provider, _)| provider.to_string());
+        Self { id: id.to_string(), provider, context_window: None, supports_tools: None }
+    }
+
+    /// `None` for entries without an id.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        if let Some(id) = value.as_str() {
+            return Some(Self::from_id(id));
+        }
+        let field = |pointers: &[&str]| pointers.iter().find_map(|pointer| value.pointer(pointer));
+        let id = field(&["/id", "/model", "/name"])?.as_str()?;
+        let mut info = Self::from_id(id);
+        if let Some(provider) = field(&["/provider", "/providerID"]).and_then(Value::as_str) {
+            info.provider = Some(provider.to_string());
+        }
+        info.context_window = field(&["/context_window", "/contextWindow", "/context", "/limit/context"])
+            .and_then(Value::as_u64)
+            .and_then(|window| u32::try_from(window).ok());
+        info.supports_tools = field(&["/supports_tools", "/supportsTools", "/tool_call", "/capabilities/tools"])
+            .and_then(Value::as_bool);
+        Some(info)
+    }
+}
+
+/// Models from `--list-models --json` output: an array, or an object with a
+/// `models` array. `None` when the output isn't JSON of either shape.
+pub fn parse_models_json(output: &str) -> Option<Vec<ModelInfo>> {
+    let value: Value = serde_json::from_str(output.trim()).ok()?;
+    let models = match &value {
+        Value::Array(models) => models,
+        Value::Object(map) => map.get("models")?.as_array()?,
+        _ => return None,
+    };
+    Some(models.iter().filter_map(ModelInfo::from_value).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_detailed_and_bare_models() {
+        let output = r#"{"models": [
+            {"id": "gpt-5.2-codex", "provider": "github-copilot", "contextWindow": 400000, "supportsTools": true},
+            {"model": "anthropic/claude-sonnet-4", "limit": {"context": 200000}},
+            "o3",
+            {"provider": "nameless"}
+        ]}"#;
+        let models = parse_models_json(output).unwrap();
+        assert_eq!(models.len(), 3);
+        assert_eq!(models[0].provider.as_deref(), Some("github-copilot"));
+        assert_eq!((models[0].context_window, models[0].supports_tools), (Some(400000), Some(true)));
+        assert_eq!(models[1].provider.as_deref(), Some("anthropic"));
+        assert_eq!((models[1].context_window, models[1].supports_tools), (Some(200000), None));
+        assert_eq!(models[2], ModelInfo::from_id("o3"));
+
+        assert_eq!(parse_models_json("gpt-5\no3\n"), None);
+    }
+}
@@ -0,0 +1,66 @@
+// Tagged Pi output lines, for the interleaved stdout/stderr view
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PiStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaggedLine {
+    pub stream: PiStream,
+    pub line: String,
+}
+
+/// Payload of `pi://line`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PiLine {
+    pub session_id: String,
+    pub stream: PiStream,
+    pub line: String,
+}
+
+/// Payload of `pi://state`. `awaiting_input` turns on when the run prints
+/// its completion marker and off again if more output follows.
+#[derive(Debug, Clone, Serialize)]
+pub struct PiState {
+    pub session_id: String,
+    pub awaiting_input: bool,
+}
+
+/// Payload of `pi://approval-request`, for a prompt matching the
+/// approval pattern. Answered with `pi_respond_approval`, or denied once
+/// `timeout_secs` pass.
+#[derive(Debug, Clone, Serialize)]
+pub struct PiApprovalRequest {
+    pub session_id: String,
+    pub id: u64,
+    pub prompt: String,
+    pub timeout_secs: u64,
+}
+
+/// Where a session's latest run stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiRunStatus {
+    /// Never run, or created and not started yet.
+    Idle,
+    Running,
+    Succeeded,
+    /// Exited non-zero or couldn't be started.
+    Failed,
+    Killed,
+    /// Stopped by the `no_output_timeout` watchdog.
+    NoOutputTimeout,
+}
+
+/// New stdout lines of one session for `pi_tail_all`. `next` is the index
+/// to pass back on the next poll.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PiTail {
+    pub lines: Vec<String>,
+    pub next: usize,
+}
@@ -0,0 +1,100 @@
+// Pending Pi runs waiting for a free concurrency slot
+// Slots drain the queue strictly front to back
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct QueuedRun {
+    pub session_id: String,
+    pub prompt: String,
+    pub workdir: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PiQueueStatus {
+    pub running: Vec<String>,
+    pub queued: Vec<String>,
+    pub max_concurrent: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct RunQueue {
+    pending: VecDeque<QueuedRun>,
+}
+
+impl RunQueue {
+    pub fn push_back(&mut self, run: QueuedRun) {
+        self.pending.push_back(run);
+    }
+
+    pub fn pop_front(&mut self) -> Option<QueuedRun> {
+        self.pending.pop_front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.pending.iter().any(|run| run.session_id == session_id)
+    }
+
+    pub fn remove(&mut self, session_id: &str) -> Option<QueuedRun> {
+        let index = self.pending.iter().position(|run| run.session_id == session_id)?;
+        self.pending.remove(index)
+    }
+
+    /// Empties the queue, returning the session ids that were waiting.
+    pub fn drain(&mut self) -> Vec<String> {
+        self.pending.drain(..).map(|run| run.session_id).collect()
+    }
+
+    /// Drops the queued runs `matches` picks, returning their session ids.
+    pub fn remove_where(&mut self, matches: impl Fn(&QueuedRun) -> bool) -> Vec<String> {
+        let (removed, kept) = self.pending.drain(..).partition(|run| matches(run));
+        self.pending = kept;
+        removed.into_iter().map(|run: QueuedRun| run.session_id).collect()
+    }
+
+    pub fn session_ids(&self) -> Vec<String> {
+        self.pending.iter().map(|run| run.session_id.clone()).collect()
+    }
+
+    /// Moves a queued session to `position` (clamped to the back). Returns
+    /// false when the session isn't queued.
+    pub fn reorder(&mut self, session_id: &str, position: usize) -> bool {
+        let Some(run) = self.remove(session_id) else {
+            return false;
+        };
+        let position = position.min(self.pending.len());
+        self.pending.insert(position, run);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: &str) -> QueuedRun {
+        QueuedRun {
+            session_id: id.to_string(),
+            prompt: String::new(),
+            workdir: ".".to_string(),
+        }
+    }
+
+    #[test]
+    fn reorders_queued_sessions() {
+        let mut queue = RunQueue::default();
+        for id in ["a", "b", "c"] {
+            queue.push_back(queued(id));
+        }
+        assert!(queue.reorder("c", 0));
+        assert_eq!(queue.session_ids(), vec!["c", "a", "b"]);
+        assert!(queue.reorder("c", 99));
+        assert_eq!(queue.session_ids(), vec!["a", "b", "c"]);
+        assert!(!queue.reorder("missing", 0));
+    }
+}
@@ -0,0 +1,77 @@
+// Final summary block of a Pi run
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PiRunSummary {
+    pub text: String,
+    pub files_touched: Vec<String>,
+}
+
+fn is_summary_heading(line: &str) -> bool {
+    let trimmed = line.trim();
+    let title = trimmed.trim_start_matches('#');
+    title.len() < trimmed.len() && title.trim().eq_ignore_ascii_case("summary")
+}
+
+/// Backticked tokens that look like file paths.
+fn file_paths(text: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for (index, token) in text.split('`').enumerate() {
+        // Odd segments are inside backticks
+        if index % 2 == 0 {
+            continue;
+        }
+        let token = token.trim();
+        let looks_like_path = !token.is_empty()
+            && !token.contains(char::is_whitespace)
+            && (token.contains('/') || token.rsplit_once('.').is_some_and(|(stem, ext)| {
+                !stem.is_empty() && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric())
+            }));
+        if looks_like_path && !paths.iter().any(|p| p == token) {
+            paths.push(token.to_string());
+        }
+    }
+    paths
+}
+
+/// Extracts the text after the last `## Summary` heading in the output.
+pub fn parse_summary(lines: &[String]) -> Option<PiRunSummary> {
+    let start = lines.iter().rposition(|line| is_summary_heading(line))?;
+    let text = lines[start + 1..].join("\n").trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    Some(PiRunSummary {
+        files_touched: file_paths(&text),
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_summary_section_and_files() {
+        let output = lines(
+            "Reading files...\n\
+             ## Summary\n\
+             - Fixed the parser in `src/parser.rs`\n\
+             - Updated `README.md` and ran `cargo test`\n",
+        );
+        let summary = parse_summary(&output).unwrap();
+        assert!(summary.text.starts_with("- Fixed the parser"));
+        assert_eq!(summary.files_touched, vec!["src/parser.rs", "README.md"]);
+    }
+
+    #[test]
+    fn returns_none_without_summary() {
+        assert_eq!(parse_summary(&lines("just logs\nmore logs")), None);
+        assert_eq!(parse_summary(&lines("## Summary\n\n")), None);
+    }
+}
@@ -1,7 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::task;
 use tauri::State;
 
@@ -21,6 +25,104 @@ pub(crate) struct CustomPromptEntry {
     pub(crate) scope: Option<String>,
 }
 
+/// `prompts_list` result. `warnings` explains prompts hidden by a same-named
+/// prompt from a higher-priority directory.
+#[derive(Serialize, Clone)]
+pub(crate) struct PromptListing {
+    pub(crate) prompts: Vec<CustomPromptEntry>,
+    pub(crate) warnings: Vec<String>,
+    /// The scan stopped early, on the timeout or because a newer
+    /// `prompts_list` call replaced it, so `prompts` may be incomplete.
+    #[serde(rename = "timedOut")]
+    pub(crate) timed_out: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct PromptValidation {
+    pub(crate) valid: bool,
+    pub(crate) description: Option<String>,
+    #[serde(rename = "argumentHint")]
+    pub(crate) argument_hint: Option<String>,
+    pub(crate) errors: Vec<String>,
+}
+
+/// A prompt file exactly as it is on disk, frontmatter included.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct RawPrompt {
+    pub(crate) name: String,
+    pub(crate) path: String,
+    pub(crate) scope: String,
+    pub(crate) content: String,
+}
+
+/// Portable copy of a set of prompts, for moving them between machines.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct PromptBundle {
+    pub(crate) version: u32,
+    pub(crate) prompts: Vec<PromptBundleEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct PromptBundleEntry {
+    pub(crate) name: String,
+    /// "workspace" or "global"; imports default to the workspace.
+    #[serde(default)]
+    pub(crate) scope: Option<String>,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    #[serde(default, rename = "argumentHint")]
+    pub(crate) argument_hint: Option<String>,
+    pub(crate) content: String,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct PromptImportResult {
+    pub(crate) name: String,
+    pub(crate) imported: bool,
+    pub(crate) error: Option<String>,
+}
+
+/// Where the global prompts live and how much is in there.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub(crate) struct PromptStorageInfo {
+    pub(crate) codex_home: Option<String>,
+    pub(crate) prompts_dir: Option<String>,
+    pub(crate) prompt_count: usize,
+    /// Size of every file under the prompts dir, prompts or not.
+    pub(crate) total_bytes: u64,
+    /// Entries skipped because they couldn't be read.
+    pub(crate) unreadable: usize,
+}
+
+const PROMPT_BUNDLE_VERSION: u32 = 1;
+// Long enough for any local directory; a slow network mount gets cut off
+const PROMPT_SCAN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Cancel flags of the `prompts_list` scans in flight by workspace id, set
+// when a newer call for the same workspace starts so the old one stops reading
+static ACTIVE_PROMPT_SCANS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+
+fn active_prompt_scans() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    ACTIVE_PROMPT_SCANS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Limits on a prompt scan: a cancel flag and an optional deadline.
+struct ScanBudget {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl ScanBudget {
+    fn unlimited() -> Self {
+        Self { cancelled: Arc::new(AtomicBool::new(false)), deadline: None }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
 fn resolve_codex_home_for_workspace(
     workspaces: &HashMap<String, WorkspaceEntry>,
     entry: &WorkspaceEntry,
@@ -178,6 +280,63 @@ fn parse_frontmatter(content: &str) -> (Option<String>, Option<String>, String)
     (description, argument_hint, body)
 }
 
+/// Runs `parse_frontmatter` and reports the cases it quietly tolerates:
+/// an unclosed block (whole file read as body), lines that aren't
+/// `key: value`, unknown keys, and a missing description.
+fn validate_prompt(content: &str) -> PromptValidation {
+    let (description, argument_hint, body) = parse_frontmatter(content);
+    let mut errors = Vec::new();
+
+    let mut lines = content.lines();
+    if lines.next().map(str::trim) == Some("---") {
+        let mut closed = false;
+        for (index, line) in lines.enumerate() {
+            let trimmed = line.trim();
+            if trimmed == "---" {
+                closed = true;
+                break;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let line_number = index + 2;
+            match trimmed.split_once(':') {
+                Some((key, _)) => match key.trim().to_ascii_lowercase().as_str() {
+                    "description" | "argument-hint" | "argument_hint" => {}
+                    other => errors.push(format!(
+                        "Line {}: unknown frontmatter key \"{}\" is ignored.",
+                        line_number, other
+                    )),
+                },
+                None => errors.push(format!(
+                    "Line {}: expected \"key: value\" in frontmatter.",
+                    line_number
+                )),
+            }
+        }
+        if !closed {
+            errors.push(
+                "Frontmatter is not closed with \"---\", so the whole file is used as the prompt."
+                    .to_string(),
+            );
+        }
+    }
+
+    if description.as_deref().is_none_or(|value| value.trim().is_empty()) {
+        errors.push("Missing description.".to_string());
+    }
+    if body.trim().is_empty() {
+        errors.push("Prompt body is empty.".to_string());
+    }
+
+    PromptValidation {
+        valid: errors.is_empty(),
+        description,
+        argument_hint,
+        errors,
+    }
+}
+
 fn build_prompt_contents(
     description: Option<String>,
     argument_hint: Option<String>,
@@ -225,14 +384,77 @@ fn sanitize_prompt_name(name: &str) -> Result<String, String> {
     Ok(trimmed.to_string())
 }
 
+/// Walks `dir` recursively, adding file counts and sizes to `info`. Entries
+/// that can't be read are counted and skipped.
+fn measure_prompts_dir(dir: &Path, info: &mut PromptStorageInfo) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return,
+        Err(_) => {
+            info.unreadable += 1;
+            return;
+        }
+    };
+    for entry in entries {
+        let Ok(entry) = entry else {
+            info.unreadable += 1;
+            continue;
+        };
+        let path = entry.path();
+        let Ok(metadata) = fs::metadata(&path) else {
+            info.unreadable += 1;
+            continue;
+        };
+        if metadata.is_dir() {
+            measure_prompts_dir(&path, info);
+            continue;
+        }
+        info.total_bytes += metadata.len();
+        let is_md = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("md"))
+            .unwrap_or(false);
+        if is_md {
+            info.prompt_count += 1;
+        }
+    }
+}
+
+fn prompt_storage_info(codex_home: Option<PathBuf>) -> PromptStorageInfo {
+    let mut info = PromptStorageInfo::default();
+    if let Some(home) = codex_home {
+        let dir = home.join("prompts");
+        measure_prompts_dir(&dir, &mut info);
+        info.codex_home = Some(home.to_string_lossy().to_string());
+        info.prompts_dir = Some(dir.to_string_lossy().to_string());
+    }
+    info
+}
+
 fn discover_prompts_in(dir: &Path, scope: Option<&str>) -> Vec<CustomPromptEntry> {
+    discover_prompts_within(dir, scope, &ScanBudget::unlimited()).0
+}
+
+/// Like `discover_prompts_in`, but stops once `budget` runs out. The flag is
+/// true when the scan stopped early.
+fn discover_prompts_within(
+    dir: &Path,
+    scope: Option<&str>,
+    budget: &ScanBudget,
+) -> (Vec<CustomPromptEntry>, bool) {
     let mut out: Vec<CustomPromptEntry> = Vec::new();
+    let mut stopped = false;
     let entries = match fs::read_dir(dir) {
         Ok(entries) => entries,
-        Err(_) => return out,
+        Err(_) => return (out, stopped),
     };
 
     for entry in entries.flatten() {
+        if budget.exhausted() {
+            stopped = true;
+            break;
+        }
         let path = entry.path();
         let is_file = fs::metadata(&path).map(|m| m.is_file()).unwrap_or(false);
         if !is_file {
@@ -269,14 +491,364 @@ fn discover_prompts_in(dir: &Path, scope: Option<&str>) -> Vec<CustomPromptEntry
     }
 
     out.sort_by(|a, b| a.name.cmp(&b.name));
+    (out, stopped)
+}
+
+fn bundle_entry(entry: CustomPromptEntry) -> PromptBundleEntry {
+    PromptBundleEntry {
+        name: entry.name,
+        scope: entry.scope,
+        description: entry.description,
+        argument_hint: entry.argument_hint,
+        content: entry.content,
+    }
+}
+
+// For names a prompt file is created under, where a leading dot would hide it
+fn sanitize_import_name(name: &str) -> Result<String, String> {
+    let name = sanitize_prompt_name(name)?;
+    if name.starts_with('.') {
+        return Err("Prompt name cannot start with a dot.".to_string());
+    }
+    Ok(name)
+}
+
+fn import_prompt(dir: &Path, prompt: &PromptBundleEntry, overwrite: bool) -> Result<(), String> {
+    let name = sanitize_import_name(&prompt.name)?;
+    let path = dir.join(format!("{name}.md"));
+    if path.parent() != Some(dir) {
+        return Err("Prompt path is not within allowed directories.".to_string());
+    }
+    if path.exists() && !overwrite {
+        return Err("Prompt already exists.".to_string());
+    }
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    let body = build_prompt_contents(
+        prompt.description.clone(),
+        prompt.argument_hint.clone(),
+        prompt.content.clone(),
+    );
+    fs::write(&path, body).map_err(|err| err.to_string())
+}
+
+/// Writes each prompt into the directory for its scope, reporting per prompt
+/// so one bad entry doesn't abort the rest.
+fn import_prompt_bundle(
+    bundle: &PromptBundle,
+    workspace_dir: &Path,
+    global_dir: Option<&Path>,
+    overwrite: bool,
+) -> Vec<PromptImportResult> {
+    bundle
+        .prompts
+        .iter()
+        .map(|prompt| {
+            let dir = match prompt.scope.as_deref().unwrap_or("workspace") {
+                "workspace" => Ok(workspace_dir),
+                "global" => global_dir.ok_or("Unable to resolve CODEX_HOME".to_string()),
+                _ => Err("Invalid scope.".to_string()),
+            };
+            let result = dir.and_then(|dir| import_prompt(dir, prompt, overwrite));
+            PromptImportResult {
+                name: prompt.name.clone(),
+                imported: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect()
+}
+
+fn normalize_quotes(input: &str) -> String {
+    input
+        .replace(['\u{201C}', '\u{201D}'], "\"")
+        .replace(['\u{2018}', '\u{2019}'], "'")
+}
+
+// Byte ranges of `$NAME` placeholders, skipping `$$NAME` escapes
+fn named_placeholders(content: &str) -> Vec<(usize, usize)> {
+    let bytes = content.as_bytes();
+    let is_name_byte = |b: u8| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_';
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        let escaped = i > 0 && bytes[i - 1] == b'$';
+        if bytes[i] == b'$' && bytes[i + 1].is_ascii_uppercase() && !escaped {
+            let mut end = i + 2;
+            while end < bytes.len() && is_name_byte(bytes[end]) {
+                end += 1;
+            }
+            out.push((i, end));
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
     out
 }
 
+fn prompt_argument_names(content: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = Vec::new();
+    for (start, end) in named_placeholders(content) {
+        let name = &content[start + 1..end];
+        if name != "ARGUMENTS" && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn expand_named_placeholders(content: &str, values: &HashMap<String, String>) -> String {
+    let mut output = String::new();
+    let mut last = 0;
+    for (start, end) in named_placeholders(content) {
+        output.push_str(&content[last..start]);
+        match values.get(&content[start + 1..end]) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&content[start..end]),
+        }
+        last = end;
+    }
+    output.push_str(&content[last..]);
+    output
+}
+
+fn expand_numeric_placeholders(content: &str, args: &[String]) -> String {
+    let mut output = String::new();
+    let mut rest = content;
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        if let Some(stripped) = after.strip_prefix('$') {
+            output.push_str("$$");
+            rest = stripped;
+        } else if let Some(index) = after
+            .chars()
+            .next()
+            .and_then(|ch| ch.to_digit(10))
+            .filter(|digit| *digit > 0)
+        {
+            if let Some(arg) = args.get(index as usize - 1) {
+                output.push_str(arg);
+            }
+            rest = &after[1..];
+        } else if let Some(stripped) = after.strip_prefix("ARGUMENTS") {
+            output.push_str(&args.join(" "));
+            rest = stripped;
+        } else {
+            output.push('$');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Fills in a prompt the way the composer expands `/prompts:<name> <args>`:
+/// `key=value` pairs when it has `$NAME` placeholders, otherwise positional
+/// arguments for `$1`..`$9` and `$ARGUMENTS`.
+pub(crate) fn render_prompt(content: &str, args: &str) -> Result<String, String> {
+    let tokens = shell_words::split(&normalize_quotes(args))
+        .map_err(|err| format!("Could not parse prompt arguments: {err}."))?;
+    let required = prompt_argument_names(content);
+    if required.is_empty() {
+        return Ok(expand_numeric_placeholders(content, &tokens));
+    }
+
+    let mut values = HashMap::new();
+    for token in tokens {
+        match token.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                values.insert(key.to_string(), value.to_string());
+            }
+            Some(_) => return Err(format!("Expected a name before '=' in '{token}'.")),
+            None => {
+                return Err(format!(
+                    "Expected key=value but found '{token}'. Wrap values in double quotes if they contain spaces."
+                ))
+            }
+        }
+    }
+    let missing: Vec<&str> = required
+        .into_iter()
+        .filter(|name| !values.contains_key(*name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required args: {}.", missing.join(", ")));
+    }
+    Ok(expand_named_placeholders(content, &values))
+}
+
+/// Merges prompt lists in priority order. The first prompt with a given name
+/// wins; later ones are dropped with a warning naming both files.
+fn merge_prompt_sources(sources: Vec<Vec<CustomPromptEntry>>) -> PromptListing {
+    let mut prompts: Vec<CustomPromptEntry> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for entry in sources.into_iter().flatten() {
+        if let Some(&index) = seen.get(&entry.name) {
+            warnings.push(format!(
+                "Prompt \"{}\" at {} is overridden by {}",
+                entry.name, entry.path, prompts[index].path
+            ));
+            continue;
+        }
+        seen.insert(entry.name.clone(), prompts.len());
+        prompts.push(entry);
+    }
+    PromptListing { prompts, warnings, timed_out: false }
+}
+
+fn list_prompts(workspace_dir: Option<PathBuf>, global_dir: Option<PathBuf>) -> PromptListing {
+    list_prompts_within(workspace_dir, global_dir, &ScanBudget::unlimited())
+}
+
+fn list_prompts_within(
+    workspace_dir: Option<PathBuf>,
+    global_dir: Option<PathBuf>,
+    budget: &ScanBudget,
+) -> PromptListing {
+    // Workspace prompts take precedence over global ones
+    let mut sources = Vec::new();
+    let mut timed_out = false;
+    for (dir, scope) in [(workspace_dir, "workspace"), (global_dir, "global")] {
+        let Some(dir) = dir else {
+            continue;
+        };
+        let _ = fs::create_dir_all(&dir);
+        let (prompts, stopped) = discover_prompts_within(&dir, Some(scope), budget);
+        sources.push(prompts);
+        timed_out |= stopped;
+    }
+    PromptListing { timed_out, ..merge_prompt_sources(sources) }
+}
+
+/// A budget for a new `prompts_list` scan of `workspace_id`, cancelling the
+/// one before it for that workspace only.
+fn start_list_scan(workspace_id: &str) -> ScanBudget {
+    let budget = ScanBudget {
+        cancelled: Arc::new(AtomicBool::new(false)),
+        deadline: Some(Instant::now() + PROMPT_SCAN_TIMEOUT),
+    };
+    let previous = active_prompt_scans()
+        .lock()
+        .unwrap()
+        .insert(workspace_id.to_string(), budget.cancelled.clone());
+    if let Some(previous) = previous {
+        previous.store(true, Ordering::Relaxed);
+    }
+    budget
+}
+
+/// Forgets a finished scan, unless a newer one for the workspace replaced it.
+fn finish_list_scan(workspace_id: &str, cancelled: &Arc<AtomicBool>) {
+    let mut scans = active_prompt_scans().lock().unwrap();
+    if scans.get(workspace_id).is_some_and(|active| Arc::ptr_eq(active, cancelled)) {
+        scans.remove(workspace_id);
+    }
+}
+
+/// Reads prompt `name` unparsed, from the workspace dir first as
+/// `prompts_list` does.
+fn read_raw_prompt(
+    workspace_dir: Option<&Path>,
+    global_dir: Option<&Path>,
+    name: &str,
+) -> Result<RawPrompt, String> {
+    let name = sanitize_prompt_name(name)?;
+    for (dir, scope) in [(workspace_dir, "workspace"), (global_dir, "global")] {
+        let Some(dir) = dir else {
+            continue;
+        };
+        let path = dir.join(format!("{name}.md"));
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                return Ok(RawPrompt {
+                    name,
+                    path: path.to_string_lossy().to_string(),
+                    scope: scope.to_string(),
+                    content,
+                })
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+    Err("Prompt not found.".to_string())
+}
+
+/// Copies prompt `source` to `new_name` next to it, byte for byte. Fails
+/// rather than overwrite an existing prompt.
+fn duplicate_prompt(
+    workspace_dir: Option<&Path>,
+    global_dir: Option<&Path>,
+    source: &str,
+    new_name: &str,
+) -> Result<CustomPromptEntry, String> {
+    let new_name = sanitize_import_name(new_name)?;
+    let raw = read_raw_prompt(workspace_dir, global_dir, source)?;
+    let source_path = PathBuf::from(&raw.path);
+    let dir = source_path
+        .parent()
+        .ok_or("Unable to resolve prompt directory.".to_string())?;
+    let path = dir.join(format!("{new_name}.md"));
+    // `create_new` so a prompt written meanwhile isn't clobbered either
+    let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err("Prompt with that name already exists.".to_string())
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+    file.write_all(raw.content.as_bytes())
+        .map_err(|err| err.to_string())?;
+    let (description, argument_hint, body) = parse_frontmatter(&raw.content);
+    Ok(CustomPromptEntry {
+        name: new_name,
+        path: path.to_string_lossy().to_string(),
+        description,
+        argument_hint,
+        content: body,
+        scope: Some(raw.scope),
+    })
+}
+
+/// Finds prompt `name` as `prompts_list` would for the workspace containing
+/// `workdir`, or among the global prompts when no workspace does.
+pub(crate) async fn find_prompt_for_workdir(
+    state: &State<'_, AppState>,
+    workdir: &str,
+    name: &str,
+) -> Result<CustomPromptEntry, String> {
+    let (workspace_dir, global_dir) = {
+        let workspaces = state.workspaces.lock().await;
+        let entry = workspaces
+            .values()
+            .filter(|entry| Path::new(workdir).starts_with(&entry.path))
+            .max_by_key(|entry| entry.path.len())
+            .cloned();
+        match entry {
+            Some(entry) => (
+                workspace_prompts_dir(state, &entry).ok(),
+                default_prompts_dir_for_workspace(&workspaces, &entry),
+            ),
+            None => (None, resolve_default_codex_home().map(|home| home.join("prompts"))),
+        }
+    };
+    let listing = task::spawn_blocking(move || list_prompts(workspace_dir, global_dir))
+        .await
+        .map_err(|_| "prompt discovery failed".to_string())?;
+    listing
+        .prompts
+        .into_iter()
+        .find(|prompt| prompt.name == name)
+        .ok_or_else(|| format!("Prompt \"{name}\" not found."))
+}
+
 #[tauri::command]
 pub(crate) async fn prompts_list(
     state: State<'_, AppState>,
     workspace_id: String,
-) -> Result<Vec<CustomPromptEntry>, String> {
+) -> Result<PromptListing, String> {
     let (workspace_dir, global_dir) = {
         let workspaces = state.workspaces.lock().await;
         let entry = workspaces.get(&workspace_id).cloned();
@@ -289,22 +861,74 @@ pub(crate) async fn prompts_list(
         (workspace_dir, global_dir)
     };
 
+    let budget = start_list_scan(&workspace_id);
+    let cancelled = budget.cancelled.clone();
+    let listing = task::spawn_blocking(move || list_prompts_within(workspace_dir, global_dir, &budget)).await;
+    finish_list_scan(&workspace_id, &cancelled);
+    listing.map_err(|_| "prompt discovery failed".to_string())
+}
+
+/// Every prompt from both directories, including ones a same-named workspace
+/// prompt hides from `prompts_list`.
+#[tauri::command]
+pub(crate) async fn prompts_export_bundle(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<PromptBundle, String> {
+    let (workspace_dir, global_dir) = {
+        let workspaces = state.workspaces.lock().await;
+        let entry = require_workspace_entry(&workspaces, &workspace_id)?;
+        (
+            workspace_prompts_dir(&state, &entry)?,
+            default_prompts_dir_for_workspace(&workspaces, &entry),
+        )
+    };
+
     task::spawn_blocking(move || {
-        let mut out = Vec::new();
-        if let Some(dir) = workspace_dir {
-            let _ = fs::create_dir_all(&dir);
-            out.extend(discover_prompts_in(&dir, Some("workspace")));
-        }
+        let mut prompts = discover_prompts_in(&workspace_dir, Some("workspace"));
         if let Some(dir) = global_dir {
-            let _ = fs::create_dir_all(&dir);
-            out.extend(discover_prompts_in(&dir, Some("global")));
+            prompts.extend(discover_prompts_in(&dir, Some("global")));
+        }
+        PromptBundle {
+            version: PROMPT_BUNDLE_VERSION,
+            prompts: prompts.into_iter().map(bundle_entry).collect(),
         }
-        out
     })
     .await
     .map_err(|_| "prompt discovery failed".to_string())
 }
 
+#[tauri::command]
+pub(crate) async fn prompts_import_bundle(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    bundle: PromptBundle,
+    overwrite: bool,
+) -> Result<Vec<PromptImportResult>, String> {
+    if bundle.version > PROMPT_BUNDLE_VERSION {
+        return Err(format!("Unsupported prompt bundle version {}.", bundle.version));
+    }
+    let (workspace_dir, global_dir) = {
+        let workspaces = state.workspaces.lock().await;
+        let entry = require_workspace_entry(&workspaces, &workspace_id)?;
+        (
+            workspace_prompts_dir(&state, &entry)?,
+            default_prompts_dir_for_workspace(&workspaces, &entry),
+        )
+    };
+
+    task::spawn_blocking(move || {
+        import_prompt_bundle(&bundle, &workspace_dir, global_dir.as_deref(), overwrite)
+    })
+    .await
+    .map_err(|_| "prompt import failed".to_string())
+}
+
+#[tauri::command]
+pub(crate) async fn prompts_validate(content: String) -> Result<PromptValidation, String> {
+    Ok(validate_prompt(&content))
+}
+
 #[tauri::command]
 pub(crate) async fn prompts_workspace_dir(
     state: State<'_, AppState>,
@@ -332,6 +956,13 @@ pub(crate) async fn prompts_global_dir(
     Ok(dir.to_string_lossy().to_string())
 }
 
+#[tauri::command]
+pub(crate) async fn prompts_storage_info() -> Result<PromptStorageInfo, String> {
+    task::spawn_blocking(|| prompt_storage_info(resolve_default_codex_home()))
+        .await
+        .map_err(|_| "prompt storage scan failed".to_string())
+}
+
 #[tauri::command]
 pub(crate) async fn prompts_create(
     state: State<'_, AppState>,
@@ -431,6 +1062,83 @@ pub(crate) async fn prompts_update(
     })
 }
 
+/// The whole prompt file, for editors that save it back with
+/// `prompts_update_raw`.
+#[tauri::command]
+pub(crate) async fn prompts_get_raw(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    name: String,
+) -> Result<RawPrompt, String> {
+    let (workspace_dir, global_dir) = {
+        let workspaces = state.workspaces.lock().await;
+        let entry = require_workspace_entry(&workspaces, &workspace_id)?;
+        (
+            workspace_prompts_dir(&state, &entry).ok(),
+            default_prompts_dir_for_workspace(&workspaces, &entry),
+        )
+    };
+    read_raw_prompt(workspace_dir.as_deref(), global_dir.as_deref(), &name)
+}
+
+/// Copies prompt `source_name` under `new_name` in the same scope, as a
+/// starting point for a variant.
+#[tauri::command]
+pub(crate) async fn prompts_duplicate(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    source_name: String,
+    new_name: String,
+) -> Result<CustomPromptEntry, String> {
+    let (workspace_dir, global_dir) = {
+        let workspaces = state.workspaces.lock().await;
+        let entry = require_workspace_entry(&workspaces, &workspace_id)?;
+        (
+            workspace_prompts_dir(&state, &entry).ok(),
+            default_prompts_dir_for_workspace(&workspaces, &entry),
+        )
+    };
+    duplicate_prompt(workspace_dir.as_deref(), global_dir.as_deref(), &source_name, &new_name)
+}
+
+/// Overwrites the prompt at `path` with `content` byte for byte, keeping
+/// whatever frontmatter and formatting the editor produced.
+#[tauri::command]
+pub(crate) async fn prompts_update_raw(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    path: String,
+    content: String,
+) -> Result<CustomPromptEntry, String> {
+    let target_path = PathBuf::from(&path);
+    if !target_path.exists() {
+        return Err("Prompt not found.".to_string());
+    }
+    let workspace_dir = {
+        let workspaces = state.workspaces.lock().await;
+        let entry = require_workspace_entry(&workspaces, &workspace_id)?;
+        let roots = prompt_roots_for_workspace(&state, &workspaces, &entry)?;
+        ensure_path_within_roots(&target_path, &roots)?;
+        workspace_prompts_dir(&state, &entry)?
+    };
+    fs::write(&target_path, &content).map_err(|err| err.to_string())?;
+    let name = target_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .ok_or("Invalid prompt path.".to_string())?
+        .to_string();
+    let scope = if target_path.starts_with(&workspace_dir) { "workspace" } else { "global" };
+    let (description, argument_hint, body) = parse_frontmatter(&content);
+    Ok(CustomPromptEntry {
+        name,
+        path,
+        description,
+        argument_hint,
+        content: body,
+        scope: Some(scope.to_string()),
+    })
+}
+
 #[tauri::command]
 pub(crate) async fn prompts_delete(
     state: State<'_, AppState>,
@@ -508,3 +1216,194 @@ pub(crate) async fn prompts_move(
         scope: Some(scope),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, path: &str, scope: &str) -> CustomPromptEntry {
+        CustomPromptEntry {
+            name: name.to_string(),
+            path: path.to_string(),
+            description: None,
+            argument_hint: None,
+            content: String::new(),
+            scope: Some(scope.to_string()),
+        }
+    }
+
+    #[test]
+    fn validation_reports_tolerated_frontmatter_issues() {
+        let result = validate_prompt("---\ndescription: Review\nargument-hint: [file]\n---\nCheck $1\n");
+        assert!(result.valid, "{:?}", result.errors);
+        assert_eq!(result.argument_hint.as_deref(), Some("[file]"));
+
+        let result = validate_prompt("---\ndescription: Review\nnot a pair\nmodel: x\nCheck $1\n");
+        assert!(!result.valid);
+        assert_eq!(result.description, None);
+        assert!(result.errors.iter().any(|e| e.starts_with("Line 3:")));
+        assert!(result.errors.iter().any(|e| e.contains("\"model\"")));
+        assert!(result.errors.iter().any(|e| e.contains("not closed")));
+        assert!(result.errors.iter().any(|e| e == "Missing description."));
+    }
+
+    #[test]
+    fn workspace_prompts_override_global_with_warning() {
+        let listing = merge_prompt_sources(vec![
+            vec![entry("review", "/ws/review.md", "workspace")],
+            vec![
+                entry("review", "/global/review.md", "global"),
+                entry("triage", "/global/triage.md", "global"),
+            ],
+        ]);
+        let names: Vec<&str> = listing.prompts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["review", "triage"]);
+        assert_eq!(listing.prompts[0].path, "/ws/review.md");
+        assert_eq!(listing.warnings.len(), 1);
+        assert!(listing.warnings[0].contains("/global/review.md"));
+        assert!(listing.warnings[0].contains("/ws/review.md"));
+    }
+
+    #[test]
+    fn bundle_import_reports_each_prompt() {
+        let dir = std::env::temp_dir().join(format!("prompt-bundle-{}", uuid::Uuid::new_v4()));
+        let prompt = |name: &str, scope: Option<&str>| PromptBundleEntry {
+            name: name.to_string(),
+            scope: scope.map(str::to_string),
+            description: Some("Review".to_string()),
+            argument_hint: None,
+            content: "Check $1\n".to_string(),
+        };
+        let bundle = PromptBundle {
+            version: PROMPT_BUNDLE_VERSION,
+            prompts: vec![
+                prompt("review", None),
+                prompt("../escape", None),
+                prompt("..", None),
+                prompt("triage", Some("global")),
+            ],
+        };
+
+        let results = import_prompt_bundle(&bundle, &dir, None, false);
+        let imported: Vec<bool> = results.iter().map(|r| r.imported).collect();
+        assert_eq!(imported, vec![true, false, false, false]);
+        assert!(results[3].error.as_deref().unwrap().contains("CODEX_HOME"));
+        let exported = discover_prompts_in(&dir, Some("workspace"));
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].description.as_deref(), Some("Review"));
+        assert_eq!(exported[0].content, "Check $1\n");
+
+        let results = import_prompt_bundle(&bundle, &dir, None, false);
+        assert_eq!(results[0].error.as_deref(), Some("Prompt already exists."));
+        assert!(import_prompt_bundle(&bundle, &dir, None, true)[0].imported);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exhausted_scan_returns_partial_listing() {
+        let dir = std::env::temp_dir().join(format!("prompt-scan-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(format!("{name}.md")), "Body").unwrap();
+        }
+
+        let full = list_prompts(Some(dir.clone()), None);
+        assert_eq!((full.prompts.len(), full.timed_out), (3, false));
+
+        // A deadline already in the past stands in for a directory that's
+        // too slow to finish in time
+        let late = ScanBudget { deadline: Some(Instant::now()), ..ScanBudget::unlimited() };
+        let partial = list_prompts_within(Some(dir.clone()), None, &late);
+        assert!(partial.timed_out);
+        assert!(partial.prompts.is_empty());
+
+        let (ws_a, ws_b) = (uuid::Uuid::new_v4().to_string(), uuid::Uuid::new_v4().to_string());
+        let first = start_list_scan(&ws_a);
+        let other = start_list_scan(&ws_b);
+        let second = start_list_scan(&ws_a);
+        assert!(first.exhausted());
+        assert!(!other.exhausted());
+        assert!(!second.exhausted());
+
+        // Only the scan still registered is cleared when it finishes
+        finish_list_scan(&ws_a, &first.cancelled);
+        assert!(active_prompt_scans().lock().unwrap().contains_key(&ws_a));
+        finish_list_scan(&ws_a, &second.cancelled);
+        finish_list_scan(&ws_b, &other.cancelled);
+        let scans = active_prompt_scans().lock().unwrap();
+        assert!(!scans.contains_key(&ws_a) && !scans.contains_key(&ws_b));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn raw_prompt_keeps_frontmatter_and_prefers_workspace() {
+        let root = std::env::temp_dir().join(format!("prompt-raw-{}", uuid::Uuid::new_v4()));
+        let (workspace, global) = (root.join("ws"), root.join("global"));
+        fs::create_dir_all(&workspace).unwrap();
+        fs::create_dir_all(&global).unwrap();
+        let raw = "---\ndescription: \"Review\"\n---\n\nCheck $1\n";
+        fs::write(global.join("review.md"), raw).unwrap();
+
+        let found = read_raw_prompt(Some(&workspace), Some(&global), "review").unwrap();
+        assert_eq!((found.scope.as_str(), found.content.as_str()), ("global", raw));
+
+        fs::write(workspace.join("review.md"), "Local\n").unwrap();
+        let found = read_raw_prompt(Some(&workspace), Some(&global), "review").unwrap();
+        assert_eq!((found.scope.as_str(), found.content.as_str()), ("workspace", "Local\n"));
+        assert_eq!(read_raw_prompt(Some(&workspace), None, "gone").unwrap_err(), "Prompt not found.");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn duplicate_copies_prompt_exactly() {
+        let root = std::env::temp_dir().join(format!("prompt-dup-{}", uuid::Uuid::new_v4()));
+        let (workspace, global) = (root.join("ws"), root.join("global"));
+        fs::create_dir_all(&global).unwrap();
+        let raw = "---\ndescription: \"Review\"\nargument-hint: [file]\n---\n\nCheck $1\n";
+        fs::write(global.join("review.md"), raw).unwrap();
+
+        let copy = duplicate_prompt(Some(&workspace), Some(&global), "review", "review-strict").unwrap();
+        assert_eq!(copy.path, global.join("review-strict.md").to_string_lossy());
+        assert_eq!(copy.scope.as_deref(), Some("global"));
+        assert_eq!(copy.description.as_deref(), Some("Review"));
+        assert_eq!(fs::read_to_string(global.join("review-strict.md")).unwrap(), raw);
+
+        let again = duplicate_prompt(Some(&workspace), Some(&global), "review", "review-strict");
+        assert_eq!(again.err().as_deref(), Some("Prompt with that name already exists."));
+        let missing = duplicate_prompt(None, Some(&global), "gone", "x");
+        assert_eq!(missing.err().as_deref(), Some("Prompt not found."));
+        let hidden = duplicate_prompt(Some(&workspace), Some(&global), "review", ".review");
+        assert_eq!(hidden.err().as_deref(), Some("Prompt name cannot start with a dot."));
+        assert!(!global.join(".review.md").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn storage_info_sums_prompt_dir() {
+        let home = std::env::temp_dir().join(format!("prompt-storage-{}", uuid::Uuid::new_v4()));
+        let empty = prompt_storage_info(Some(home.clone()));
+        assert_eq!(empty.prompt_count, 0);
+        assert!(empty.prompts_dir.unwrap().ends_with("prompts"));
+
+        let dir = home.join("prompts");
+        fs::create_dir_all(dir.join("archive")).unwrap();
+        fs::write(dir.join("review.md"), "12345").unwrap();
+        fs::write(dir.join("archive").join("old.MD"), "123").unwrap();
+        fs::write(dir.join("notes.txt"), "12").unwrap();
+        let info = prompt_storage_info(Some(home.clone()));
+        assert_eq!((info.prompt_count, info.total_bytes, info.unreadable), (2, 10, 0));
+        assert_eq!(prompt_storage_info(None), PromptStorageInfo::default());
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn renders_named_and_positional_placeholders() {
+        let rendered = render_prompt("Review $FILE for $$FILE and $FOCUS", "FILE=src/main.rs FOCUS=\u{201C}error handling\u{201D}").unwrap();
+        assert_eq!(rendered, "Review src/main.rs for $$FILE and error handling");
+        assert_eq!(render_prompt("Review $FILE", "").unwrap_err(), "Missing required args: FILE.");
+        assert!(render_prompt("Review $FILE", "main.rs").unwrap_err().starts_with("Expected key=value"));
+
+        let rendered = render_prompt("Fix $1 in $2 ($ARGUMENTS) for $5 $", "bug 'src/a b.rs'").unwrap();
+        assert_eq!(rendered, "Fix bug in src/a b.rs (bug src/a b.rs) for  $");
+    }
+}